@@ -1,6 +1,23 @@
-use renderer::Renderer;
+use std::path::Path;
+
+use renderer::{Renderer, config::RendererConfig};
 
 fn main() {
-    let renderer = Renderer::new(true);
-    renderer.await_close_request();
+    let config_path = std::env::args().nth(1).unwrap_or(String::from("renderer.toml"));
+    let config = if Path::new(&config_path).exists(){
+        match RendererConfig::load(Path::new(&config_path)){
+            Ok(config)=>{config}
+            Err(error)=>{println!("{}", error);return}
+        }
+    }
+    else{
+        RendererConfig::builder().validation(true).build().unwrap()
+    };
+    let renderer = match Renderer::new(config){
+        Ok(renderer)=>{renderer}
+        Err(error)=>{println!("{}", error);return}
+    };
+    if let Err(error) = renderer.await_close_request(){
+        println!("Renderer stopped: {}", error);
+    }
 }
@@ -0,0 +1,91 @@
+use std::{ffi::CString, fmt::{Display, Formatter}};
+#[cfg(debug_assertions)]
+use std::backtrace::{Backtrace, BacktraceStatus};
+
+use ash::{extensions::ext::DebugUtils, vk::{Handle, DebugUtilsObjectNameInfoEXT, StructureType}};
+
+use crate::error::RendererResult;
+
+use super::{Allocator, MemoryRegionPointer, memory_backend::MemoryBackend, memory_regions::MemoryRegion};
+
+pub struct AllocationLabel{
+    pub name : String,
+    #[cfg(debug_assertions)]
+    pub backtrace : Backtrace,
+}
+impl AllocationLabel{
+    pub fn new(name : &str) -> Self{
+        return Self{
+            name : String::from(name),
+            #[cfg(debug_assertions)]
+            backtrace : Backtrace::capture(),
+        };
+    }
+    pub fn get_backtrace(&self) -> Option<String>{
+        #[cfg(debug_assertions)]
+        if self.backtrace.status() == BacktraceStatus::Captured{return Some(self.backtrace.to_string())}
+        return None;
+    }
+}
+#[derive(Clone, Debug)]
+pub struct LiveAllocation{
+    pub memory_type : u32,
+    pub region : MemoryRegion,
+    pub name : String,
+    pub backtrace : Option<String>,
+}
+impl Display for LiveAllocation{
+    fn fmt(&self, f : &mut Formatter<'_>) -> std::fmt::Result{
+        let name = if self.name.is_empty(){"unnamed"}else{&self.name};
+        write!(f, "Leaked allocation \"{}\" of {} bytes at offset {} in memory type {}", name, self.region.size, self.region.offset, self.memory_type)?;
+        if let Some(backtrace) = &self.backtrace{write!(f, ", created at:\n{}", backtrace)?}
+        return Ok(());
+    }
+}
+impl<B : MemoryBackend> Allocator<B>{
+    pub fn set_allocation_name(&mut self, allocation : &MemoryRegionPointer, name : &str){
+        if let Some(block) = self.blocks[allocation.block].as_mut(){block.set_label_name(allocation.region, name)}
+    }
+    pub fn get_allocation_name(&self, allocation : &MemoryRegionPointer) -> Option<&str>{
+        return self.blocks[allocation.block].as_ref()?.get_label(allocation.region).map(|label|label.name.as_str());
+    }
+    pub fn get_live_allocations(&self) -> Vec<LiveAllocation>{
+        let mut live_allocations = vec!();
+        for block in self.blocks.iter().flatten(){
+            for (i, region) in block.regions.iter().enumerate(){
+                let region = match region{
+                    Some(region)=>{*region}
+                    None=>{continue}
+                };
+                let label = block.get_label(i);
+                live_allocations.push(LiveAllocation{
+                    memory_type : block.get_memory_type(),
+                    region,
+                    name : label.map_or(String::new(), |label|label.name.clone()),
+                    backtrace : label.and_then(|label|label.get_backtrace()),
+                });
+            }
+        }
+        return live_allocations;
+    }
+}
+impl Allocator{
+    pub fn set_debug_utils(&mut self, debug_utils : Option<DebugUtils>){
+        self.debug_utils = debug_utils;
+    }
+    pub unsafe fn set_object_name<H : Handle>(&self, handle : H, name : &str) -> RendererResult<()>{
+        let debug_utils = match &self.debug_utils{
+            Some(debug_utils)=>{debug_utils}
+            None=>{return Ok(())}
+        };
+        let name = CString::new(name).unwrap_or_default();
+        let name_info = DebugUtilsObjectNameInfoEXT{
+            s_type : StructureType::DEBUG_UTILS_OBJECT_NAME_INFO_EXT,
+            p_next : std::ptr::null(),
+            object_type : H::TYPE,
+            object_handle : handle.as_raw(),
+            p_object_name : name.as_ptr(),
+        };
+        return Ok(debug_utils.debug_utils_set_object_name(self.device.handle(), &name_info)?);
+    }
+}
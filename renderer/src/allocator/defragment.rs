@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+
+use ash::vk::{Buffer, DeviceMemory, BufferCreateInfo, BufferCreateFlags, BufferUsageFlags, BufferCopy, SharingMode, StructureType, CommandPool, Queue};
+
+use crate::{error::RendererResult, functions};
+
+use super::{Allocator, BufferAndAllocation, MemoryRegionPointer, memory_regions::{FreeList, MemoryRegion, ResourceKind}};
+
+#[derive(Clone, Debug)]
+pub struct DefragmentationBlock{
+    pub memory_type : u32,
+    pub size : u64,
+    pub used : Vec<(MemoryRegion, ResourceKind)>,
+}
+#[derive(Clone, Copy, Debug)]
+pub struct DefragmentationAllocation{
+    pub block : usize,
+    pub region : MemoryRegion,
+    pub alignment : u64,
+    pub kind : ResourceKind,
+}
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DefragmentationMove{
+    pub allocation : usize,
+    pub block : usize,
+    pub offset : u64,
+}
+pub fn plan_defragmentation(blocks : &[DefragmentationBlock], allocations : &[DefragmentationAllocation], granularity : u64) -> Vec<DefragmentationMove>{
+    let mut free_lists = blocks.iter().map(|block|{
+        let mut free_list = FreeList::new(block.size);
+        for &(region, kind) in block.used.iter(){free_list.reserve(region, kind);}
+        free_list
+    }).collect::<Vec<_>>();
+    let mut order = (0..blocks.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&block|std::cmp::Reverse(blocks[block].used.iter().map(|(region, _)|region.size).sum::<u64>()));
+    let mut rank = vec!(0; blocks.len());
+    for (i, &block) in order.iter().enumerate(){rank[block] = i}
+    let mut candidates = (0..allocations.len()).collect::<Vec<_>>();
+    candidates.sort_by_key(|&allocation|(std::cmp::Reverse(rank[allocations[allocation].block]), std::cmp::Reverse(allocations[allocation].region.size)));
+    let mut moves = vec!();
+    for index in candidates{
+        let allocation = &allocations[index];
+        for &block in order[..=rank[allocation.block]].iter(){
+            if blocks[block].memory_type != blocks[allocation.block].memory_type{continue}
+            let region = match free_lists[block].allocate(allocation.region.size, allocation.alignment, allocation.kind, granularity){
+                Some(region)=>{region}
+                None=>{continue}
+            };
+            if block == allocation.block && region.offset >= allocation.region.offset{
+                free_lists[block].free(region);
+                break;
+            }
+            moves.push(DefragmentationMove{allocation:index,block,offset:region.offset});
+            break;
+        }
+    }
+    return moves;
+}
+impl Allocator{
+    pub unsafe fn defragment(&mut self, buffers : &mut [&mut BufferAndAllocation], command_pool : CommandPool, queue : Queue) -> RendererResult<usize>{
+        let mut block_indices = vec!();
+        let mut aliases = vec!();
+        for (i, block) in self.blocks.iter().enumerate(){
+            let block = match block{
+                Some(block) if !block.is_dedicated() && block.get_mapped_ptr().is_null()=>{block}
+                _=>{continue}
+            };
+            match self.create_alias_buffer(block.memory, block.get_size(), block.get_memory_type()){
+                Ok(Some(alias))=>{
+                    block_indices.push(i);
+                    aliases.push(alias);
+                }
+                Ok(None)=>{}
+                Err(error)=>{
+                    for &alias in aliases.iter(){self.device.destroy_buffer(alias, None)}
+                    return Err(error);
+                }
+            }
+        }
+        let result = self.move_buffers(buffers, command_pool, queue, &block_indices, &aliases);
+        for &alias in aliases.iter(){self.device.destroy_buffer(alias, None)}
+        return result;
+    }
+    unsafe fn move_buffers(&mut self, buffers : &mut [&mut BufferAndAllocation], command_pool : CommandPool, queue : Queue, block_indices : &[usize], aliases : &[Buffer]) -> RendererResult<usize>{
+        let planned_blocks = block_indices.iter().map(|&block|{
+            let block = self.blocks[block].as_ref().unwrap();
+            DefragmentationBlock{memory_type:block.get_memory_type(),size:block.get_size(),used:block.get_used_ranges()}
+        }).collect::<Vec<_>>();
+        let mut movable = vec!();
+        let mut allocations = vec!();
+        for (i, buffer) in buffers.iter().enumerate(){
+            let block = match block_indices.iter().position(|&block|block == buffer.allocation.block){
+                Some(block)=>{block}
+                None=>{continue}
+            };
+            let region = self.blocks[buffer.allocation.block].as_ref().unwrap().regions[buffer.allocation.region].unwrap();
+            let (requirements, _) = self.get_buffer_requirements(buffer.buffer);
+            movable.push(i);
+            allocations.push(DefragmentationAllocation{block,region,alignment:requirements.alignment,kind:ResourceKind::Linear});
+        }
+        let moves = plan_defragmentation(&planned_blocks, &allocations, self.buffer_image_granularity);
+        let mut copies = BTreeMap::<(usize, usize), Vec<BufferCopy>>::new();
+        for planned_move in moves.iter(){
+            let allocation = &allocations[planned_move.allocation];
+            copies.entry((allocation.block, planned_move.block)).or_default().push(BufferCopy{
+                src_offset : allocation.region.offset,
+                dst_offset : planned_move.offset,
+                size : allocation.region.size,
+            });
+        }
+        for (&(src, dst), regions) in copies.iter(){
+            functions::buffer::copy_buffer_regions(&self.device, aliases[src], aliases[dst], command_pool, queue, regions)?;
+        }
+        let mut sources = vec!();
+        for planned_move in moves.iter(){
+            let allocation = &allocations[planned_move.allocation];
+            let buffer = &mut buffers[movable[planned_move.allocation]];
+            let block = block_indices[planned_move.block];
+            let buffer_create_info = BufferCreateInfo{
+                s_type : StructureType::BUFFER_CREATE_INFO,
+                p_next : std::ptr::null(),
+                flags : BufferCreateFlags::empty(),
+                p_queue_family_indices : std::ptr::null(),
+                queue_family_index_count : 0,
+                sharing_mode : SharingMode::EXCLUSIVE,
+                size : buffer.size,
+                usage : buffer.usage,
+            };
+            let moved_buffer = self.device.create_buffer(&buffer_create_info, None)?;
+            if let Err(error) = self.device.bind_buffer_memory(moved_buffer, self.blocks[block].as_ref().unwrap().memory, planned_move.offset){
+                self.device.destroy_buffer(moved_buffer, None);
+                return Err(error.into());
+            }
+            let region = self.blocks[block].as_mut().unwrap().reserve_region(MemoryRegion{offset:planned_move.offset,size:allocation.region.size}, allocation.kind).unwrap();
+            let label = self.blocks[buffer.allocation.block].as_mut().unwrap().take_label(buffer.allocation.region);
+            if let Some(label) = &label{self.set_object_name(moved_buffer, &label.name).ok();}
+            self.blocks[block].as_mut().unwrap().set_label(region, label);
+            self.blocks[buffer.allocation.block].as_mut().unwrap().free_region(buffer.allocation.region);
+            sources.push(buffer.allocation.block);
+            self.device.destroy_buffer(buffer.buffer, None);
+            buffer.buffer = moved_buffer;
+            buffer.allocation = MemoryRegionPointer{block,region};
+        }
+        for block in sources{
+            if self.blocks[block].as_ref().map_or(false, |block|block.is_empty()){self.release_block(block)}
+        }
+        return Ok(moves.len());
+    }
+    unsafe fn create_alias_buffer(&self, memory : DeviceMemory, size : u64, memory_type : u32) -> RendererResult<Option<Buffer>>{
+        let buffer_create_info = BufferCreateInfo{
+            s_type : StructureType::BUFFER_CREATE_INFO,
+            p_next : std::ptr::null(),
+            flags : BufferCreateFlags::empty(),
+            p_queue_family_indices : std::ptr::null(),
+            queue_family_index_count : 0,
+            sharing_mode : SharingMode::EXCLUSIVE,
+            size,
+            usage : BufferUsageFlags::TRANSFER_SRC | BufferUsageFlags::TRANSFER_DST,
+        };
+        let buffer = self.device.create_buffer(&buffer_create_info, None)?;
+        let requirements = self.device.get_buffer_memory_requirements(buffer);
+        if requirements.size > size || requirements.memory_type_bits & (1 << memory_type) == 0{
+            self.device.destroy_buffer(buffer, None);
+            return Ok(None);
+        }
+        if let Err(error) = self.device.bind_buffer_memory(buffer, memory, 0){
+            self.device.destroy_buffer(buffer, None);
+            return Err(error.into());
+        }
+        return Ok(Some(buffer));
+    }
+}
@@ -0,0 +1,122 @@
+use ash::vk::{Buffer, BufferCreateInfo, BufferCreateFlags, BufferUsageFlags, SharingMode, StructureType, MemoryPropertyFlags};
+
+use crate::error::{RendererError, RendererResult};
+
+use super::{Allocator, BufferAndAllocation, memory_regions::align_up};
+
+pub struct LinearAllocator{
+    size : u64,
+    offset : u64,
+}
+impl LinearAllocator{
+    pub fn new(size : u64) -> Self{
+        return Self{size,offset:0};
+    }
+    pub fn allocate(&mut self, size : u64, alignment : u64) -> Option<u64>{
+        if size == 0{return None}
+        let offset = align_up(self.offset, alignment.max(1));
+        if offset + size > self.size{return None}
+        self.offset = offset + size;
+        return Some(offset);
+    }
+    pub fn reset(&mut self){
+        self.offset = 0;
+    }
+    pub fn get_used_size(&self) -> u64{
+        return self.offset;
+    }
+    pub fn get_size(&self) -> u64{
+        return self.size;
+    }
+}
+pub struct TransientAllocation{
+    pub buffer : Buffer,
+    pub offset : u64,
+    pub size : u64,
+    mapped : *mut u8,
+}
+impl TransientAllocation{
+    pub unsafe fn write<T>(&self, data : &[T]){
+        let size = std::mem::size_of_val(data).min(self.size as usize);
+        self.mapped.copy_from_nonoverlapping(data.as_ptr() as *const u8, size);
+    }
+}
+struct FrameArena{
+    buffer : BufferAndAllocation,
+    linear : LinearAllocator,
+    mapped : *mut u8,
+    retired : Vec<BufferAndAllocation>,
+}
+pub struct FrameAllocator{
+    arenas : Vec<FrameArena>,
+    current : usize,
+    alignment : u64,
+}
+impl FrameAllocator{
+    pub unsafe fn new(allocator : &mut Allocator, frame_count : usize, size : u64, alignment : u64) -> RendererResult<Self>{
+        let mut frame_allocator = Self{arenas:vec!(),current:0,alignment};
+        for _ in 0..frame_count{
+            let buffer_create_info = BufferCreateInfo{
+                s_type : StructureType::BUFFER_CREATE_INFO,
+                p_next : std::ptr::null(),
+                flags : BufferCreateFlags::empty(),
+                p_queue_family_indices : std::ptr::null(),
+                queue_family_index_count : 0,
+                sharing_mode : SharingMode::EXCLUSIVE,
+                size,
+                usage : BufferUsageFlags::TRANSFER_SRC | BufferUsageFlags::UNIFORM_BUFFER | BufferUsageFlags::VERTEX_BUFFER | BufferUsageFlags::INDEX_BUFFER,
+            };
+            let buffer = match BufferAndAllocation::new_dedicated(allocator, &buffer_create_info, MemoryPropertyFlags::HOST_VISIBLE, MemoryPropertyFlags::HOST_COHERENT, "frame arena"){
+                Ok(buffer)=>{buffer}
+                Err(error)=>{
+                    frame_allocator.destroy(allocator);
+                    return Err(error);
+                }
+            };
+            let mapped = allocator.get_memory_map_data(&buffer.allocation).mapped;
+            frame_allocator.arenas.push(FrameArena{buffer,linear:LinearAllocator::new(size),mapped,retired:vec!()});
+            if mapped.is_null(){
+                frame_allocator.destroy(allocator);
+                return Err(RendererError::NotMapped);
+            }
+        }
+        return Ok(frame_allocator);
+    }
+    pub unsafe fn begin_frame(&mut self, allocator : &mut Allocator, frame : usize){
+        self.current = frame % self.arenas.len();
+        let arena = &mut self.arenas[self.current];
+        arena.linear.reset();
+        for buffer in arena.retired.drain(..){
+            buffer.destroy(allocator);
+        }
+    }
+    pub fn allocate(&mut self, size : u64, alignment : u64) -> Option<TransientAllocation>{
+        let arena = self.arenas.get_mut(self.current)?;
+        let offset = arena.linear.allocate(size, alignment.max(self.alignment))?;
+        return Some(TransientAllocation{
+            buffer : arena.buffer.buffer,
+            offset,size,
+            mapped : unsafe{arena.mapped.add(offset as usize)},
+        });
+    }
+    pub unsafe fn flush(&self, allocator : &Allocator, allocation : &TransientAllocation) -> RendererResult<()>{
+        return allocator.flush_allocation(&self.arenas[self.current].buffer.allocation, allocation.offset, allocation.size);
+    }
+    pub fn retire(&mut self, buffer : BufferAndAllocation){
+        self.arenas[self.current].retired.push(buffer);
+    }
+    pub fn get_buffers(&self) -> Vec<Buffer>{
+        return self.arenas.iter().map(|arena|arena.buffer.buffer).collect();
+    }
+    pub fn get_used_size(&self) -> u64{
+        return self.arenas.get(self.current).map_or(0, |arena|arena.linear.get_used_size());
+    }
+    pub unsafe fn destroy(&mut self, allocator : &mut Allocator){
+        for mut arena in self.arenas.drain(..){
+            for buffer in arena.retired.drain(..){
+                buffer.destroy(allocator);
+            }
+            arena.buffer.destroy(allocator);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use ash::{Device, vk::{DeviceMemory, MemoryAllocateInfo, MappedMemoryRange, MemoryMapFlags, WHOLE_SIZE, MemoryDedicatedAllocateInfo, StructureType, Image, Buffer}};
+
+use crate::error::RendererResult;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DedicatedResource{
+    Image(Image),
+    Buffer(Buffer),
+}
+pub trait MemoryBackend{
+    unsafe fn allocate_memory(&self, size : u64, memory_type : u32, dedicated : Option<DedicatedResource>) -> RendererResult<DeviceMemory>;
+    unsafe fn free_memory(&self, memory : DeviceMemory);
+    unsafe fn map_memory(&self, memory : DeviceMemory) -> RendererResult<*mut u8>;
+    unsafe fn unmap_memory(&self, memory : DeviceMemory);
+    unsafe fn flush_memory(&self, memory : DeviceMemory, offset : u64, size : u64) -> RendererResult<()>;
+    unsafe fn invalidate_memory(&self, memory : DeviceMemory, offset : u64, size : u64) -> RendererResult<()>;
+}
+impl MemoryBackend for Device{
+    unsafe fn allocate_memory(&self, size : u64, memory_type : u32, dedicated : Option<DedicatedResource>) -> RendererResult<DeviceMemory>{
+        let dedicated_allocate_info = MemoryDedicatedAllocateInfo{
+            s_type : StructureType::MEMORY_DEDICATED_ALLOCATE_INFO,
+            p_next : std::ptr::null(),
+            image : match dedicated{Some(DedicatedResource::Image(image))=>{image} _=>{Image::null()}},
+            buffer : match dedicated{Some(DedicatedResource::Buffer(buffer))=>{buffer} _=>{Buffer::null()}},
+        };
+        let allocate_info = MemoryAllocateInfo{
+            s_type : StructureType::MEMORY_ALLOCATE_INFO,
+            p_next : if dedicated.is_some(){&dedicated_allocate_info as *const MemoryDedicatedAllocateInfo as *const std::ffi::c_void}else{std::ptr::null()},
+            allocation_size : size,
+            memory_type_index : memory_type,
+        };
+        return Ok(Device::allocate_memory(self, &allocate_info, None)?);
+    }
+    unsafe fn free_memory(&self, memory : DeviceMemory){
+        Device::free_memory(self, memory, None);
+    }
+    unsafe fn map_memory(&self, memory : DeviceMemory) -> RendererResult<*mut u8>{
+        return Ok(Device::map_memory(self, memory, 0, WHOLE_SIZE, MemoryMapFlags::empty())? as *mut u8);
+    }
+    unsafe fn unmap_memory(&self, memory : DeviceMemory){
+        Device::unmap_memory(self, memory);
+    }
+    unsafe fn flush_memory(&self, memory : DeviceMemory, offset : u64, size : u64) -> RendererResult<()>{
+        let ranges = [
+            MappedMemoryRange{
+                s_type : StructureType::MAPPED_MEMORY_RANGE,
+                p_next : std::ptr::null(),
+                memory,offset,size,
+            }
+        ];
+        return Ok(self.flush_mapped_memory_ranges(&ranges)?);
+    }
+    unsafe fn invalidate_memory(&self, memory : DeviceMemory, offset : u64, size : u64) -> RendererResult<()>{
+        let ranges = [
+            MappedMemoryRange{
+                s_type : StructureType::MAPPED_MEMORY_RANGE,
+                p_next : std::ptr::null(),
+                memory,offset,size,
+            }
+        ];
+        return Ok(self.invalidate_mapped_memory_ranges(&ranges)?);
+    }
+}
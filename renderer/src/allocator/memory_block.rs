@@ -1,94 +1,126 @@
-use std::cmp::max;
+use ash::vk::{DeviceMemory, PhysicalDeviceMemoryProperties, MemoryPropertyFlags};
 
-use ash::{vk::{DeviceMemory, PhysicalDeviceMemoryProperties, MemoryPropertyFlags, MemoryAllocateInfo, StructureType}, Device};
+use crate::error::{RendererError, RendererResult};
 
-use super::memory_regions::MemoryRegion;
-
-pub const MIN_BLOCK_SIZE : u64 = 32_000_000;
+use super::{debug::AllocationLabel, memory_regions::{MemoryRegion, FreeList, ResourceKind}, memory_backend::{MemoryBackend, DedicatedResource}};
 
 pub struct MemoryBlock{
     pub memory : DeviceMemory,
     size : u64,
     memory_type : u32,
     pub regions : Vec<Option<MemoryRegion>>,
+    labels : Vec<Option<AllocationLabel>>,
+    free_list : FreeList,
+    dedicated : bool,
+    mapped : *mut u8,
 }
 impl MemoryBlock{
-    pub unsafe fn create(device : &Device, size : u64, memory_type_filter : u32, memory_property_flags : MemoryPropertyFlags, physical_device_memory_properties : PhysicalDeviceMemoryProperties) -> Self{
-        let memory_type = Self::get_memory_type(physical_device_memory_properties, memory_property_flags, memory_type_filter);
-        let allocate_info = MemoryAllocateInfo{
-            s_type : StructureType::MEMORY_ALLOCATE_INFO,
-            p_next : std::ptr::null(),
-            allocation_size : max(MIN_BLOCK_SIZE, size),
-            memory_type_index : memory_type,
-        };
-        let memory = device.allocate_memory(&allocate_info, None).expect("Failed to allocate GPU memory");
-        return Self{
+    pub unsafe fn create<B : MemoryBackend>(backend : &B, size : u64, memory_type : u32, dedicated : Option<DedicatedResource>) -> RendererResult<Self>{
+        let memory = backend.allocate_memory(size, memory_type, dedicated)?;
+        return Ok(Self{
             memory,
-            size : max(MIN_BLOCK_SIZE, size),
+            size,
             memory_type,
             regions : vec!(),
-        }
+            labels : vec!(),
+            free_list : FreeList::new(size),
+            dedicated : dedicated.is_some(),
+            mapped : std::ptr::null_mut(),
+        })
+    }
+    pub unsafe fn map<B : MemoryBackend>(&mut self, backend : &B) -> RendererResult<()>{
+        if self.mapped.is_null(){self.mapped = backend.map_memory(self.memory)?}
+        return Ok(());
+    }
+    pub fn get_mapped_ptr(&self) -> *mut u8{
+        return self.mapped;
     }
-    fn get_memory_type(physical_device_memory_properties : PhysicalDeviceMemoryProperties, memory_property_flags : MemoryPropertyFlags, memory_type_filter : u32) -> u32{
-        for (i, memory_type) in physical_device_memory_properties.memory_types.iter().enumerate(){
+    pub fn find_memory_type(physical_device_memory_properties : PhysicalDeviceMemoryProperties, memory_property_flags : MemoryPropertyFlags, memory_type_filter : u32) -> RendererResult<u32>{
+        for (i, memory_type) in physical_device_memory_properties.memory_types.iter().enumerate().take(physical_device_memory_properties.memory_type_count as usize){
             if memory_type.property_flags.contains(memory_property_flags) && (memory_type_filter & (1 << i)) > 0{
-                return i as u32;
+                return Ok(i as u32);
             }
         }
-        panic!("Requested unsupported memory type");
+        return Err(RendererError::NoMemoryType);
     }
     pub fn is_block_compatible(&self, physical_device_memory_properties : PhysicalDeviceMemoryProperties, memory_type_filter : u32, memory_property_flags : MemoryPropertyFlags) -> bool{
-        return physical_device_memory_properties.memory_types[self.memory_type as usize].property_flags.contains(memory_property_flags) && (memory_type_filter & (1 << self.memory_type)) > 0;
+        return !self.dedicated && physical_device_memory_properties.memory_types[self.memory_type as usize].property_flags.contains(memory_property_flags) && (memory_type_filter & (1 << self.memory_type)) > 0;
     }
     pub fn fit_region(&mut self, region : MemoryRegion) -> usize{
         for (i,memory_region) in self.regions.iter_mut().enumerate(){
-            if memory_region.is_none(){*memory_region = Some(region);return i}
+            if memory_region.is_none(){
+                *memory_region = Some(region);
+                self.labels[i] = Some(AllocationLabel::new(""));
+                return i;
+            }
         }
         self.regions.push(Some(region));
+        self.labels.push(Some(AllocationLabel::new("")));
         return self.regions.len() - 1;
     }
-    pub fn try_fit_region(&mut self, size : u64, alignment : u64) -> Option<usize>{
-        let regions = self.regions.clone();
-        let mut regions = regions.iter().filter_map(|region|{*region}).collect::<Vec<_>>();
-        regions.sort_unstable_by_key(|region|region.offset);
-        if regions.len() == 0 && self.size >= size{
-            let region = MemoryRegion{size,offset:0};
-            return Some(self.fit_region(region));
-        }
-        for (i, region) in regions.iter().enumerate(){
-            if i == 0 && region.offset >= size{
-                let region = MemoryRegion{size,offset:0};
-                return Some(self.fit_region(region));
-            }
-            if i != 0{
-                let start_seek = regions[i-1].offset+regions[i-1].size;
-                let alignment_offset = (alignment - (start_seek % alignment)) % alignment;
-                let offset = start_seek + alignment_offset;
-                if offset >= region.offset && region.offset - offset >= size{
-                    let region = MemoryRegion{
-                        size,offset,
-                    };
-                    return Some(self.fit_region(region));
-                }
-            }
-            if i != 0 && i == regions.len() - 1{
-                let start_seek = regions[i-1].offset+regions[i-1].size;
-                let alignment_offset = (alignment - (start_seek % alignment)) % alignment;
-                let offset = start_seek + alignment_offset;
-                if offset + size <= self.size{
-                    let region = MemoryRegion{
-                        size,offset
-                    };
-                    return Some(self.fit_region(region))
-                }
-            }
+    pub fn set_label_name(&mut self, region : usize, name : &str){
+        if let Some(label) = self.labels[region].as_mut(){label.name = String::from(name)}
+    }
+    pub fn set_label(&mut self, region : usize, label : Option<AllocationLabel>){
+        self.labels[region] = label;
+    }
+    pub fn take_label(&mut self, region : usize) -> Option<AllocationLabel>{
+        return self.labels[region].take();
+    }
+    pub fn get_label(&self, region : usize) -> Option<&AllocationLabel>{
+        return self.labels.get(region)?.as_ref();
+    }
+    pub fn try_fit_region(&mut self, size : u64, alignment : u64, kind : ResourceKind, granularity : u64) -> Option<usize>{
+        let region = self.free_list.allocate(size, alignment, kind, granularity)?;
+        return Some(self.fit_region(region));
+    }
+    pub fn reserve_region(&mut self, region : MemoryRegion, kind : ResourceKind) -> Option<usize>{
+        if !self.free_list.reserve(region, kind){return None}
+        return Some(self.fit_region(region));
+    }
+    pub fn free_region(&mut self, region : usize){
+        if let Some(memory_region) = self.regions[region].take(){
+            self.labels[region] = None;
+            self.free_list.free(memory_region);
         }
-        return None;
     }
-    pub unsafe fn destroy(&mut self, device : &Device){
-        device.free_memory(self.memory, None);
+    pub fn get_size(&self) -> u64{
+        return self.size;
+    }
+    pub fn get_memory_type(&self) -> u32{
+        return self.memory_type;
+    }
+    pub fn get_used_size(&self) -> u64{
+        return self.size - self.free_list.get_free_size();
+    }
+    pub fn get_allocation_count(&self) -> usize{
+        return self.regions.iter().flatten().count();
+    }
+    pub fn get_largest_free_range(&self) -> u64{
+        return self.free_list.get_largest_free_range();
+    }
+    pub fn get_free_ranges(&self) -> Vec<MemoryRegion>{
+        return self.free_list.get_free_ranges();
+    }
+    pub fn get_used_ranges(&self) -> Vec<(MemoryRegion, ResourceKind)>{
+        return self.free_list.get_used_ranges();
+    }
+    pub fn is_dedicated(&self) -> bool{
+        return self.dedicated;
+    }
+    pub fn is_empty(&self) -> bool{
+        return self.free_list.is_empty();
+    }
+    pub unsafe fn destroy<B : MemoryBackend>(&mut self, backend : &B){
+        if !self.mapped.is_null(){
+            backend.unmap_memory(self.memory);
+            self.mapped = std::ptr::null_mut();
+        }
+        backend.free_memory(self.memory);
         self.regions = vec!();
+        self.labels = vec!();
+        self.free_list = FreeList::new(0);
         self.size = 0;
         self.memory_type = 0;
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,115 @@
+use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct MemoryRegion{
     pub offset : u64,
     pub size : u64,
-}
\ No newline at end of file
+}
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResourceKind{
+    Linear,
+    Optimal,
+}
+pub struct FreeList{
+    size : u64,
+    by_offset : BTreeMap<u64, u64>,
+    by_size : BTreeSet<(u64, u64)>,
+    used : BTreeMap<u64, (u64, ResourceKind)>,
+}
+impl FreeList{
+    pub fn new(size : u64) -> Self{
+        let mut free_list = Self{size,by_offset:BTreeMap::new(),by_size:BTreeSet::new(),used:BTreeMap::new()};
+        if size > 0{free_list.insert(0, size)}
+        return free_list;
+    }
+    pub fn allocate(&mut self, size : u64, alignment : u64, kind : ResourceKind, granularity : u64) -> Option<MemoryRegion>{
+        if size == 0{return None}
+        let alignment = alignment.max(1);
+        let granularity = granularity.max(1);
+        let mut found = None;
+        for &(free_size, free_offset) in self.by_size.range((size, 0)..){
+            let mut offset = align_up(free_offset, alignment);
+            let mut end = free_offset + free_size;
+            if granularity > 1{
+                if let Some((&previous_offset, &(previous_size, previous_kind))) = self.used.range(..free_offset).next_back(){
+                    if previous_kind != kind && (previous_offset + previous_size - 1) / granularity == offset / granularity{
+                        offset = align_up(offset, granularity);
+                    }
+                }
+                if let Some(&(_, next_kind)) = self.used.get(&end){
+                    if next_kind != kind{end = end / granularity * granularity}
+                }
+            }
+            if offset + size <= end{
+                found = Some((free_offset, free_size, offset));
+                break;
+            }
+        }
+        let (free_offset, free_size, offset) = found?;
+        self.remove(free_offset, free_size);
+        if offset > free_offset{self.insert(free_offset, offset - free_offset)}
+        if offset + size < free_offset + free_size{self.insert(offset + size, free_offset + free_size - offset - size)}
+        self.used.insert(offset, (size, kind));
+        return Some(MemoryRegion{offset,size});
+    }
+    pub fn reserve(&mut self, region : MemoryRegion, kind : ResourceKind) -> bool{
+        let (free_offset, free_size) = match self.by_offset.range(..=region.offset).next_back(){
+            Some((&free_offset, &free_size))=>{(free_offset, free_size)}
+            None=>{return false}
+        };
+        if region.size == 0 || region.offset + region.size > free_offset + free_size{return false}
+        self.remove(free_offset, free_size);
+        if region.offset > free_offset{self.insert(free_offset, region.offset - free_offset)}
+        if region.offset + region.size < free_offset + free_size{self.insert(region.offset + region.size, free_offset + free_size - region.offset - region.size)}
+        self.used.insert(region.offset, (region.size, kind));
+        return true;
+    }
+    pub fn free(&mut self, region : MemoryRegion){
+        self.used.remove(&region.offset);
+        let mut offset = region.offset;
+        let mut size = region.size;
+        let previous = self.by_offset.range(..offset).next_back().map(|(&offset, &size)|(offset, size));
+        if let Some((previous_offset, previous_size)) = previous{
+            if previous_offset + previous_size == offset{
+                self.remove(previous_offset, previous_size);
+                offset = previous_offset;
+                size += previous_size;
+            }
+        }
+        let next = self.by_offset.get(&(region.offset + region.size)).map(|&size|(region.offset + region.size, size));
+        if let Some((next_offset, next_size)) = next{
+            self.remove(next_offset, next_size);
+            size += next_size;
+        }
+        self.insert(offset, size);
+    }
+    pub fn get_free_size(&self) -> u64{
+        return self.by_offset.values().sum();
+    }
+    pub fn get_largest_free_range(&self) -> u64{
+        return self.by_size.iter().next_back().map_or(0, |&(size, _)|size);
+    }
+    pub fn get_free_ranges(&self) -> Vec<MemoryRegion>{
+        return self.by_offset.iter().map(|(&offset, &size)|MemoryRegion{offset,size}).collect();
+    }
+    pub fn get_used_ranges(&self) -> Vec<(MemoryRegion, ResourceKind)>{
+        return self.used.iter().map(|(&offset, &(size, kind))|(MemoryRegion{offset,size}, kind)).collect();
+    }
+    pub fn get_free_range_count(&self) -> usize{
+        return self.by_offset.len();
+    }
+    pub fn is_empty(&self) -> bool{
+        return self.get_largest_free_range() == self.size;
+    }
+    fn insert(&mut self, offset : u64, size : u64){
+        self.by_offset.insert(offset, size);
+        self.by_size.insert((size, offset));
+    }
+    fn remove(&mut self, offset : u64, size : u64){
+        self.by_offset.remove(&offset);
+        self.by_size.remove(&(size, offset));
+    }
+}
+pub fn align_up(offset : u64, alignment : u64) -> u64{
+    return offset.div_ceil(alignment) * alignment;
+}
@@ -1,40 +1,208 @@
 pub mod memory_block;
 pub mod memory_regions;
+pub mod memory_backend;
+pub mod stats;
+pub mod defragment;
+pub mod frame;
+pub mod debug;
 
-use ash::{Instance, vk::{PhysicalDevice, PhysicalDeviceMemoryProperties, MemoryPropertyFlags, Image, Buffer, DeviceMemory}, Device};
+use ash::{Instance, extensions::{khr::GetPhysicalDeviceProperties2, ext::DebugUtils}, vk::{self, API_VERSION_1_1, MemoryRequirements, MemoryRequirements2, MemoryDedicatedRequirements, ImageMemoryRequirementsInfo2, BufferMemoryRequirementsInfo2, PhysicalDevice, PhysicalDeviceMemoryProperties, PhysicalDeviceMemoryProperties2, PhysicalDeviceMemoryBudgetPropertiesEXT, StructureType, MemoryPropertyFlags, Image, ImageTiling, Buffer, BufferCreateInfo, BufferUsageFlags, DeviceMemory}, Device};
 
-use self::memory_block::MemoryBlock;
+use crate::error::{RendererError, RendererResult};
 
-pub struct Allocator{
+use self::{debug::LiveAllocation, memory_block::MemoryBlock, memory_regions::ResourceKind, memory_backend::{MemoryBackend, DedicatedResource}};
+
+const SMALL_HEAP_MAX_SIZE : u64 = 1024 * 1024 * 1024;
+const LARGE_HEAP_BLOCK_SIZE : u64 = 256 * 1024 * 1024;
+
+pub struct Allocator<B : MemoryBackend = Device>{
     physical_device_memory_properties : PhysicalDeviceMemoryProperties,
-    pub device : Device,
+    pub device : B,
     blocks : Vec<Option<MemoryBlock>>,
+    heap_usage : Vec<u64>,
+    heap_budgets : Vec<Option<u64>>,
+    empty_blocks_kept : usize,
+    memory_budget : Option<(GetPhysicalDeviceProperties2, PhysicalDevice)>,
+    dedicated_requirements : bool,
+    buffer_image_granularity : u64,
+    non_coherent_atom_size : u64,
+    debug_utils : Option<DebugUtils>,
 }
 impl Allocator{
-    pub unsafe fn new(instance : &Instance, physical_device : PhysicalDevice, device : Device) -> Self{
+    pub unsafe fn new(instance : &Instance, physical_device : PhysicalDevice, device : Device, memory_budget : Option<GetPhysicalDeviceProperties2>) -> Self{
         let physical_device_memory_properties = instance.get_physical_device_memory_properties(physical_device);
-        return Self{physical_device_memory_properties,device,blocks:vec!()}
+        let mut allocator = Self::with_backend(device, physical_device_memory_properties);
+        allocator.memory_budget = memory_budget.map(|loader|(loader, physical_device));
+        let physical_device_properties = instance.get_physical_device_properties(physical_device);
+        allocator.dedicated_requirements = physical_device_properties.api_version >= API_VERSION_1_1;
+        allocator.buffer_image_granularity = physical_device_properties.limits.buffer_image_granularity;
+        allocator.non_coherent_atom_size = physical_device_properties.limits.non_coherent_atom_size;
+        return allocator;
+    }
+    unsafe fn get_image_requirements(&self, image : Image) -> (MemoryRequirements, bool){
+        if !self.dedicated_requirements{return (self.device.get_image_memory_requirements(image), false)}
+        let info = ImageMemoryRequirementsInfo2{
+            s_type : StructureType::IMAGE_MEMORY_REQUIREMENTS_INFO_2,
+            p_next : std::ptr::null(),
+            image,
+        };
+        let mut dedicated_requirements = MemoryDedicatedRequirements{
+            s_type : StructureType::MEMORY_DEDICATED_REQUIREMENTS,
+            p_next : std::ptr::null_mut(),
+            ..Default::default()
+        };
+        let mut requirements = MemoryRequirements2{
+            s_type : StructureType::MEMORY_REQUIREMENTS_2,
+            p_next : &mut dedicated_requirements as *mut MemoryDedicatedRequirements as *mut std::ffi::c_void,
+            memory_requirements : MemoryRequirements::default(),
+        };
+        self.device.get_image_memory_requirements2(&info, &mut requirements);
+        return (requirements.memory_requirements, dedicated_requirements.prefers_dedicated_allocation != 0 || dedicated_requirements.requires_dedicated_allocation != 0);
+    }
+    unsafe fn get_buffer_requirements(&self, buffer : Buffer) -> (MemoryRequirements, bool){
+        if !self.dedicated_requirements{return (self.device.get_buffer_memory_requirements(buffer), false)}
+        let info = BufferMemoryRequirementsInfo2{
+            s_type : StructureType::BUFFER_MEMORY_REQUIREMENTS_INFO_2,
+            p_next : std::ptr::null(),
+            buffer,
+        };
+        let mut dedicated_requirements = MemoryDedicatedRequirements{
+            s_type : StructureType::MEMORY_DEDICATED_REQUIREMENTS,
+            p_next : std::ptr::null_mut(),
+            ..Default::default()
+        };
+        let mut requirements = MemoryRequirements2{
+            s_type : StructureType::MEMORY_REQUIREMENTS_2,
+            p_next : &mut dedicated_requirements as *mut MemoryDedicatedRequirements as *mut std::ffi::c_void,
+            memory_requirements : MemoryRequirements::default(),
+        };
+        self.device.get_buffer_memory_requirements2(&info, &mut requirements);
+        return (requirements.memory_requirements, dedicated_requirements.prefers_dedicated_allocation != 0 || dedicated_requirements.requires_dedicated_allocation != 0);
+    }
+}
+impl<B : MemoryBackend> Allocator<B>{
+    pub fn with_backend(device : B, physical_device_memory_properties : PhysicalDeviceMemoryProperties) -> Self{
+        let heap_count = physical_device_memory_properties.memory_heap_count as usize;
+        return Self{physical_device_memory_properties,device,blocks:vec!(),heap_usage:vec!(0;heap_count),heap_budgets:vec!(None;heap_count),empty_blocks_kept:1,memory_budget:None,dedicated_requirements:false,buffer_image_granularity:1,non_coherent_atom_size:1,debug_utils:None}
+    }
+    pub fn set_heap_budget(&mut self, heap : u32, budget : Option<u64>){
+        if let Some(heap_budget) = self.heap_budgets.get_mut(heap as usize){*heap_budget = budget}
+    }
+    pub fn set_buffer_image_granularity(&mut self, buffer_image_granularity : u64){
+        self.buffer_image_granularity = buffer_image_granularity;
+    }
+    pub fn set_non_coherent_atom_size(&mut self, non_coherent_atom_size : u64){
+        self.non_coherent_atom_size = non_coherent_atom_size;
+    }
+    pub fn set_empty_blocks_kept(&mut self, empty_blocks_kept : usize){
+        self.empty_blocks_kept = empty_blocks_kept;
+    }
+    pub fn get_heap_usage(&self, heap : u32) -> u64{
+        return self.heap_usage[heap as usize];
+    }
+    pub fn get_block_size(&self, memory_type : u32) -> u64{
+        let heap = self.physical_device_memory_properties.memory_types[memory_type as usize].heap_index;
+        let heap_size = self.physical_device_memory_properties.memory_heaps[heap as usize].size;
+        return if heap_size <= SMALL_HEAP_MAX_SIZE{heap_size / 8}else{LARGE_HEAP_BLOCK_SIZE};
     }
-    pub unsafe fn create_allocation(&mut self, size : u64, alignment : u64, memory_property_flags : MemoryPropertyFlags, memory_type_filter : u32) -> MemoryRegionPointer{
+    fn get_available_budget(&self, heap : u32) -> Option<u64>{
+        let mut available = self.heap_budgets[heap as usize].map(|budget|budget.saturating_sub(self.heap_usage[heap as usize]));
+        if let Some((loader, physical_device)) = &self.memory_budget{
+            let mut budget_properties = PhysicalDeviceMemoryBudgetPropertiesEXT{
+                s_type : StructureType::PHYSICAL_DEVICE_MEMORY_BUDGET_PROPERTIES_EXT,
+                p_next : std::ptr::null_mut(),
+                ..Default::default()
+            };
+            let mut memory_properties = PhysicalDeviceMemoryProperties2{
+                s_type : StructureType::PHYSICAL_DEVICE_MEMORY_PROPERTIES_2,
+                p_next : &mut budget_properties as *mut PhysicalDeviceMemoryBudgetPropertiesEXT as *mut std::ffi::c_void,
+                memory_properties : PhysicalDeviceMemoryProperties::default(),
+            };
+            unsafe{loader.get_physical_device_memory_properties2(*physical_device, &mut memory_properties)};
+            let driver_available = budget_properties.heap_budget[heap as usize].saturating_sub(budget_properties.heap_usage[heap as usize]);
+            available = Some(available.map_or(driver_available, |available|available.min(driver_available)));
+        }
+        return available;
+    }
+    pub unsafe fn create_allocation(&mut self, size : u64, alignment : u64, kind : ResourceKind, memory_property_flags : MemoryPropertyFlags, memory_type_filter : u32) -> RendererResult<MemoryRegionPointer>{
         for (i, block) in self.blocks.iter_mut().enumerate(){
             if block.is_some() && block.as_ref().unwrap().is_block_compatible(self.physical_device_memory_properties, memory_type_filter, memory_property_flags){
-               let region = block.as_mut().unwrap().try_fit_region(size, alignment);
+               let region = block.as_mut().unwrap().try_fit_region(size, alignment, kind, self.buffer_image_granularity);
                if region.is_some(){
-                   return MemoryRegionPointer{
+                   return Ok(MemoryRegionPointer{
                        block : i,
                        region : region.unwrap(),
-                   };
+                   });
                }
             }
         }
-        let block = self.fit_block(MemoryBlock::create(&self.device, size, memory_type_filter, memory_property_flags, self.physical_device_memory_properties));
-        let region = self.blocks[block].as_mut().unwrap().try_fit_region(size, alignment).unwrap();
-        return MemoryRegionPointer{
+        let memory_type = MemoryBlock::find_memory_type(self.physical_device_memory_properties, memory_property_flags, memory_type_filter)?;
+        let heap = self.physical_device_memory_properties.memory_types[memory_type as usize].heap_index;
+        let mut block_size = self.get_block_size(memory_type).max(size);
+        if let Some(available) = self.get_available_budget(heap){
+            if size > available{return Err(RendererError::OutOfBudget(heap))}
+            block_size = block_size.min(available);
+        }
+        let block = match self.create_block(block_size, memory_type, None){
+            Err(RendererError::Vulkan(vk::Result::ERROR_OUT_OF_DEVICE_MEMORY | vk::Result::ERROR_OUT_OF_HOST_MEMORY)) if block_size > size=>{self.create_block(size, memory_type, None)?}
+            block=>{block?}
+        };
+        let region = self.blocks[block].as_mut().unwrap().try_fit_region(size, alignment, kind, self.buffer_image_granularity).unwrap();
+        return Ok(MemoryRegionPointer{
             block,region,
+        })
+    }
+    pub unsafe fn create_dedicated_allocation(&mut self, size : u64, memory_property_flags : MemoryPropertyFlags, memory_type_filter : u32, resource : DedicatedResource) -> RendererResult<MemoryRegionPointer>{
+        let memory_type = MemoryBlock::find_memory_type(self.physical_device_memory_properties, memory_property_flags, memory_type_filter)?;
+        let heap = self.physical_device_memory_properties.memory_types[memory_type as usize].heap_index;
+        if let Some(available) = self.get_available_budget(heap){
+            if size > available{return Err(RendererError::OutOfBudget(heap))}
         }
+        let block = self.create_block(size, memory_type, Some(resource))?;
+        let region = self.blocks[block].as_mut().unwrap().try_fit_region(size, 1, ResourceKind::Linear, 1).unwrap();
+        return Ok(MemoryRegionPointer{
+            block,region,
+        })
+    }
+    unsafe fn create_block(&mut self, size : u64, memory_type : u32, dedicated : Option<DedicatedResource>) -> RendererResult<usize>{
+        let mut memory_block = MemoryBlock::create(&self.device, size, memory_type, dedicated)?;
+        if self.physical_device_memory_properties.memory_types[memory_type as usize].property_flags.contains(MemoryPropertyFlags::HOST_VISIBLE){
+            if let Err(error) = memory_block.map(&self.device){
+                memory_block.destroy(&self.device);
+                return Err(error);
+            }
+        }
+        let heap = self.physical_device_memory_properties.memory_types[memory_type as usize].heap_index;
+        self.heap_usage[heap as usize] += memory_block.get_size();
+        return Ok(self.fit_block(memory_block));
+    }
+    pub fn get_preferred_memory_property_flags(&self, memory_property_flags : MemoryPropertyFlags, preferred_memory_property_flags : MemoryPropertyFlags, memory_type_filter : u32) -> MemoryPropertyFlags{
+        let preferred = memory_property_flags | preferred_memory_property_flags;
+        if MemoryBlock::find_memory_type(self.physical_device_memory_properties, preferred, memory_type_filter).is_ok(){return preferred}
+        return memory_property_flags;
+    }
+    pub fn prefers_dedicated_allocation(&self, size : u64, memory_property_flags : MemoryPropertyFlags, memory_type_filter : u32) -> bool{
+        return match MemoryBlock::find_memory_type(self.physical_device_memory_properties, memory_property_flags, memory_type_filter){
+            Ok(memory_type)=>{size > self.get_block_size(memory_type) / 2}
+            Err(_)=>{false}
+        };
     }
     pub unsafe fn destroy_allocation(&mut self, allocation : &MemoryRegionPointer){
-        self.blocks[allocation.block].as_mut().unwrap().regions.remove(allocation.region);
+        let block = self.blocks[allocation.block].as_mut().unwrap();
+        block.free_region(allocation.region);
+        if block.is_dedicated(){return self.release_block(allocation.block)}
+        if !block.is_empty(){return}
+        let empty_blocks = self.blocks.iter().filter(|block|block.as_ref().map_or(false, |block|!block.is_dedicated() && block.is_empty())).count();
+        if empty_blocks > self.empty_blocks_kept{
+            self.release_block(allocation.block);
+        }
+    }
+    unsafe fn release_block(&mut self, index : usize){
+        if let Some(mut block) = self.blocks[index].take(){
+            let heap = self.physical_device_memory_properties.memory_types[block.get_memory_type() as usize].heap_index;
+            self.heap_usage[heap as usize] -= block.get_size();
+            block.destroy(&self.device);
+        }
     }
     fn fit_block(&mut self, block : MemoryBlock) -> usize{
         for (i,memory_block) in self.blocks.iter_mut().enumerate(){
@@ -43,7 +211,10 @@ impl Allocator{
         self.blocks.push(Some(block));
         return self.blocks.len() - 1;
     }
-    pub fn destroy(&mut self){
+    pub fn destroy(&mut self) -> Vec<LiveAllocation>{
+        let live_allocations = self.get_live_allocations();
+        #[cfg(debug_assertions)]
+        for live_allocation in live_allocations.iter(){eprintln!("{}", live_allocation)}
         for block in self.blocks.iter_mut(){
             if block.is_some(){
                 let block = block.as_mut().unwrap();
@@ -51,6 +222,8 @@ impl Allocator{
             }
             *block = None;
         }
+        for usage in self.heap_usage.iter_mut(){*usage = 0}
+        return live_allocations;
     }
     pub unsafe fn get_memory_map_data(&self, allocation : &MemoryRegionPointer) -> MemoryMapData{
         let block = self.blocks[allocation.block].as_ref().unwrap();
@@ -59,25 +232,30 @@ impl Allocator{
             memory:block.memory,
             offset:region.offset,
             size:region.size,
+            mapped:if block.get_mapped_ptr().is_null(){std::ptr::null_mut()}else{block.get_mapped_ptr().add(region.offset as usize)},
         }
     }
-    pub fn dump_contents(&self){
-        for (i, block) in self.blocks.iter().enumerate(){
-            if block.is_some(){
-                println!("[{}]Block:",i);
-                for (i,region) in block.as_ref().unwrap().regions.iter().enumerate(){
-                    if region.is_some(){
-                        println!("  [{i}]Region, Offset: {}, Size: {}",region.as_ref().unwrap().offset, region.as_ref().unwrap().size);
-                    }
-                    else{
-                        println!("  [{}]Region, Non-existant",i);
-                    }
-                }
-            }
-            else{
-                println!("[{}]Block, Non-existant",i);
-            }
-        }
+    pub unsafe fn flush_allocation(&self, allocation : &MemoryRegionPointer, offset : u64, size : u64) -> RendererResult<()>{
+        return match self.get_non_coherent_range(allocation, offset, size){
+            Some((memory, offset, size))=>{self.device.flush_memory(memory, offset, size)}
+            None=>{Ok(())}
+        };
+    }
+    pub unsafe fn invalidate_allocation(&self, allocation : &MemoryRegionPointer, offset : u64, size : u64) -> RendererResult<()>{
+        return match self.get_non_coherent_range(allocation, offset, size){
+            Some((memory, offset, size))=>{self.device.invalidate_memory(memory, offset, size)}
+            None=>{Ok(())}
+        };
+    }
+    fn get_non_coherent_range(&self, allocation : &MemoryRegionPointer, offset : u64, size : u64) -> Option<(DeviceMemory, u64, u64)>{
+        let block = self.blocks[allocation.block].as_ref().unwrap();
+        let property_flags = self.physical_device_memory_properties.memory_types[block.get_memory_type() as usize].property_flags;
+        if block.get_mapped_ptr().is_null() || property_flags.contains(MemoryPropertyFlags::HOST_COHERENT){return None}
+        let region = block.regions[allocation.region].as_ref().unwrap();
+        let atom = self.non_coherent_atom_size.max(1);
+        let start = (region.offset + offset) / atom * atom;
+        let end = memory_regions::align_up(region.offset + offset + size.min(region.size.saturating_sub(offset)), atom).min(block.get_size());
+        return Some((block.memory, start, end - start));
     }
 }
 #[derive(Debug)]
@@ -85,25 +263,49 @@ pub struct MemoryRegionPointer{
     block : usize,
     region : usize,
 }
+impl MemoryRegionPointer{
+    pub unsafe fn mapped_slice<'a, T>(&self, allocator : &'a mut Allocator<impl MemoryBackend>) -> Option<&'a mut [T]>{
+        let map_data = allocator.get_memory_map_data(self);
+        if map_data.mapped.is_null() || std::mem::size_of::<T>() == 0 || !(map_data.mapped as usize).is_multiple_of(std::mem::align_of::<T>()){return None}
+        return Some(std::slice::from_raw_parts_mut(map_data.mapped as *mut T, map_data.size as usize / std::mem::size_of::<T>()));
+    }
+}
 pub struct MemoryMapData{
     pub memory : DeviceMemory,
     pub offset : u64,
     pub size : u64,
+    pub mapped : *mut u8,
 }
 pub struct ImageAndAllocation{
     pub image : Image,
     pub allocation : MemoryRegionPointer,
 }
 impl ImageAndAllocation{
-    pub unsafe fn new(allocator : &mut Allocator, image : Image, memory_property_flags : MemoryPropertyFlags) -> Self{
-        let requirements = allocator.device.get_image_memory_requirements(image);
-        let allocation = allocator.create_allocation(requirements.size, requirements.alignment, memory_property_flags, requirements.memory_type_bits);
-        allocator.device.bind_image_memory(image, 
-        allocator.blocks[allocation.block].as_ref().unwrap().memory, 
-        allocator.blocks[allocation.block].as_ref().unwrap().regions[allocation.region].as_ref().unwrap().offset).expect("Failed to bind image memory");
-        return Self{
-            image,allocation,
+    pub unsafe fn new(allocator : &mut Allocator, image : Image, tiling : ImageTiling, memory_property_flags : MemoryPropertyFlags, name : &str) -> RendererResult<Self>{
+        return Self::create(allocator, image, tiling, memory_property_flags, name, false);
+    }
+    pub unsafe fn new_dedicated(allocator : &mut Allocator, image : Image, tiling : ImageTiling, memory_property_flags : MemoryPropertyFlags, name : &str) -> RendererResult<Self>{
+        return Self::create(allocator, image, tiling, memory_property_flags, name, true);
+    }
+    unsafe fn create(allocator : &mut Allocator, image : Image, tiling : ImageTiling, memory_property_flags : MemoryPropertyFlags, name : &str, dedicated : bool) -> RendererResult<Self>{
+        let kind = if tiling == ImageTiling::LINEAR{ResourceKind::Linear}else{ResourceKind::Optimal};
+        let (requirements, prefers_dedicated) = allocator.get_image_requirements(image);
+        let allocation = if dedicated || prefers_dedicated || allocator.prefers_dedicated_allocation(requirements.size, memory_property_flags, requirements.memory_type_bits){
+            allocator.create_dedicated_allocation(requirements.size, memory_property_flags, requirements.memory_type_bits, DedicatedResource::Image(image))?
         }
+        else{
+            allocator.create_allocation(requirements.size, requirements.alignment, kind, memory_property_flags, requirements.memory_type_bits)?
+        };
+        let map_data = allocator.get_memory_map_data(&allocation);
+        if let Err(error) = allocator.device.bind_image_memory(image, map_data.memory, map_data.offset){
+            allocator.destroy_allocation(&allocation);
+            return Err(error.into());
+        }
+        allocator.set_allocation_name(&allocation, name);
+        allocator.set_object_name(image, name).ok();
+        return Ok(Self{
+            image,allocation,
+        })
     }
     pub unsafe fn destroy(&self, allocator : &mut Allocator){
         allocator.destroy_allocation(&self.allocation);
@@ -114,17 +316,46 @@ impl ImageAndAllocation{
 pub struct BufferAndAllocation{
     pub buffer : Buffer,
     pub allocation : MemoryRegionPointer,
+    pub size : u64,
+    pub usage : BufferUsageFlags,
 }
 impl BufferAndAllocation{
-    pub unsafe fn new(allocator : &mut Allocator, buffer : Buffer, memory_property_flags : MemoryPropertyFlags) -> Self{
-        let requirements = allocator.device.get_buffer_memory_requirements(buffer);
-        let allocation = allocator.create_allocation(requirements.size, requirements.alignment, memory_property_flags, requirements.memory_type_bits);
-        allocator.device.bind_buffer_memory(buffer, 
-        allocator.blocks[allocation.block].as_ref().unwrap().memory, 
-        allocator.blocks[allocation.block].as_ref().unwrap().regions[allocation.region].as_ref().unwrap().offset).expect("Failed to bind image memory");
-        return Self{
-            buffer,allocation,
+    pub unsafe fn new(allocator : &mut Allocator, buffer_create_info : &BufferCreateInfo, memory_property_flags : MemoryPropertyFlags, preferred_memory_property_flags : MemoryPropertyFlags, name : &str) -> RendererResult<Self>{
+        return Self::create(allocator, buffer_create_info, memory_property_flags, preferred_memory_property_flags, name, false);
+    }
+    pub unsafe fn new_dedicated(allocator : &mut Allocator, buffer_create_info : &BufferCreateInfo, memory_property_flags : MemoryPropertyFlags, preferred_memory_property_flags : MemoryPropertyFlags, name : &str) -> RendererResult<Self>{
+        return Self::create(allocator, buffer_create_info, memory_property_flags, preferred_memory_property_flags, name, true);
+    }
+    unsafe fn create(allocator : &mut Allocator, buffer_create_info : &BufferCreateInfo, memory_property_flags : MemoryPropertyFlags, preferred_memory_property_flags : MemoryPropertyFlags, name : &str, dedicated : bool) -> RendererResult<Self>{
+        let buffer = allocator.device.create_buffer(buffer_create_info, None)?;
+        let allocation = match Self::allocate(allocator, buffer, memory_property_flags, preferred_memory_property_flags, dedicated){
+            Ok(allocation)=>{allocation}
+            Err(error)=>{
+                allocator.device.destroy_buffer(buffer, None);
+                return Err(error);
+            }
+        };
+        allocator.set_allocation_name(&allocation, name);
+        allocator.set_object_name(buffer, name).ok();
+        return Ok(Self{
+            buffer,allocation,size:buffer_create_info.size,usage:buffer_create_info.usage,
+        })
+    }
+    unsafe fn allocate(allocator : &mut Allocator, buffer : Buffer, memory_property_flags : MemoryPropertyFlags, preferred_memory_property_flags : MemoryPropertyFlags, dedicated : bool) -> RendererResult<MemoryRegionPointer>{
+        let (requirements, prefers_dedicated) = allocator.get_buffer_requirements(buffer);
+        let memory_property_flags = allocator.get_preferred_memory_property_flags(memory_property_flags, preferred_memory_property_flags, requirements.memory_type_bits);
+        let allocation = if dedicated || prefers_dedicated || allocator.prefers_dedicated_allocation(requirements.size, memory_property_flags, requirements.memory_type_bits){
+            allocator.create_dedicated_allocation(requirements.size, memory_property_flags, requirements.memory_type_bits, DedicatedResource::Buffer(buffer))?
+        }
+        else{
+            allocator.create_allocation(requirements.size, requirements.alignment, ResourceKind::Linear, memory_property_flags, requirements.memory_type_bits)?
+        };
+        let map_data = allocator.get_memory_map_data(&allocation);
+        if let Err(error) = allocator.device.bind_buffer_memory(buffer, map_data.memory, map_data.offset){
+            allocator.destroy_allocation(&allocation);
+            return Err(error.into());
         }
+        return Ok(allocation);
     }
     pub unsafe fn destroy(&self, allocator : &mut Allocator){
         allocator.destroy_allocation(&self.allocation);
@@ -0,0 +1,164 @@
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use super::{Allocator, memory_backend::MemoryBackend, memory_block::MemoryBlock};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct MemoryStats{
+    pub block_count : usize,
+    pub reserved_bytes : u64,
+    pub used_bytes : u64,
+    pub allocation_count : usize,
+    pub largest_free_range : u64,
+    pub fragmentation : f32,
+    pub dedicated_allocation_count : usize,
+    pub dedicated_bytes : u64,
+}
+impl MemoryStats{
+    fn add_block(&mut self, block : &MemoryBlock){
+        if block.is_dedicated(){
+            self.dedicated_allocation_count += 1;
+            self.dedicated_bytes += block.get_size();
+            return;
+        }
+        self.block_count += 1;
+        self.reserved_bytes += block.get_size();
+        self.used_bytes += block.get_used_size();
+        self.allocation_count += block.get_allocation_count();
+        self.largest_free_range = self.largest_free_range.max(block.get_largest_free_range());
+        self.update_fragmentation();
+    }
+    fn update_fragmentation(&mut self){
+        let free_bytes = self.reserved_bytes - self.used_bytes;
+        self.fragmentation = if free_bytes == 0{0.0}else{1.0 - self.largest_free_range as f32 / free_bytes as f32};
+    }
+    fn to_json(&self) -> Value{
+        return json!({
+            "BlockCount" : self.block_count + self.dedicated_allocation_count,
+            "BlockBytes" : self.reserved_bytes + self.dedicated_bytes,
+            "AllocationCount" : self.allocation_count + self.dedicated_allocation_count,
+            "AllocationBytes" : self.used_bytes + self.dedicated_bytes,
+            "UnusedRangeSizeMax" : self.largest_free_range,
+        });
+    }
+}
+#[derive(Clone, Debug, Serialize)]
+pub struct MemoryTypeStats{
+    pub memory_type : u32,
+    pub heap : u32,
+    pub property_flags : u32,
+    pub stats : MemoryStats,
+}
+#[derive(Clone, Debug, Serialize)]
+pub struct HeapStats{
+    pub heap : u32,
+    pub size : u64,
+    pub budget : Option<u64>,
+    pub stats : MemoryStats,
+}
+#[derive(Clone, Debug, Serialize)]
+pub struct AllocatorStats{
+    pub memory_types : Vec<MemoryTypeStats>,
+    pub heaps : Vec<HeapStats>,
+    pub total : MemoryStats,
+}
+impl<B : MemoryBackend> Allocator<B>{
+    pub fn get_stats(&self) -> AllocatorStats{
+        let properties = &self.physical_device_memory_properties;
+        let mut memory_types = properties.memory_types[..properties.memory_type_count as usize].iter().enumerate().map(|(i, memory_type)|MemoryTypeStats{
+            memory_type : i as u32,
+            heap : memory_type.heap_index,
+            property_flags : memory_type.property_flags.as_raw(),
+            stats : MemoryStats::default(),
+        }).collect::<Vec<_>>();
+        let mut heaps = properties.memory_heaps[..properties.memory_heap_count as usize].iter().enumerate().map(|(i, heap)|HeapStats{
+            heap : i as u32,
+            size : heap.size,
+            budget : self.heap_budgets[i],
+            stats : MemoryStats::default(),
+        }).collect::<Vec<_>>();
+        let mut total = MemoryStats::default();
+        for block in self.blocks.iter().flatten(){
+            let memory_type = &mut memory_types[block.get_memory_type() as usize];
+            memory_type.stats.add_block(block);
+            heaps[memory_type.heap as usize].stats.add_block(block);
+            total.add_block(block);
+        }
+        return AllocatorStats{memory_types,heaps,total};
+    }
+    pub fn get_stats_json(&self) -> String{
+        let stats = self.get_stats();
+        let mut memory_info = Map::new();
+        for heap in stats.heaps.iter(){
+            let mut memory_pools = Map::new();
+            for memory_type in stats.memory_types.iter().filter(|memory_type|memory_type.heap == heap.heap){
+                memory_pools.insert(format!("Type {}", memory_type.memory_type), json!({
+                    "Flags" : memory_property_names(memory_type.property_flags),
+                    "Stats" : memory_type.stats.to_json(),
+                }));
+            }
+            let heap_flags = self.physical_device_memory_properties.memory_heaps[heap.heap as usize].flags;
+            memory_info.insert(format!("Heap {}", heap.heap), json!({
+                "Flags" : if heap_flags.contains(ash::vk::MemoryHeapFlags::DEVICE_LOCAL){vec!("DEVICE_LOCAL")}else{vec!()},
+                "Size" : heap.size,
+                "Budget" : {
+                    "BudgetBytes" : heap.budget.unwrap_or(heap.size),
+                    "UsageBytes" : self.heap_usage[heap.heap as usize],
+                },
+                "Stats" : heap.stats.to_json(),
+                "MemoryPools" : memory_pools,
+            }));
+        }
+        let mut default_pools = Map::new();
+        for memory_type in stats.memory_types.iter(){
+            let mut blocks = Map::new();
+            let mut dedicated_allocations = vec!();
+            for (i, block) in self.blocks.iter().enumerate(){
+                let block = match block{
+                    Some(block) if block.get_memory_type() == memory_type.memory_type=>{block}
+                    _=>{continue}
+                };
+                if block.is_dedicated(){
+                    let name = block.get_label(0).map_or("", |label|label.name.as_str());
+                    dedicated_allocations.push(if name.is_empty(){json!({"Type" : "UNKNOWN", "Size" : block.get_size()})}else{json!({"Type" : "UNKNOWN", "Size" : block.get_size(), "Name" : name})});
+                    continue;
+                }
+                let mut suballocations = block.regions.iter().enumerate().filter_map(|(i, region)|region.map(|region|(region.offset, region.size, "UNKNOWN", block.get_label(i).map_or("", |label|label.name.as_str())))).collect::<Vec<_>>();
+                suballocations.extend(block.get_free_ranges().iter().map(|range|(range.offset, range.size, "FREE", "")));
+                suballocations.sort_unstable_by_key(|suballocation|suballocation.0);
+                blocks.insert(i.to_string(), json!({
+                    "MapRefs" : 0,
+                    "TotalBytes" : block.get_size(),
+                    "UnusedBytes" : block.get_size() - block.get_used_size(),
+                    "Allocations" : block.get_allocation_count(),
+                    "UnusedRanges" : block.get_free_ranges().len(),
+                    "Suballocations" : suballocations.iter().map(|&(offset, size, kind, name)|{
+                        let mut suballocation = json!({"Offset" : offset, "Type" : kind, "Size" : size});
+                        if !name.is_empty(){suballocation["Name"] = json!(name)}
+                        suballocation
+                    }).collect::<Vec<_>>(),
+                }));
+            }
+            default_pools.insert(format!("Type {}", memory_type.memory_type), json!({
+                "PreferredBlockSize" : self.get_block_size(memory_type.memory_type),
+                "Blocks" : blocks,
+                "DedicatedAllocations" : dedicated_allocations,
+            }));
+        }
+        let document = json!({
+            "General" : {
+                "API" : "Vulkan",
+                "memoryHeapCount" : stats.heaps.len(),
+                "memoryTypeCount" : stats.memory_types.len(),
+            },
+            "Total" : stats.total.to_json(),
+            "MemoryInfo" : memory_info,
+            "DefaultPools" : default_pools,
+        });
+        return serde_json::to_string_pretty(&document).unwrap();
+    }
+}
+fn memory_property_names(property_flags : u32) -> Vec<&'static str>{
+    let names = [(1, "DEVICE_LOCAL"), (2, "HOST_VISIBLE"), (4, "HOST_COHERENT"), (8, "HOST_CACHED"), (16, "LAZILY_ALLOCATED"), (32, "PROTECTED")];
+    return names.iter().filter(|(bit, _)|property_flags & bit != 0).map(|(_, name)|*name).collect();
+}
@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+
+use ash::vk::{PresentModeKHR, SurfaceFormatKHR, Format, ColorSpaceKHR};
+use serde::{Serialize, Deserialize};
+
+use crate::error::{RendererError, RendererResult};
+
+pub const MIN_FRAME_ARENA_SIZE : u64 = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PresentMode{
+    Immediate,
+    Mailbox,
+    Fifo,
+    FifoRelaxed,
+}
+impl PresentMode{
+    pub fn to_vk(self) -> PresentModeKHR{
+        match self{
+            Self::Immediate=>{PresentModeKHR::IMMEDIATE}
+            Self::Mailbox=>{PresentModeKHR::MAILBOX}
+            Self::Fifo=>{PresentModeKHR::FIFO}
+            Self::FifoRelaxed=>{PresentModeKHR::FIFO_RELAXED}
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SurfaceFormat{
+    Rgba8Srgb,
+    Bgra8Srgb,
+    Rgba8Unorm,
+    Bgra8Unorm,
+}
+impl SurfaceFormat{
+    pub fn to_vk(self) -> SurfaceFormatKHR{
+        let format = match self{
+            Self::Rgba8Srgb=>{Format::R8G8B8A8_SRGB}
+            Self::Bgra8Srgb=>{Format::B8G8R8A8_SRGB}
+            Self::Rgba8Unorm=>{Format::R8G8B8A8_UNORM}
+            Self::Bgra8Unorm=>{Format::B8G8R8A8_UNORM}
+        };
+        return SurfaceFormatKHR{format, color_space : ColorSpaceKHR::SRGB_NONLINEAR};
+    }
+}
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceSelection{
+    Discrete,
+    Integrated,
+    Index(usize),
+    Name(String),
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeapBudget{
+    pub heap : u32,
+    pub size : u64,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraSensitivity{
+    pub rotate : f32,
+    pub pan : f32,
+    pub zoom : f32,
+    pub fly_speed : f32,
+    pub fly_fast_multiplier : f32,
+}
+impl Default for CameraSensitivity{
+    fn default() -> Self{
+        return Self{
+            rotate : 0.005,
+            pan : 1.0,
+            zoom : 0.1,
+            fly_speed : 2.0,
+            fly_fast_multiplier : 4.0,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GizmoSnap{
+    pub translate : f32,
+    pub rotate : f32,
+    pub scale : f32,
+}
+impl Default for GizmoSnap{
+    fn default() -> Self{
+        return Self{
+            translate : 0.25,
+            rotate : 15.0,
+            scale : 0.1,
+        }
+    }
+}
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RendererConfig{
+    pub window_title : String,
+    pub window_size : (u32, u32),
+    pub present_modes : Vec<PresentMode>,
+    pub surface_formats : Vec<SurfaceFormat>,
+    pub max_frames_in_flight : usize,
+    pub pipeline_cache : Option<PathBuf>,
+    pub device : DeviceSelection,
+    pub validation : bool,
+    pub best_practices_validation : bool,
+    pub synchronization_validation : bool,
+    pub heap_budgets : Vec<HeapBudget>,
+    pub empty_blocks_kept : usize,
+    pub frame_arena_size : u64,
+    pub camera_sensitivity : CameraSensitivity,
+    pub camera_transition_time : f32,
+    pub auto_clip_planes : bool,
+    pub reverse_z : bool,
+    pub id_buffer : bool,
+    pub gizmo_snap : GizmoSnap,
+}
+impl Default for RendererConfig{
+    fn default() -> Self{
+        return Self{
+            window_title : String::from("Drawr"),
+            window_size : (800, 600),
+            present_modes : vec!(PresentMode::Mailbox, PresentMode::Fifo),
+            surface_formats : vec!(SurfaceFormat::Rgba8Srgb, SurfaceFormat::Bgra8Srgb),
+            max_frames_in_flight : 2,
+            pipeline_cache : Some(PathBuf::from("pipeline.cache")),
+            device : DeviceSelection::Discrete,
+            validation : false,
+            best_practices_validation : false,
+            synchronization_validation : false,
+            heap_budgets : vec!(),
+            empty_blocks_kept : 1,
+            frame_arena_size : 4 * 1024 * 1024,
+            camera_sensitivity : CameraSensitivity::default(),
+            camera_transition_time : 0.3,
+            auto_clip_planes : true,
+            reverse_z : false,
+            id_buffer : false,
+            gizmo_snap : GizmoSnap::default(),
+        }
+    }
+}
+impl RendererConfig{
+    pub fn builder() -> RendererConfigBuilder{
+        return RendererConfigBuilder{config : Self::default()};
+    }
+    pub fn load(path : &Path) -> RendererResult<Self>{
+        let contents = std::fs::read_to_string(path)?;
+        let config : Self = match path.extension().and_then(|extension|extension.to_str()){
+            Some("ron")=>{ron::from_str(&contents).map_err(|error|RendererError::Config(format!("{}: {}", path.display(), error)))?}
+            _=>{toml::from_str(&contents).map_err(|error|RendererError::Config(format!("{}: {}", path.display(), error)))?}
+        };
+        config.validate()?;
+        return Ok(config);
+    }
+    pub fn validate(&self) -> RendererResult<()>{
+        if self.window_size.0 == 0 || self.window_size.1 == 0{return Err(RendererError::Config(String::from("window_size must be non-zero")))}
+        if self.max_frames_in_flight == 0{return Err(RendererError::Config(String::from("max_frames_in_flight must be at least 1")))}
+        if self.present_modes.is_empty(){return Err(RendererError::Config(String::from("present_modes must not be empty")))}
+        if self.surface_formats.is_empty(){return Err(RendererError::Config(String::from("surface_formats must not be empty")))}
+        if !(self.camera_sensitivity.zoom >= 0.0 && self.camera_sensitivity.zoom < 1.0){return Err(RendererError::Config(String::from("camera_sensitivity.zoom must be in [0, 1)")))}
+        if !(self.camera_transition_time >= 0.0){return Err(RendererError::Config(String::from("camera_transition_time must not be negative")))}
+        if !(self.gizmo_snap.translate > 0.0 && self.gizmo_snap.rotate > 0.0 && self.gizmo_snap.scale > 0.0){return Err(RendererError::Config(String::from("gizmo_snap increments must be positive")))}
+        if self.frame_arena_size < MIN_FRAME_ARENA_SIZE{return Err(RendererError::Config(format!("frame_arena_size must be at least {} bytes", MIN_FRAME_ARENA_SIZE)))}
+        return Ok(());
+    }
+    pub fn get_pipeline_cache_path(&self) -> RendererResult<Option<PathBuf>>{
+        let path = match &self.pipeline_cache{
+            Some(path)=>{path}
+            None=>{return Ok(None)}
+        };
+        if path.is_absolute(){return Ok(Some(path.clone()))}
+        let executable = std::env::current_exe()?;
+        let mut file = executable.parent().ok_or_else(||RendererError::Config(format!("executable path {} has no parent directory", executable.display())))?.to_path_buf();
+        file.push(path);
+        return Ok(Some(file));
+    }
+}
+pub struct RendererConfigBuilder{
+    config : RendererConfig,
+}
+impl RendererConfigBuilder{
+    pub fn window_title(mut self, title : &str) -> Self{
+        self.config.window_title = String::from(title);
+        return self;
+    }
+    pub fn window_size(mut self, width : u32, height : u32) -> Self{
+        self.config.window_size = (width, height);
+        return self;
+    }
+    pub fn present_modes(mut self, present_modes : Vec<PresentMode>) -> Self{
+        self.config.present_modes = present_modes;
+        return self;
+    }
+    pub fn surface_formats(mut self, surface_formats : Vec<SurfaceFormat>) -> Self{
+        self.config.surface_formats = surface_formats;
+        return self;
+    }
+    pub fn max_frames_in_flight(mut self, max_frames_in_flight : usize) -> Self{
+        self.config.max_frames_in_flight = max_frames_in_flight;
+        return self;
+    }
+    pub fn pipeline_cache(mut self, path : Option<PathBuf>) -> Self{
+        self.config.pipeline_cache = path;
+        return self;
+    }
+    pub fn device(mut self, device : DeviceSelection) -> Self{
+        self.config.device = device;
+        return self;
+    }
+    pub fn validation(mut self, validation : bool) -> Self{
+        self.config.validation = validation;
+        return self;
+    }
+    pub fn best_practices_validation(mut self, enabled : bool) -> Self{
+        self.config.best_practices_validation = enabled;
+        return self;
+    }
+    pub fn synchronization_validation(mut self, enabled : bool) -> Self{
+        self.config.synchronization_validation = enabled;
+        return self;
+    }
+    pub fn heap_budget(mut self, heap : u32, size : u64) -> Self{
+        self.config.heap_budgets.retain(|budget|budget.heap != heap);
+        self.config.heap_budgets.push(HeapBudget{heap, size});
+        return self;
+    }
+    pub fn empty_blocks_kept(mut self, empty_blocks_kept : usize) -> Self{
+        self.config.empty_blocks_kept = empty_blocks_kept;
+        return self;
+    }
+    pub fn frame_arena_size(mut self, frame_arena_size : u64) -> Self{
+        self.config.frame_arena_size = frame_arena_size;
+        return self;
+    }
+    pub fn camera_sensitivity(mut self, camera_sensitivity : CameraSensitivity) -> Self{
+        self.config.camera_sensitivity = camera_sensitivity;
+        return self;
+    }
+    pub fn camera_transition_time(mut self, camera_transition_time : f32) -> Self{
+        self.config.camera_transition_time = camera_transition_time;
+        return self;
+    }
+    pub fn auto_clip_planes(mut self, auto_clip_planes : bool) -> Self{
+        self.config.auto_clip_planes = auto_clip_planes;
+        return self;
+    }
+    pub fn reverse_z(mut self, reverse_z : bool) -> Self{
+        self.config.reverse_z = reverse_z;
+        return self;
+    }
+    pub fn id_buffer(mut self, id_buffer : bool) -> Self{
+        self.config.id_buffer = id_buffer;
+        return self;
+    }
+    pub fn gizmo_snap(mut self, gizmo_snap : GizmoSnap) -> Self{
+        self.config.gizmo_snap = gizmo_snap;
+        return self;
+    }
+    pub fn build(self) -> RendererResult<RendererConfig>{
+        self.config.validate()?;
+        return Ok(self.config);
+    }
+}
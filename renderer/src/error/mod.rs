@@ -0,0 +1,72 @@
+use std::fmt::{Display, Formatter};
+
+use ash::{vk, LoadingError};
+use winit::error::OsError;
+
+pub type RendererResult<T> = Result<T, RendererError>;
+
+#[derive(Debug)]
+pub enum RendererError{
+    Vulkan(vk::Result),
+    Loading(LoadingError),
+    Window(OsError),
+    Io(std::io::Error),
+    ShaderLoad(String, std::io::Error),
+    NoDevice,
+    NoMemoryType,
+    NotMapped,
+    OutOfBudget(u32),
+    FrameArenaExhausted(u64),
+    NoFormat(&'static str),
+    Config(String),
+    MeshParse(usize, String),
+    ThreadPool(rayon::ThreadPoolBuildError),
+    RenderThreadStopped,
+}
+impl Display for RendererError{
+    fn fmt(&self, f : &mut Formatter<'_>) -> std::fmt::Result{
+        match self{
+            Self::Vulkan(result)=>{write!(f, "Vulkan call failed: {}", result)}
+            Self::Loading(error)=>{write!(f, "Failed to load Vulkan drivers: {}", error)}
+            Self::Window(error)=>{write!(f, "Failed to create render window: {}", error)}
+            Self::Io(error)=>{write!(f, "IO error: {}", error)}
+            Self::ShaderLoad(shader, error)=>{write!(f, "Failed to load shader {}: {}", shader, error)}
+            Self::NoDevice=>{write!(f, "No Vulkan compatible device found")}
+            Self::NoMemoryType=>{write!(f, "Requested unsupported memory type")}
+            Self::NotMapped=>{write!(f, "Allocation is not host visible")}
+            Self::OutOfBudget(heap)=>{write!(f, "Memory heap {} is over budget", heap)}
+            Self::FrameArenaExhausted(size)=>{write!(f, "Frame arena has no room for {} bytes, increase frame_arena_size", size)}
+            Self::NoFormat(usage)=>{write!(f, "No supported {} format", usage)}
+            Self::Config(message)=>{write!(f, "Invalid renderer config: {}", message)}
+            Self::MeshParse(line, message)=>{write!(f, "Failed to parse mesh at line {}: {}", line, message)}
+            Self::ThreadPool(error)=>{write!(f, "Failed to create render thread pool: {}", error)}
+            Self::RenderThreadStopped=>{write!(f, "The render thread has stopped")}
+        }
+    }
+}
+impl std::error::Error for RendererError{}
+impl From<vk::Result> for RendererError{
+    fn from(result : vk::Result) -> Self{
+        return Self::Vulkan(result);
+    }
+}
+impl From<LoadingError> for RendererError{
+    fn from(error : LoadingError) -> Self{
+        return Self::Loading(error);
+    }
+}
+impl From<OsError> for RendererError{
+    fn from(error : OsError) -> Self{
+        return Self::Window(error);
+    }
+}
+impl From<std::io::Error> for RendererError{
+    fn from(error : std::io::Error) -> Self{
+        return Self::Io(error);
+    }
+}
+impl From<rayon::ThreadPoolBuildError> for RendererError{
+    fn from(error : rayon::ThreadPoolBuildError) -> Self{
+        return Self::ThreadPool(error);
+    }
+}
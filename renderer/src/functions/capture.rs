@@ -0,0 +1,180 @@
+use std::{path::Path, io::Write};
+
+use ash::{Device, vk::{Image, ImageLayout, Format, Extent2D, CommandPool, Queue, CommandBufferUsageFlags, ImageMemoryBarrier, StructureType, AccessFlags, QUEUE_FAMILY_IGNORED, ImageSubresourceRange, ImageAspectFlags, PipelineStageFlags, DependencyFlags, BufferImageCopy, ImageSubresourceLayers, Offset3D, Extent3D, SubmitInfo, Fence, Buffer, CommandBuffer}};
+
+use crate::{allocator::{Allocator, BufferAndAllocation}, error::{RendererError, RendererResult}};
+
+pub struct ImageData{
+    pub width : u32,
+    pub height : u32,
+    pub pixels : Vec<u8>,
+}
+impl ImageData{
+    pub fn save(&self, path : &Path) -> std::io::Result<()>{
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        match path.extension().and_then(|extension|extension.to_str()){
+            Some("png")=>{self.write_png(file)}
+            _=>{self.write_ppm(file)}
+        }
+    }
+    fn write_png<W : Write>(&self, file : W) -> std::io::Result<()>{
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        return Ok(());
+    }
+    fn write_ppm<W : Write>(&self, mut file : W) -> std::io::Result<()>{
+        write!(file, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for pixel in self.pixels.chunks_exact(4){
+            file.write_all(&pixel[0..3])?;
+        }
+        return file.flush();
+    }
+}
+pub fn get_swizzle(format : Format) -> RendererResult<bool>{
+    match format{
+        Format::R8G8B8A8_SRGB | Format::R8G8B8A8_UNORM=>{return Ok(false)}
+        Format::B8G8R8A8_SRGB | Format::B8G8R8A8_UNORM=>{return Ok(true)}
+        _=>{return Err(RendererError::NoFormat("screenshot"))}
+    }
+}
+pub unsafe fn record_image_copy(device : &Device, command_pool : CommandPool, image : Image, layout : ImageLayout, extent : Extent2D, buffer : Buffer) -> RendererResult<Vec<CommandBuffer>>{
+    let subresource_range = ImageSubresourceRange{
+        aspect_mask : ImageAspectFlags::COLOR,
+        base_array_layer : 0,
+        base_mip_level : 0,
+        layer_count : 1,
+        level_count : 1,
+    };
+    let to_transfer = [
+        ImageMemoryBarrier{
+            s_type : StructureType::IMAGE_MEMORY_BARRIER,
+            p_next : std::ptr::null(),
+            src_access_mask : AccessFlags::COLOR_ATTACHMENT_WRITE,
+            dst_access_mask : AccessFlags::TRANSFER_READ,
+            old_layout : layout,
+            new_layout : ImageLayout::TRANSFER_SRC_OPTIMAL,
+            src_queue_family_index : QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index : QUEUE_FAMILY_IGNORED,
+            image,
+            subresource_range,
+        }
+    ];
+    let to_original = [
+        ImageMemoryBarrier{
+            s_type : StructureType::IMAGE_MEMORY_BARRIER,
+            p_next : std::ptr::null(),
+            src_access_mask : AccessFlags::TRANSFER_READ,
+            dst_access_mask : AccessFlags::empty(),
+            old_layout : ImageLayout::TRANSFER_SRC_OPTIMAL,
+            new_layout : layout,
+            src_queue_family_index : QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index : QUEUE_FAMILY_IGNORED,
+            image,
+            subresource_range,
+        }
+    ];
+    let regions = [
+        BufferImageCopy{
+            buffer_offset : 0,
+            buffer_row_length : 0,
+            buffer_image_height : 0,
+            image_subresource : ImageSubresourceLayers{
+                aspect_mask : ImageAspectFlags::COLOR,
+                mip_level : 0,
+                base_array_layer : 0,
+                layer_count : 1,
+            },
+            image_offset : Offset3D{x:0,y:0,z:0},
+            image_extent : Extent3D{width:extent.width,height:extent.height,depth:1},
+        }
+    ];
+    let command_buffer = super::command::create_command_buffers(device, command_pool, 1, false)?;
+    super::command::begin_primary_command_buffers(device, &command_buffer, CommandBufferUsageFlags::ONE_TIME_SUBMIT)?;
+    device.cmd_pipeline_barrier(command_buffer[0], PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, PipelineStageFlags::TRANSFER, DependencyFlags::empty(), &[], &[], &to_transfer);
+    device.cmd_copy_image_to_buffer(command_buffer[0], image, ImageLayout::TRANSFER_SRC_OPTIMAL, buffer, &regions);
+    device.cmd_pipeline_barrier(command_buffer[0], PipelineStageFlags::TRANSFER, PipelineStageFlags::BOTTOM_OF_PIPE, DependencyFlags::empty(), &[], &[], &to_original);
+    super::command::end_command_buffers(device, &command_buffer)?;
+    return Ok(command_buffer);
+}
+pub unsafe fn read_pixels(allocator : &mut Allocator, readback_buffer : &BufferAndAllocation, format : Format, extent : Extent2D) -> RendererResult<ImageData>{
+    let swizzle = get_swizzle(format)?;
+    let size = extent.width as u64 * extent.height as u64 * 4;
+    allocator.invalidate_allocation(&readback_buffer.allocation, 0, size)?;
+    let mut pixels = readback_buffer.allocation.mapped_slice::<u8>(allocator).ok_or(RendererError::NotMapped)?[..size as usize].to_vec();
+    for pixel in pixels.chunks_exact_mut(4){
+        if swizzle{pixel.swap(0, 2)}
+        pixel[3] = 255;
+    }
+    return Ok(ImageData{
+        width : extent.width,
+        height : extent.height,
+        pixels,
+    })
+}
+pub unsafe fn read_id(device : &Device, allocator : &mut Allocator, command_pool : CommandPool, queue : Queue, image : Image, x : u32, y : u32) -> RendererResult<u32>{
+    let readback_buffer = super::buffer::create_readback_buffer(allocator, 4)?;
+    let subresource_range = ImageSubresourceRange{
+        aspect_mask : ImageAspectFlags::COLOR,
+        base_array_layer : 0,
+        base_mip_level : 0,
+        layer_count : 1,
+        level_count : 1,
+    };
+    let to_transfer = [
+        ImageMemoryBarrier{
+            s_type : StructureType::IMAGE_MEMORY_BARRIER,
+            p_next : std::ptr::null(),
+            src_access_mask : AccessFlags::COLOR_ATTACHMENT_WRITE,
+            dst_access_mask : AccessFlags::TRANSFER_READ,
+            old_layout : ImageLayout::TRANSFER_SRC_OPTIMAL,
+            new_layout : ImageLayout::TRANSFER_SRC_OPTIMAL,
+            src_queue_family_index : QUEUE_FAMILY_IGNORED,
+            dst_queue_family_index : QUEUE_FAMILY_IGNORED,
+            image,
+            subresource_range,
+        }
+    ];
+    let regions = [
+        BufferImageCopy{
+            buffer_offset : 0,
+            buffer_row_length : 0,
+            buffer_image_height : 0,
+            image_subresource : ImageSubresourceLayers{
+                aspect_mask : ImageAspectFlags::COLOR,
+                mip_level : 0,
+                base_array_layer : 0,
+                layer_count : 1,
+            },
+            image_offset : Offset3D{x:x as i32,y:y as i32,z:0},
+            image_extent : Extent3D{width:1,height:1,depth:1},
+        }
+    ];
+    let command_buffer = super::command::create_command_buffers(device, command_pool, 1, false)?;
+    super::command::begin_primary_command_buffers(device, &command_buffer, CommandBufferUsageFlags::ONE_TIME_SUBMIT)?;
+    device.cmd_pipeline_barrier(command_buffer[0], PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, PipelineStageFlags::TRANSFER, DependencyFlags::empty(), &[], &[], &to_transfer);
+    device.cmd_copy_image_to_buffer(command_buffer[0], image, ImageLayout::TRANSFER_SRC_OPTIMAL, readback_buffer.buffer, &regions);
+    super::command::end_command_buffers(device, &command_buffer)?;
+    let submits = [
+        SubmitInfo{
+            s_type : StructureType::SUBMIT_INFO,
+            p_next : std::ptr::null(),
+            command_buffer_count : 1,
+            p_command_buffers : command_buffer.as_ptr(),
+            p_signal_semaphores : std::ptr::null(),
+            p_wait_dst_stage_mask : std::ptr::null(),
+            p_wait_semaphores : std::ptr::null(),
+            signal_semaphore_count : 0,
+            wait_semaphore_count : 0,
+        }
+    ];
+    device.queue_submit(queue, &submits, Fence::null())?;
+    device.queue_wait_idle(queue)?;
+    device.free_command_buffers(command_pool, &command_buffer);
+    allocator.invalidate_allocation(&readback_buffer.allocation, 0, 4)?;
+    let id = readback_buffer.allocation.mapped_slice::<u32>(allocator).ok_or(RendererError::NotMapped)?[0];
+    readback_buffer.destroy(allocator);
+    return Ok(id);
+}
@@ -1,17 +1,17 @@
-use ash::{Device, vk::{CommandPoolCreateFlags, CommandPool, CommandPoolCreateInfo, StructureType, CommandBufferUsageFlags, CommandBuffer, CommandBufferAllocateInfo, CommandBufferLevel, CommandBufferBeginInfo, Pipeline, DescriptorSet, RenderPass, ClearColorValue, ClearValue, RenderPassBeginInfo, Framebuffer, Extent2D, ClearDepthStencilValue, Rect2D, Offset2D, SubpassContents, PipelineBindPoint, PipelineLayout}};
+use ash::{Device, vk::{CommandPoolCreateFlags, CommandPool, CommandPoolCreateInfo, StructureType, CommandBufferUsageFlags, CommandBuffer, CommandBufferAllocateInfo, CommandBufferLevel, CommandBufferBeginInfo, Pipeline, DescriptorSet, RenderPass, ClearValue, RenderPassBeginInfo, Framebuffer, Extent2D, Rect2D, Offset2D, SubpassContents, PipelineBindPoint, PipelineLayout, Buffer, IndexType}};
 
-use crate::allocator::BufferAndAllocation;
+use crate::{allocator::BufferAndAllocation, mesh::{Mesh, MeshHandle}, error::RendererResult};
 
-pub unsafe fn create_command_pool(device : &Device, queue_family_index : u32) -> CommandPool{
+pub unsafe fn create_command_pool(device : &Device, queue_family_index : u32) -> RendererResult<CommandPool>{
     let command_pool_create_info = CommandPoolCreateInfo{
         s_type : StructureType::COMMAND_POOL_CREATE_INFO,
         p_next : std::ptr::null(),
-        flags : CommandPoolCreateFlags::empty(),
+        flags : CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
         queue_family_index,
     };
-    return device.create_command_pool(&command_pool_create_info, None).expect("Failed to create command pool");
+    return Ok(device.create_command_pool(&command_pool_create_info, None)?);
 }
-pub unsafe fn create_command_buffers(device : &Device, command_pool : CommandPool, count : u32, secondary : bool) -> Vec<CommandBuffer>{
+pub unsafe fn create_command_buffers(device : &Device, command_pool : CommandPool, count : u32, secondary : bool) -> RendererResult<Vec<CommandBuffer>>{
     let allocate_info = CommandBufferAllocateInfo{
         s_type : StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
         p_next : std::ptr::null(),
@@ -19,9 +19,9 @@ pub unsafe fn create_command_buffers(device : &Device, command_pool : CommandPoo
         command_buffer_count : count,
         command_pool,
     };
-    return device.allocate_command_buffers(&allocate_info).expect("Failed to allocate command buffers");
+    return Ok(device.allocate_command_buffers(&allocate_info)?);
 }
-pub unsafe fn begin_primary_command_buffers(device : &Device, buffers : &Vec<CommandBuffer>, flags : CommandBufferUsageFlags){
+pub unsafe fn begin_primary_command_buffers(device : &Device, buffers : &[CommandBuffer], flags : CommandBufferUsageFlags) -> RendererResult<()>{
     let begin_info = CommandBufferBeginInfo{
         s_type : StructureType::COMMAND_BUFFER_BEGIN_INFO,
         p_next : std::ptr::null(),
@@ -29,20 +29,18 @@ pub unsafe fn begin_primary_command_buffers(device : &Device, buffers : &Vec<Com
         p_inheritance_info : std::ptr::null(),
     };
     for &command_buffer in buffers.iter(){
-        device.begin_command_buffer(command_buffer, &begin_info).expect("Failed to begin command buffers");
+        device.begin_command_buffer(command_buffer, &begin_info)?;
     }
+    return Ok(());
 }
-pub unsafe fn end_command_buffers(device : &Device, buffers : &Vec<CommandBuffer>){
+pub unsafe fn end_command_buffers(device : &Device, buffers : &[CommandBuffer]) -> RendererResult<()>{
     for &command_buffer in buffers.iter(){
-        device.end_command_buffer(command_buffer).expect("Failed to finish command buffer recording");
+        device.end_command_buffer(command_buffer)?;
     }
+    return Ok(());
 }
-pub unsafe fn begin_render_pass(device : &Device, command_buffers : &Vec<CommandBuffer>, render_pass : RenderPass, framebuffers : &Vec<Framebuffer>,extent : Extent2D, secondary : bool){
+pub unsafe fn begin_render_pass(device : &Device, command_buffers : &[CommandBuffer], render_pass : RenderPass, framebuffers : &[Framebuffer], extent : Extent2D, clear_values : &[ClearValue], secondary : bool){
     for (i,&command_buffer) in command_buffers.iter().enumerate(){
-        let clear_values = [
-            ClearValue{color:ClearColorValue{float32:[1.0,1.0,1.0,0.0]}},
-            ClearValue{depth_stencil:ClearDepthStencilValue{depth:1.0,stencil:0}},
-        ];
         let render_area = Rect2D{
             offset : Offset2D{x:0,y:0},
             extent,
@@ -59,30 +57,63 @@ pub unsafe fn begin_render_pass(device : &Device, command_buffers : &Vec<Command
         device.cmd_begin_render_pass(command_buffer, &begin_info, if secondary{SubpassContents::SECONDARY_COMMAND_BUFFERS}else{SubpassContents::INLINE});
     }
 }
-pub unsafe fn end_render_pass(device : &Device, command_buffers : &Vec<CommandBuffer>){
+pub unsafe fn end_render_pass(device : &Device, command_buffers : &[CommandBuffer]){
     for &command_buffer in command_buffers.iter(){
         device.cmd_end_render_pass(command_buffer);
     }
 }
-pub unsafe fn create_drawing_command_buffers(device : &Device, command_pool : CommandPool,pipeline_layout : PipelineLayout, pipelines : &Vec<Pipeline>, render_pass : RenderPass, framebuffers : &Vec<Framebuffer>, descriptor_sets : &Vec<DescriptorSet>, vertex_buffers : &Vec<(u32,BufferAndAllocation)>, extent : Extent2D) -> Vec<CommandBuffer>{
-    let command_buffers = create_command_buffers(device, command_pool, descriptor_sets.len() as u32, false);
-    begin_primary_command_buffers(device, &command_buffers, CommandBufferUsageFlags::empty());
-    begin_render_pass(device, &command_buffers, render_pass, framebuffers, extent, false);
-    
-    for (i,&command_buffer) in command_buffers.iter().enumerate(){
-        device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::GRAPHICS, pipelines[0]);
-        let vertex_buffers_pass = [vertex_buffers[0].1.buffer, vertex_buffers[2].1.buffer];
-        device.cmd_bind_vertex_buffers(command_buffer, 0, &vertex_buffers_pass, &[0,0]);
-        device.cmd_bind_descriptor_sets(command_buffer, PipelineBindPoint::GRAPHICS, pipeline_layout, 0, &[descriptor_sets[i]], &[]);
-        device.cmd_draw(command_buffer, vertex_buffers[0].0, vertex_buffers[2].0, 0, 0);
-        device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::GRAPHICS, pipelines[1]);
-        let vertex_buffers_pass = [vertex_buffers[1].1.buffer];
-        device.cmd_bind_vertex_buffers(command_buffer, 0, &vertex_buffers_pass, &[0]);
-        device.cmd_bind_descriptor_sets(command_buffer, PipelineBindPoint::GRAPHICS, pipeline_layout, 0, &[descriptor_sets[i]], &[]);
-        device.cmd_draw(command_buffer, vertex_buffers[1].0, 1, 0, 0);
+pub struct DrawInputs<'a>{
+    pub pipeline_layout : PipelineLayout,
+    pub pipelines : &'a [Pipeline],
+    pub render_pass : RenderPass,
+    pub descriptor_set : DescriptorSet,
+    pub uniform_offset : u32,
+    pub vertex_buffers : &'a [(u32,BufferAndAllocation)],
+    pub instance_buffer : Buffer,
+    pub selection_buffer : (u32, Buffer),
+    pub gizmo_lines : Option<(u32, Buffer, u64)>,
+    pub meshes : &'a [Mesh],
+    pub batches : &'a [(MeshHandle, u32, u32)],
+    pub selection_batches : &'a [(MeshHandle, u32, u32)],
+    pub extent : Extent2D,
+    pub clear_values : &'a [ClearValue],
+    pub outline : bool,
+}
+pub unsafe fn record_drawing_commands(device : &Device, command_buffer : CommandBuffer, framebuffer : Framebuffer, inputs : &DrawInputs){
+    let DrawInputs{pipeline_layout, pipelines, render_pass, descriptor_set, uniform_offset, vertex_buffers, instance_buffer, selection_buffer, gizmo_lines, meshes, batches, selection_batches, extent, clear_values, outline} = *inputs;
+    begin_render_pass(device, &[command_buffer], render_pass, &[framebuffer], extent, clear_values, false);
+    device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::GRAPHICS, pipelines[0]);
+    device.cmd_bind_descriptor_sets(command_buffer, PipelineBindPoint::GRAPHICS, pipeline_layout, 0, &[descriptor_set], &[uniform_offset]);
+    draw_batches(device, command_buffer, &vertex_buffers[0], meshes, instance_buffer, batches, 0);
+    device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::GRAPHICS, pipelines[1]);
+    device.cmd_bind_vertex_buffers(command_buffer, 0, &[vertex_buffers[1].1.buffer], &[0]);
+    device.cmd_draw(command_buffer, vertex_buffers[1].0, 1, 0, 0);
+    let selected = selection_buffer.0 / 2;
+    if outline && selected > 0{
+        device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::GRAPHICS, pipelines[2]);
+        draw_batches(device, command_buffer, &vertex_buffers[0], meshes, selection_buffer.1, selection_batches, 0);
+        device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::GRAPHICS, pipelines[3]);
+        draw_batches(device, command_buffer, &vertex_buffers[0], meshes, selection_buffer.1, selection_batches, selected);
     }
-
-    end_render_pass(device, &command_buffers);
-    end_command_buffers(device, &command_buffers);
-    return command_buffers;
-}
\ No newline at end of file
+    if let Some((count, buffer, offset)) = gizmo_lines{
+        device.cmd_bind_pipeline(command_buffer, PipelineBindPoint::GRAPHICS, pipelines[4]);
+        device.cmd_bind_vertex_buffers(command_buffer, 0, &[buffer], &[offset]);
+        device.cmd_draw(command_buffer, count, 1, 0, 0);
+    }
+    end_render_pass(device, &[command_buffer]);
+}
+unsafe fn draw_batches(device : &Device, command_buffer : CommandBuffer, default_mesh : &(u32,BufferAndAllocation), meshes : &[Mesh], instance_buffer : Buffer, batches : &[(MeshHandle, u32, u32)], instance_offset : u32){
+    for &(mesh, first_instance, instance_count) in batches.iter(){
+        match meshes.iter().find(|loaded|loaded.handle == mesh){
+            Some(loaded)=>{
+                device.cmd_bind_vertex_buffers(command_buffer, 0, &[loaded.vertex_buffer.1.buffer, instance_buffer], &[0,0]);
+                device.cmd_bind_index_buffer(command_buffer, loaded.index_buffer.1.buffer, 0, IndexType::UINT32);
+                device.cmd_draw_indexed(command_buffer, loaded.index_buffer.0, instance_count, 0, 0, first_instance + instance_offset);
+            }
+            None=>{
+                device.cmd_bind_vertex_buffers(command_buffer, 0, &[default_mesh.1.buffer, instance_buffer], &[0,0]);
+                device.cmd_draw(command_buffer, default_mesh.0, instance_count, 0, first_instance + instance_offset);
+            }
+        }
+    }
+}
@@ -1,8 +1,8 @@
-use ash::{Device, vk::{DescriptorSetLayout, DescriptorSetLayoutCreateInfo, StructureType, DescriptorSetLayoutCreateFlags, DescriptorPool, DescriptorPoolCreateInfo, DescriptorPoolCreateFlags, DescriptorPoolSize, DescriptorType, DescriptorSet, DescriptorSetAllocateInfo, DescriptorBufferInfo, PhysicalDeviceLimits, Buffer, WriteDescriptorSet}};
+use ash::{Device, vk::{DescriptorSetLayout, DescriptorSetLayoutCreateInfo, StructureType, DescriptorSetLayoutCreateFlags, DescriptorPool, DescriptorPoolCreateInfo, DescriptorPoolCreateFlags, DescriptorPoolSize, DescriptorType, DescriptorSet, DescriptorSetAllocateInfo, DescriptorBufferInfo, Buffer, WriteDescriptorSet}};
 
-use crate::math::UniformBuffer;
+use crate::{math::UniformBuffer, error::RendererResult};
 
-pub unsafe fn create_descriptor_set_layout(device : &Device) -> DescriptorSetLayout{
+pub unsafe fn create_descriptor_set_layout(device : &Device) -> RendererResult<DescriptorSetLayout>{
     let descriptor_set_layout_binding = UniformBuffer::get_bindings();
     let descriptor_layout_create_info = DescriptorSetLayoutCreateInfo{
         s_type : StructureType::DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
@@ -11,13 +11,13 @@ pub unsafe fn create_descriptor_set_layout(device : &Device) -> DescriptorSetLay
         binding_count : descriptor_set_layout_binding.len() as u32,
         p_bindings : descriptor_set_layout_binding.as_ptr(),
     };
-    return device.create_descriptor_set_layout(&descriptor_layout_create_info, None).expect("Failed to create descriptor set layouts");
+    return Ok(device.create_descriptor_set_layout(&descriptor_layout_create_info, None)?);
 }
-pub unsafe fn create_descriptor_pool(device : &Device, count : u32) -> DescriptorPool{
+pub unsafe fn create_descriptor_pool(device : &Device, count : u32) -> RendererResult<DescriptorPool>{
     let pool_size = [
         DescriptorPoolSize{
             descriptor_count : count,
-            ty : DescriptorType::UNIFORM_BUFFER,
+            ty : DescriptorType::UNIFORM_BUFFER_DYNAMIC,
         }
     ];
     let descriptor_pool_create_info = DescriptorPoolCreateInfo{
@@ -28,42 +28,38 @@ pub unsafe fn create_descriptor_pool(device : &Device, count : u32) -> Descripto
         p_pool_sizes : pool_size.as_ptr(),
         pool_size_count : pool_size.len() as u32,
     };
-    return device.create_descriptor_pool(&descriptor_pool_create_info, None).expect("Failed to create descriptor pool");
+    return Ok(device.create_descriptor_pool(&descriptor_pool_create_info, None)?);
 }
-pub unsafe fn create_descriptor_sets(device : &Device, layout : DescriptorSetLayout, descriptor_pool : DescriptorPool, count : u32, uniform_buffer : Buffer ,device_limits : &PhysicalDeviceLimits) -> Vec<DescriptorSet>{
-    let layouts = vec!(layout;count as usize);
+pub unsafe fn create_descriptor_sets(device : &Device, layout : DescriptorSetLayout, descriptor_pool : DescriptorPool, uniform_buffers : &[Buffer]) -> RendererResult<Vec<DescriptorSet>>{
+    let layouts = vec!(layout;uniform_buffers.len());
     let descriptor_set_allocate_info = DescriptorSetAllocateInfo{
         s_type : StructureType::DESCRIPTOR_SET_ALLOCATE_INFO,
         p_next : std::ptr::null(),
         descriptor_pool,
-        descriptor_set_count : count,
+        descriptor_set_count : layouts.len() as u32,
         p_set_layouts : layouts.as_ptr(),
     };
-    let descriptor_sets = device.allocate_descriptor_sets(&descriptor_set_allocate_info).expect("Failed to allocate descriptor sets");
+    let descriptor_sets = device.allocate_descriptor_sets(&descriptor_set_allocate_info)?;
+    let buffer_infos = uniform_buffers.iter().map(|&buffer|DescriptorBufferInfo{
+        buffer,
+        offset : 0,
+        range : std::mem::size_of::<UniformBuffer>() as u64,
+    }).collect::<Vec<_>>();
     let mut descriptor_writes = vec!();
-    for (i,&descriptor_set) in descriptor_sets.iter().enumerate(){
-        let buffer_object_size = std::mem::size_of::<UniformBuffer>();
-        let alignment_offset = device_limits.min_uniform_buffer_offset_alignment;
-        let alignment_size_increase = alignment_offset - (buffer_object_size as u64 % alignment_offset);
-        let true_buffer_size = buffer_object_size as u64 + alignment_size_increase;
-        let buffer_info = DescriptorBufferInfo{
-            buffer: uniform_buffer,
-            offset : true_buffer_size*i as u64,
-            range : buffer_object_size as u64,
-        };
+    for (descriptor_set, buffer_info) in descriptor_sets.iter().zip(buffer_infos.iter()){
         descriptor_writes.push(WriteDescriptorSet{
             s_type : StructureType::WRITE_DESCRIPTOR_SET,
             p_next : std::ptr::null(),
-            dst_set : descriptor_set,
+            dst_set : *descriptor_set,
             descriptor_count : 1,
-            descriptor_type : DescriptorType::UNIFORM_BUFFER,
+            descriptor_type : DescriptorType::UNIFORM_BUFFER_DYNAMIC,
             dst_array_element : 0,
             dst_binding : 0,
-            p_buffer_info : &buffer_info,
+            p_buffer_info : buffer_info,
             p_image_info : std::ptr::null(),
             p_texel_buffer_view : std::ptr::null(),
         });
     }
     device.update_descriptor_sets(&descriptor_writes, &[]);
-    return descriptor_sets;
-}
\ No newline at end of file
+    return Ok(descriptor_sets);
+}
@@ -1,22 +1,53 @@
-use ash::{Instance, extensions::khr::{Surface, Swapchain}, vk::{SurfaceKHR, PhysicalDevice, QueueFlags, PhysicalDeviceType, DeviceQueueCreateInfo, StructureType, DeviceQueueCreateFlags, PhysicalDeviceFeatures, DeviceCreateInfo, DeviceCreateFlags}, Device};
+use std::ffi::CStr;
 
-pub fn get_device_handle(instance : &Instance, surface_loader : &Surface, surface : &SurfaceKHR) -> PhysicalDevice{
+use ash::{Entry, Instance, extensions::khr::Surface, vk::{InstanceCreateInfo, SurfaceKHR, PhysicalDevice, QueueFlags, PhysicalDeviceType, DeviceQueueCreateInfo, StructureType, DeviceQueueCreateFlags, PhysicalDeviceFeatures, DeviceCreateInfo, DeviceCreateFlags}, Device};
+
+use crate::{error::{RendererError, RendererResult}, config::DeviceSelection};
+
+pub fn is_vulkan_available() -> bool{
+    let entry = match unsafe{Entry::load()}{
+        Ok(entry)=>{entry}
+        Err(_)=>{return false}
+    };
+    let instance = match unsafe{entry.create_instance(&InstanceCreateInfo::default(), None)}{
+        Ok(instance)=>{instance}
+        Err(_)=>{return false}
+    };
+    let mut available = false;
+    for existing_device in unsafe{instance.enumerate_physical_devices()}.unwrap_or_default(){
+        for queue_family in unsafe{instance.get_physical_device_queue_family_properties(existing_device)}.iter(){
+            if queue_family.queue_flags.contains(QueueFlags::GRAPHICS){available = true}
+        }
+    }
+    unsafe{instance.destroy_instance(None)};
+    return available;
+}
+pub fn get_device_handle(instance : &Instance, surface : Option<(&Surface, SurfaceKHR)>, selection : &DeviceSelection) -> RendererResult<PhysicalDevice>{
     let mut fallback_device = None;
-    for existing_device in unsafe{instance.enumerate_physical_devices()}.expect("Failed to get Vulkan devices"){
+    for (index, existing_device) in unsafe{instance.enumerate_physical_devices()}?.into_iter().enumerate(){
         let mut supports_graphics = false;
         let mut supports_compute = false;
-        let mut supports_presentation = false;
+        let mut supports_presentation = surface.is_none();
         for (i, queue_family) in unsafe{instance.get_physical_device_queue_family_properties(existing_device)}.iter().enumerate(){
             if queue_family.queue_flags.contains(QueueFlags::GRAPHICS){supports_graphics=true}
             if queue_family.queue_flags.contains(QueueFlags::COMPUTE){supports_compute=true}
-            if unsafe{surface_loader.get_physical_device_surface_support(existing_device, i as u32, *surface)}.expect("Failed to check device surface support"){supports_presentation=true};
+            if let Some((surface_loader, surface)) = surface{
+                if unsafe{surface_loader.get_physical_device_surface_support(existing_device, i as u32, surface)}?{supports_presentation=true};
+            }
         } 
         if supports_compute && supports_graphics && supports_presentation{
-            if unsafe{instance.get_physical_device_properties(existing_device)}.device_type == PhysicalDeviceType::DISCRETE_GPU{return existing_device}
+            let properties = unsafe{instance.get_physical_device_properties(existing_device)};
+            let device_name = unsafe{CStr::from_ptr(properties.device_name.as_ptr())}.to_string_lossy();
+            match selection{
+                DeviceSelection::Discrete=>{if properties.device_type == PhysicalDeviceType::DISCRETE_GPU{return Ok(existing_device)}}
+                DeviceSelection::Integrated=>{if properties.device_type == PhysicalDeviceType::INTEGRATED_GPU{return Ok(existing_device)}}
+                DeviceSelection::Index(selected)=>{if *selected == index{return Ok(existing_device)} continue}
+                DeviceSelection::Name(name)=>{if device_name.contains(name.as_str()){return Ok(existing_device)} continue}
+            }
             if fallback_device.is_none(){fallback_device = Some(existing_device)};
         }
     }
-    return fallback_device.expect("No Vulkan compatible device found");
+    return fallback_device.ok_or(RendererError::NoDevice);
 }
 pub struct QueueInfo{
     pub graphics_family : u32,
@@ -25,14 +56,17 @@ pub struct QueueInfo{
     pub transfer_family : u32,
 }
 impl QueueInfo{
-    pub fn new(instance : &Instance, surface_loader : &Surface, surface : &SurfaceKHR, physical_device : PhysicalDevice) -> Self{
+    pub fn new(instance : &Instance, surface : Option<(&Surface, SurfaceKHR)>, physical_device : PhysicalDevice) -> RendererResult<Self>{
         let mut graphics_family = None;
         let mut compute_family = None;
         let mut transfer_family = None;
         let mut presentation_family = None;
         for (i, queue_flags) in unsafe{instance.get_physical_device_queue_family_properties(physical_device)}.iter().enumerate(){
             if graphics_family.is_none() && queue_flags.queue_flags.contains(QueueFlags::GRAPHICS){graphics_family = Some(i as u32)}
-            let surface_support = unsafe{surface_loader.get_physical_device_surface_support(physical_device, i as u32, *surface).expect("Failed to get device surface queue support")};
+            let surface_support = match surface{
+                Some((surface_loader, surface))=>{unsafe{surface_loader.get_physical_device_surface_support(physical_device, i as u32, surface)?}}
+                None=>{queue_flags.queue_flags.contains(QueueFlags::GRAPHICS)}
+            };
             if graphics_family.is_some() && graphics_family.unwrap() == i as u32 && surface_support{
                 presentation_family = Some(i as u32)
             }
@@ -42,15 +76,15 @@ impl QueueInfo{
             if transfer_family.is_none() && queue_flags.queue_flags.contains(QueueFlags::TRANSFER){transfer_family = Some(i as u32)}
             else if queue_flags.queue_flags.contains(QueueFlags::TRANSFER) && !queue_flags.queue_flags.contains(QueueFlags::COMPUTE) && !queue_flags.queue_flags.contains(QueueFlags::GRAPHICS){transfer_family = Some(i as u32)}
         }
-        return Self{
-            graphics_family : graphics_family.unwrap(),
-            compute_family : compute_family.unwrap(),
-            presentation_family : presentation_family.unwrap(),
-            transfer_family : transfer_family.unwrap(),
-        }
+        return Ok(Self{
+            graphics_family : graphics_family.ok_or(RendererError::NoDevice)?,
+            compute_family : compute_family.ok_or(RendererError::NoDevice)?,
+            presentation_family : presentation_family.ok_or(RendererError::NoDevice)?,
+            transfer_family : transfer_family.ok_or(RendererError::NoDevice)?,
+        })
     }
 }
-pub unsafe fn create_device(instance : &Instance, physical_device : PhysicalDevice, queue_info : &QueueInfo) -> Device{
+pub unsafe fn create_device(instance : &Instance, physical_device : PhysicalDevice, queue_info : &QueueInfo, extensions : &[&CStr]) -> RendererResult<Device>{
     let priorities = [1.0];
     let mut device_queue_create_infos = vec!(
         DeviceQueueCreateInfo{
@@ -102,7 +136,7 @@ pub unsafe fn create_device(instance : &Instance, physical_device : PhysicalDevi
         wide_lines:1,
         ..Default::default()
     };
-    let enabled_extensions = [Swapchain::name().as_ptr()];
+    let enabled_extensions = extensions.iter().map(|extension|extension.as_ptr()).collect::<Vec<_>>();
     let device_create_info = DeviceCreateInfo{
         s_type : StructureType::DEVICE_CREATE_INFO,
         p_next : std::ptr::null(),
@@ -115,5 +149,11 @@ pub unsafe fn create_device(instance : &Instance, physical_device : PhysicalDevi
         queue_create_info_count : device_queue_create_infos.len() as u32,
         p_queue_create_infos : device_queue_create_infos.as_ptr(),
     };
-    return instance.create_device(physical_device, &device_create_info, None).expect("Failed to create Vulkan device");
-}
\ No newline at end of file
+    return Ok(instance.create_device(physical_device, &device_create_info, None)?);
+}
+pub fn supports_device_extension(instance : &Instance, physical_device : PhysicalDevice, name : &CStr) -> RendererResult<bool>{
+    for extension in unsafe{instance.enumerate_device_extension_properties(physical_device)}?{
+        if unsafe{CStr::from_ptr(extension.extension_name.as_ptr())} == name{return Ok(true)}
+    }
+    return Ok(false);
+}
@@ -1,9 +1,12 @@
 use ash::{vk::{Framebuffer, ImageView, FramebufferCreateInfo, StructureType, FramebufferCreateFlags, RenderPass, Extent2D}, Device};
 
-pub unsafe fn create_framebuffers(device : &Device, swapchain_views : &Vec<ImageView>, depth_image : ImageView, render_pass : RenderPass, extent : Extent2D) -> Vec<Framebuffer>{
+use crate::error::RendererResult;
+
+pub unsafe fn create_framebuffers(device : &Device, swapchain_views : &Vec<ImageView>, depth_image : ImageView, id_image : Option<ImageView>, render_pass : RenderPass, extent : Extent2D) -> RendererResult<Vec<Framebuffer>>{
     let mut framebuffers = vec!();
     for &image in swapchain_views.iter(){
-        let attachments = [image,depth_image];
+        let mut attachments = vec!(image,depth_image);
+        attachments.extend(id_image);
         let framebuffer_create_info = FramebufferCreateInfo{
             s_type : StructureType::FRAMEBUFFER_CREATE_INFO,
             p_next : std::ptr::null(),
@@ -15,7 +18,7 @@ pub unsafe fn create_framebuffers(device : &Device, swapchain_views : &Vec<Image
             height : extent.height,
             layers : 1,
         };
-        framebuffers.push(device.create_framebuffer(&framebuffer_create_info, None).expect("Failed to create framebuffer"));
+        framebuffers.push(device.create_framebuffer(&framebuffer_create_info, None)?);
     }
-    return framebuffers;
+    return Ok(framebuffers);
 }
\ No newline at end of file
@@ -1,8 +1,13 @@
 use ash::{Device, vk::{ImageViewCreateInfo, Image, Format, ImageView, StructureType, ImageViewType, ComponentMapping, ImageSubresourceRange, ImageAspectFlags, ComponentSwizzle, ImageViewCreateFlags, ImageCreateInfo, Extent2D, ImageCreateFlags, Extent3D, ImageType, ImageLayout, SampleCountFlags, SharingMode, ImageTiling, ImageUsageFlags, MemoryPropertyFlags}};
 
-use crate::allocator::{Allocator, ImageAndAllocation};
+use crate::{allocator::{Allocator, ImageAndAllocation}, error::RendererResult};
 
-pub unsafe fn create_swapchain_image_views(device : &Device, images : &Vec<Image>, format : Format) -> Vec<ImageView>{
+pub const ID_FORMAT : Format = Format::R32_UINT;
+
+pub fn has_stencil(format : Format) -> bool{
+    return matches!(format, Format::D16_UNORM_S8_UINT | Format::D24_UNORM_S8_UINT | Format::D32_SFLOAT_S8_UINT | Format::S8_UINT);
+}
+pub unsafe fn create_swapchain_image_views(device : &Device, images : &Vec<Image>, format : Format) -> RendererResult<Vec<ImageView>>{
     let mut views = vec!();
     for &image in images.iter(){
         let image_view_create_info = ImageViewCreateInfo{
@@ -21,11 +26,11 @@ pub unsafe fn create_swapchain_image_views(device : &Device, images : &Vec<Image
                 level_count : 1,
             }
         };
-        views.push(device.create_image_view(&image_view_create_info, None).expect("Failed to create image view"));
+        views.push(device.create_image_view(&image_view_create_info, None)?);
     }
-    return views;
+    return Ok(views);
 }
-pub unsafe fn create_depth_image(device : &Device, allocator : &mut Allocator, extent : Extent2D, format : Format) -> ImageAndView{
+pub unsafe fn create_depth_image(device : &Device, allocator : &mut Allocator, extent : Extent2D, format : Format) -> RendererResult<ImageAndView>{
     let image_create_info = ImageCreateInfo{
         s_type : StructureType::IMAGE_CREATE_INFO,
         p_next : std::ptr::null(),
@@ -43,8 +48,8 @@ pub unsafe fn create_depth_image(device : &Device, allocator : &mut Allocator, e
         tiling : ImageTiling::OPTIMAL,
         usage : ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
     };
-    let image = device.create_image(&image_create_info, None).expect("Failed to create depth image");
-    let image = ImageAndAllocation::new(allocator, image, MemoryPropertyFlags::DEVICE_LOCAL);
+    let image = device.create_image(&image_create_info, None)?;
+    let image = ImageAndAllocation::new_dedicated(allocator, image, ImageTiling::OPTIMAL, MemoryPropertyFlags::DEVICE_LOCAL, "depth image")?;
     let image_view_create_info = ImageViewCreateInfo{
         s_type : StructureType::IMAGE_VIEW_CREATE_INFO,
         p_next : std::ptr::null(),
@@ -54,15 +59,44 @@ pub unsafe fn create_depth_image(device : &Device, allocator : &mut Allocator, e
         format,
         view_type : ImageViewType::TYPE_2D,
         subresource_range : ImageSubresourceRange{
-            aspect_mask : ImageAspectFlags::DEPTH,
+            aspect_mask : if has_stencil(format){ImageAspectFlags::DEPTH | ImageAspectFlags::STENCIL}else{ImageAspectFlags::DEPTH},
             base_array_layer : 0,
             base_mip_level : 0,
             layer_count : 1,
             level_count : 1,
         }
     };
-    let view = device.create_image_view(&image_view_create_info, None).expect("Failed to create image view");
-    return ImageAndView{image,view,}
+    let view = device.create_image_view(&image_view_create_info, None)?;
+    return Ok(ImageAndView{image,view,})
+}
+pub unsafe fn create_color_image(device : &Device, allocator : &mut Allocator, extent : Extent2D, format : Format) -> RendererResult<ImageAndView>{
+    return create_attachment_image(device, allocator, extent, format, "color image");
+}
+pub unsafe fn create_id_image(device : &Device, allocator : &mut Allocator, extent : Extent2D) -> RendererResult<ImageAndView>{
+    return create_attachment_image(device, allocator, extent, ID_FORMAT, "id image");
+}
+unsafe fn create_attachment_image(device : &Device, allocator : &mut Allocator, extent : Extent2D, format : Format, name : &str) -> RendererResult<ImageAndView>{
+    let image_create_info = ImageCreateInfo{
+        s_type : StructureType::IMAGE_CREATE_INFO,
+        p_next : std::ptr::null(),
+        flags : ImageCreateFlags::empty(),
+        array_layers : 1,
+        format,
+        extent : Extent3D{width:extent.width,height:extent.height,depth:1},
+        image_type : ImageType::TYPE_2D,
+        initial_layout : ImageLayout::UNDEFINED,
+        mip_levels : 1,
+        p_queue_family_indices : std::ptr::null(),
+        queue_family_index_count : 0,
+        samples : SampleCountFlags::TYPE_1,
+        sharing_mode : SharingMode::EXCLUSIVE,
+        tiling : ImageTiling::OPTIMAL,
+        usage : ImageUsageFlags::COLOR_ATTACHMENT | ImageUsageFlags::TRANSFER_SRC,
+    };
+    let image = device.create_image(&image_create_info, None)?;
+    let image = ImageAndAllocation::new_dedicated(allocator, image, ImageTiling::OPTIMAL, MemoryPropertyFlags::DEVICE_LOCAL, name)?;
+    let view = create_swapchain_image_views(device, &vec!(image.image), format)?[0];
+    return Ok(ImageAndView{image,view,})
 }
 pub struct ImageAndView{
     pub image : ImageAndAllocation,
@@ -1,26 +1,55 @@
-use std::ffi::CString;
+use std::ffi::{CString, CStr, c_void};
 
-use ash::{Instance, vk::{ApplicationInfo, StructureType, InstanceCreateInfo, InstanceCreateFlags, API_VERSION_1_0}, Entry};
+use ash::{Instance, extensions::ext::DebugUtils, vk::{ApplicationInfo, StructureType, InstanceCreateInfo, InstanceCreateFlags, API_VERSION_1_1, ValidationFeaturesEXT, ValidationFeatureEnableEXT, ExtValidationFeaturesFn, KhrGetPhysicalDeviceProperties2Fn, ExtensionProperties}, Entry};
 use winit::window::Window;
 
-pub unsafe fn create_instance(entry : &Entry, window : &Window, debugging : bool) -> Instance{
+use crate::{error::RendererResult, config::RendererConfig};
+
+pub unsafe fn create_instance(entry : &Entry, window : Option<&Window>, config : &RendererConfig, warnings : &mut Vec<String>) -> RendererResult<Instance>{
     let name = CString::new("PWS").unwrap();
     let app_info = ApplicationInfo{
         s_type : StructureType::APPLICATION_INFO,
         p_next : std::ptr::null(),
-        api_version : API_VERSION_1_0,
+        api_version : API_VERSION_1_1,
         application_version : 1,
         engine_version : 1,
         p_application_name : name.as_ptr(),
         p_engine_name : name.as_ptr(),
     };
-    let window_extensions = ash_window::enumerate_required_extensions(window).expect("Failed to get window extensions");
-    let enabled_extensions = window_extensions.iter().map(|ext|ext.as_ptr()).collect::<Vec<_>>();
+    let window_extensions = match window{
+        Some(window)=>{ash_window::enumerate_required_extensions(window)?}
+        None=>{vec!()}
+    };
+    let mut enabled_extensions = window_extensions.iter().map(|ext|ext.as_ptr()).collect::<Vec<_>>();
     let validation_layer = CString::new("VK_LAYER_KHRONOS_validation").unwrap();
-    let enabled_layers = if debugging {vec!(validation_layer.as_ptr())}else{vec!()};
+    let validation = config.validation && supports_instance_layer(entry, &validation_layer)?;
+    if config.validation && !validation{warnings.push(format!("{} is not installed, continuing without validation", validation_layer.to_string_lossy()))}
+    let enabled_layers = if validation {vec!(validation_layer.as_ptr())}else{vec!()};
+    let mut enabled_validation_features = vec!();
+    if validation && config.best_practices_validation{enabled_validation_features.push(ValidationFeatureEnableEXT::BEST_PRACTICES)}
+    if validation && config.synchronization_validation{enabled_validation_features.push(ValidationFeatureEnableEXT::SYNCHRONIZATION_VALIDATION)}
+    if !enabled_validation_features.is_empty(){
+        if supports_instance_extension(entry, ExtValidationFeaturesFn::name())? || supports_layer_extension(entry, &validation_layer, ExtValidationFeaturesFn::name())?{
+            enabled_extensions.push(ExtValidationFeaturesFn::name().as_ptr());
+        }
+        else{
+            warnings.push(format!("{} is not supported, continuing without best practices and synchronization validation", ExtValidationFeaturesFn::name().to_string_lossy()));
+            enabled_validation_features.clear();
+        }
+    }
+    if config.validation && supports_instance_extension(entry, DebugUtils::name())?{enabled_extensions.push(DebugUtils::name().as_ptr())}
+    if supports_instance_extension(entry, KhrGetPhysicalDeviceProperties2Fn::name())?{enabled_extensions.push(KhrGetPhysicalDeviceProperties2Fn::name().as_ptr())}
+    let validation_features = ValidationFeaturesEXT{
+        s_type : StructureType::VALIDATION_FEATURES_EXT,
+        p_next : std::ptr::null(),
+        enabled_validation_feature_count : enabled_validation_features.len() as u32,
+        p_enabled_validation_features : enabled_validation_features.as_ptr(),
+        disabled_validation_feature_count : 0,
+        p_disabled_validation_features : std::ptr::null(),
+    };
     let instance_create_info = InstanceCreateInfo{
         s_type : StructureType::INSTANCE_CREATE_INFO,
-        p_next : std::ptr::null(),
+        p_next : if enabled_validation_features.is_empty(){std::ptr::null()}else{&validation_features as *const ValidationFeaturesEXT as *const c_void},
         flags : InstanceCreateFlags::empty(),
         p_application_info : &app_info,
         enabled_extension_count : enabled_extensions.len() as u32,
@@ -28,5 +57,28 @@ pub unsafe fn create_instance(entry : &Entry, window : &Window, debugging : bool
         enabled_layer_count : enabled_layers.len() as u32,
         pp_enabled_layer_names : enabled_layers.as_ptr(),
     };
-    return entry.create_instance(&instance_create_info, None).expect("Failed to create instance");
-}
\ No newline at end of file
+    return Ok(entry.create_instance(&instance_create_info, None)?);
+}
+pub fn supports_instance_extension(entry : &Entry, name : &CStr) -> RendererResult<bool>{
+    for extension in entry.enumerate_instance_extension_properties()?{
+        if unsafe{CStr::from_ptr(extension.extension_name.as_ptr())} == name{return Ok(true)}
+    }
+    return Ok(false);
+}
+pub fn supports_instance_layer(entry : &Entry, name : &CStr) -> RendererResult<bool>{
+    for layer in entry.enumerate_instance_layer_properties()?{
+        if unsafe{CStr::from_ptr(layer.layer_name.as_ptr())} == name{return Ok(true)}
+    }
+    return Ok(false);
+}
+pub fn supports_layer_extension(entry : &Entry, layer : &CStr, name : &CStr) -> RendererResult<bool>{
+    let mut count = 0;
+    unsafe{entry.fp_v1_0().enumerate_instance_extension_properties(layer.as_ptr(), &mut count, std::ptr::null_mut())}.result()?;
+    let mut extensions = vec!(ExtensionProperties::default();count as usize);
+    unsafe{entry.fp_v1_0().enumerate_instance_extension_properties(layer.as_ptr(), &mut count, extensions.as_mut_ptr())}.result()?;
+    extensions.truncate(count as usize);
+    for extension in extensions.iter(){
+        if unsafe{CStr::from_ptr(extension.extension_name.as_ptr())} == name{return Ok(true)}
+    }
+    return Ok(false);
+}
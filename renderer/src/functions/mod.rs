@@ -11,3 +11,4 @@ pub mod pipeline;
 pub mod synchronization;
 pub mod command;
 pub mod vertex;
+pub mod capture;
\ No newline at end of file
@@ -1,10 +1,12 @@
-use std::ffi::{c_void, CString};
+use std::{ffi::{c_void, CString}, path::Path};
 
-use ash::{vk::{PipelineLayoutCreateFlags, DescriptorSetLayout, PipelineLayout, PipelineLayoutCreateInfo, StructureType, PipelineCache, PipelineCacheCreateInfo, PipelineCacheCreateFlags, Pipeline, PipelineCreateFlags, GraphicsPipelineCreateInfo, ShaderStageFlags, PipelineShaderStageCreateFlags, PipelineShaderStageCreateInfo, SampleCountFlags, PipelineMultisampleStateCreateFlags, PipelineMultisampleStateCreateInfo, StencilOpState, CompareOp, PipelineDepthStencilStateCreateFlags, PipelineDepthStencilStateCreateInfo, LogicOp, PipelineColorBlendStateCreateFlags, PipelineColorBlendStateCreateInfo, BlendFactor, ColorComponentFlags, BlendOp, PipelineColorBlendAttachmentState, PipelineViewportStateCreateFlags, PipelineViewportStateCreateInfo, Viewport, Offset2D, Rect2D, PolygonMode, FrontFace, CullModeFlags, PipelineRasterizationStateCreateFlags, PipelineRasterizationStateCreateInfo, PrimitiveTopology, PipelineInputAssemblyStateCreateFlags, PipelineInputAssemblyStateCreateInfo, PipelineVertexInputStateCreateFlags, PipelineVertexInputStateCreateInfo, RenderPass, ShaderModule, Extent2D}, Device};
+use ash::{vk::{PipelineLayoutCreateFlags, DescriptorSetLayout, PipelineLayout, PipelineLayoutCreateInfo, StructureType, PipelineCache, PipelineCacheCreateInfo, PipelineCacheCreateFlags, Pipeline, PipelineCreateFlags, GraphicsPipelineCreateInfo, ShaderStageFlags, PipelineShaderStageCreateFlags, PipelineShaderStageCreateInfo, SampleCountFlags, PipelineMultisampleStateCreateFlags, PipelineMultisampleStateCreateInfo, StencilOpState, StencilOp, CompareOp, PipelineDepthStencilStateCreateFlags, PipelineDepthStencilStateCreateInfo, LogicOp, PipelineColorBlendStateCreateFlags, PipelineColorBlendStateCreateInfo, BlendFactor, ColorComponentFlags, BlendOp, PipelineColorBlendAttachmentState, PipelineViewportStateCreateFlags, PipelineViewportStateCreateInfo, Viewport, Offset2D, Rect2D, PolygonMode, FrontFace, CullModeFlags, PipelineRasterizationStateCreateFlags, PipelineRasterizationStateCreateInfo, PrimitiveTopology, PipelineInputAssemblyStateCreateFlags, PipelineInputAssemblyStateCreateInfo, PipelineVertexInputStateCreateFlags, PipelineVertexInputStateCreateInfo, RenderPass, ShaderModule}, Device};
 
-use crate::math::{Vertex, InstanceVertex};
+use crate::{math::{Vertex, InstanceVertex}, error::RendererResult};
 
-pub unsafe fn create_pipeline_layout(device : &Device, descriptor_set_layout : &DescriptorSetLayout) -> PipelineLayout{
+use super::swapchain::SwapchainInfo;
+
+pub unsafe fn create_pipeline_layout(device : &Device, descriptor_set_layout : &DescriptorSetLayout) -> RendererResult<PipelineLayout>{
     let pipeline_layout_create_info = PipelineLayoutCreateInfo{
         s_type : StructureType::PIPELINE_LAYOUT_CREATE_INFO,
         p_next : std::ptr::null(),
@@ -14,13 +16,12 @@ pub unsafe fn create_pipeline_layout(device : &Device, descriptor_set_layout : &
         p_set_layouts : descriptor_set_layout,
         set_layout_count : 1,
     };
-    return device.create_pipeline_layout(&pipeline_layout_create_info, None).expect("Failed to create pipeline layout");
+    return Ok(device.create_pipeline_layout(&pipeline_layout_create_info, None)?);
 }
-pub unsafe fn create_pipeline_cache(device : &Device) -> PipelineCache{
-    let mut file = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
-    file.push("./pipeline.cache");
+pub unsafe fn create_pipeline_cache(device : &Device, file : Option<&Path>) -> RendererResult<PipelineCache>{
     let pipeline_cache_create_info;
-    if !file.exists(){
+    let file_contents;
+    if !file.map_or(false, |file|file.exists()){
         pipeline_cache_create_info = PipelineCacheCreateInfo{
             s_type : StructureType::PIPELINE_CACHE_CREATE_INFO,
             p_next : std::ptr::null(),
@@ -30,7 +31,7 @@ pub unsafe fn create_pipeline_cache(device : &Device) -> PipelineCache{
         };
     }
     else{
-        let file_contents = std::fs::read(file).expect("Failed to read pipeline cache");
+        file_contents = std::fs::read(file.unwrap())?;
         pipeline_cache_create_info = PipelineCacheCreateInfo{
             s_type : StructureType::PIPELINE_CACHE_CREATE_INFO,
             p_next : std::ptr::null(),
@@ -39,16 +40,20 @@ pub unsafe fn create_pipeline_cache(device : &Device) -> PipelineCache{
             initial_data_size : file_contents.len(),
         };
     }
-    return device.create_pipeline_cache(&pipeline_cache_create_info, None).expect("Failed to create pipeline cache");
+    return Ok(device.create_pipeline_cache(&pipeline_cache_create_info, None)?);
 }
-pub unsafe fn save_pipeline_cache(device : &Device, cache : PipelineCache){
-    let cache_contents = device.get_pipeline_cache_data(cache).expect("Failed to get pipeline cache contents");
-    let mut file = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
-    file.push("./pipeline.cache");
-    if !file.parent().unwrap().exists(){std::fs::create_dir_all(file.clone()).expect("Failed to create cache directory")}
-    std::fs::write(file, cache_contents).expect("Failed to save pipeline cache");
+pub unsafe fn save_pipeline_cache(device : &Device, cache : PipelineCache, file : Option<&Path>) -> RendererResult<()>{
+    let file = match file{
+        Some(file)=>{file}
+        None=>{return Ok(())}
+    };
+    let cache_contents = device.get_pipeline_cache_data(cache)?;
+    if let Some(parent) = file.parent(){std::fs::create_dir_all(parent)?}
+    std::fs::write(file, cache_contents)?;
+    return Ok(());
 }
-pub unsafe fn create_pipelines(device : &Device, cache : PipelineCache, layout : PipelineLayout, render_pass : RenderPass, modules : &Vec<ShaderModule>, extent : Extent2D) -> Vec<Pipeline>{
+pub unsafe fn create_pipelines(device : &Device, cache : PipelineCache, layout : PipelineLayout, render_pass : RenderPass, modules : &[ShaderModule], swapchain_info : &SwapchainInfo) -> RendererResult<Vec<Pipeline>>{
+    let SwapchainInfo{extent, reverse_z, id_buffer, ..} = *swapchain_info;
     let vertex_attributes = InstanceVertex::get_attributes();
     let vertex_bindings = InstanceVertex::get_bindings();
     let vertex_input_state = PipelineVertexInputStateCreateInfo{
@@ -140,18 +145,25 @@ pub unsafe fn create_pipelines(device : &Device, cache : PipelineCache, layout :
         p_viewports : viewports.as_ptr(),
         viewport_count : viewports.len() as u32,
     };
-    let color_blend_attachments = [
-        PipelineColorBlendAttachmentState{
-            alpha_blend_op : BlendOp::ADD,
-            color_blend_op : BlendOp::ADD,
-            blend_enable : 0,
-            color_write_mask : ColorComponentFlags::R | ColorComponentFlags::G | ColorComponentFlags::B | ColorComponentFlags::A,
-            dst_alpha_blend_factor : BlendFactor::ZERO,
-            dst_color_blend_factor : BlendFactor::ZERO,
-            src_alpha_blend_factor : BlendFactor::ONE,
-            src_color_blend_factor : BlendFactor::ONE,
-        }
-    ];
+    let color_blend_attachment = PipelineColorBlendAttachmentState{
+        alpha_blend_op : BlendOp::ADD,
+        color_blend_op : BlendOp::ADD,
+        blend_enable : 0,
+        color_write_mask : ColorComponentFlags::R | ColorComponentFlags::G | ColorComponentFlags::B | ColorComponentFlags::A,
+        dst_alpha_blend_factor : BlendFactor::ZERO,
+        dst_color_blend_factor : BlendFactor::ZERO,
+        src_alpha_blend_factor : BlendFactor::ONE,
+        src_color_blend_factor : BlendFactor::ONE,
+    };
+    let disabled_blend_attachment = PipelineColorBlendAttachmentState{color_write_mask:ColorComponentFlags::empty(),..color_blend_attachment};
+    let mut color_blend_attachments = vec!(color_blend_attachment);
+    let mut grid_color_blend_attachments = vec!(color_blend_attachment);
+    let mut mark_color_blend_attachments = vec!(disabled_blend_attachment);
+    if id_buffer{
+        color_blend_attachments.push(PipelineColorBlendAttachmentState{color_write_mask:ColorComponentFlags::R,..color_blend_attachment});
+        grid_color_blend_attachments.push(disabled_blend_attachment);
+        mark_color_blend_attachments.push(disabled_blend_attachment);
+    }
     let color_blend_state = PipelineColorBlendStateCreateInfo{
         s_type : StructureType::PIPELINE_COLOR_BLEND_STATE_CREATE_INFO,
         p_next : std::ptr::null(),
@@ -162,12 +174,22 @@ pub unsafe fn create_pipelines(device : &Device, cache : PipelineCache, layout :
         logic_op : LogicOp::COPY,
         logic_op_enable : 0,
     };
+    let grid_color_blend_state = PipelineColorBlendStateCreateInfo{
+        attachment_count: grid_color_blend_attachments.len() as u32,
+        p_attachments : grid_color_blend_attachments.as_ptr(),
+        ..color_blend_state
+    };
+    let mark_color_blend_state = PipelineColorBlendStateCreateInfo{
+        attachment_count: mark_color_blend_attachments.len() as u32,
+        p_attachments : mark_color_blend_attachments.as_ptr(),
+        ..color_blend_state
+    };
     let depth_stencil_state = PipelineDepthStencilStateCreateInfo{
         s_type : StructureType::PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
         p_next : std::ptr::null(),
         flags : PipelineDepthStencilStateCreateFlags::empty(),
         depth_bounds_test_enable : 0,
-        depth_compare_op : CompareOp::LESS,
+        depth_compare_op : if reverse_z{CompareOp::GREATER}else{CompareOp::LESS},
         depth_test_enable : 1,
         depth_write_enable : 1,
         back : StencilOpState::default(),
@@ -176,6 +198,39 @@ pub unsafe fn create_pipelines(device : &Device, cache : PipelineCache, layout :
         min_depth_bounds : 0.0,
         stencil_test_enable : 0
     };
+    let mark_stencil_op_state = StencilOpState{
+        fail_op : StencilOp::KEEP,
+        pass_op : StencilOp::REPLACE,
+        depth_fail_op : StencilOp::KEEP,
+        compare_op : CompareOp::ALWAYS,
+        compare_mask : 0xff,
+        write_mask : 0xff,
+        reference : 1,
+    };
+    let mark_depth_stencil_state = PipelineDepthStencilStateCreateInfo{
+        depth_test_enable : 0,
+        depth_write_enable : 0,
+        back : mark_stencil_op_state,
+        front : mark_stencil_op_state,
+        stencil_test_enable : 1,
+        ..depth_stencil_state
+    };
+    let outline_stencil_op_state = StencilOpState{
+        pass_op : StencilOp::KEEP,
+        compare_op : CompareOp::NOT_EQUAL,
+        write_mask : 0,
+        ..mark_stencil_op_state
+    };
+    let outline_depth_stencil_state = PipelineDepthStencilStateCreateInfo{
+        back : outline_stencil_op_state,
+        front : outline_stencil_op_state,
+        ..mark_depth_stencil_state
+    };
+    let gizmo_depth_stencil_state = PipelineDepthStencilStateCreateInfo{
+        depth_test_enable : 0,
+        depth_write_enable : 0,
+        ..depth_stencil_state
+    };
     let multisample_state = PipelineMultisampleStateCreateInfo{
         s_type : StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
         p_next : std::ptr::null(),
@@ -193,7 +248,7 @@ pub unsafe fn create_pipelines(device : &Device, cache : PipelineCache, layout :
             s_type : StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
             p_next : std::ptr::null(),
             flags : PipelineShaderStageCreateFlags::empty(),
-            module : modules[0],
+            module : if id_buffer{modules[4]}else{modules[0]},
             p_specialization_info : std::ptr::null(),
             stage : ShaderStageFlags::VERTEX,
             p_name : name.as_ptr(),
@@ -202,7 +257,7 @@ pub unsafe fn create_pipelines(device : &Device, cache : PipelineCache, layout :
             s_type : StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
             p_next : std::ptr::null(),
             flags : PipelineShaderStageCreateFlags::empty(),
-            module : modules[1],
+            module : if id_buffer{modules[5]}else{modules[1]},
             p_specialization_info : std::ptr::null(),
             stage : ShaderStageFlags::FRAGMENT,
             p_name : name.as_ptr(),
@@ -228,6 +283,30 @@ pub unsafe fn create_pipelines(device : &Device, cache : PipelineCache, layout :
             p_name : name.as_ptr(),
         },
     ];
+    let mark_stages = [
+        PipelineShaderStageCreateInfo{
+            module : modules[0],
+            ..grid_stages[0]
+        },
+        PipelineShaderStageCreateInfo{
+            module : modules[1],
+            ..grid_stages[1]
+        },
+    ];
+    let outline_stages = [
+        mark_stages[0],
+        PipelineShaderStageCreateInfo{
+            module : modules[6],
+            ..grid_stages[1]
+        },
+    ];
+    let gizmo_stages = [
+        grid_stages[0],
+        PipelineShaderStageCreateInfo{
+            module : modules[7],
+            ..grid_stages[1]
+        },
+    ];
     let pipeline_create_infos = [
         GraphicsPipelineCreateInfo{
             s_type : StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
@@ -259,7 +338,7 @@ pub unsafe fn create_pipelines(device : &Device, cache : PipelineCache, layout :
             layout : layout,
             render_pass : render_pass,
             subpass : 0,
-            p_color_blend_state : &color_blend_state,
+            p_color_blend_state : &grid_color_blend_state,
             p_depth_stencil_state : &depth_stencil_state,
             p_dynamic_state : std::ptr::null(),
             p_input_assembly_state : &input_assembly_grid_state,
@@ -270,7 +349,70 @@ pub unsafe fn create_pipelines(device : &Device, cache : PipelineCache, layout :
             p_multisample_state : &multisample_state,
             p_stages : grid_stages.as_ptr(),
             stage_count : grid_stages.len() as u32,
+        },
+        GraphicsPipelineCreateInfo{
+            s_type : StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
+            p_next : std::ptr::null(),
+            flags : PipelineCreateFlags::empty(),
+            base_pipeline_handle : Pipeline::null(),
+            base_pipeline_index : -1,
+            layout : layout,
+            render_pass : render_pass,
+            subpass : 0,
+            p_color_blend_state : &mark_color_blend_state,
+            p_depth_stencil_state : &mark_depth_stencil_state,
+            p_dynamic_state : std::ptr::null(),
+            p_input_assembly_state : &input_assembly_state,
+            p_rasterization_state : &rasterization_state,
+            p_tessellation_state : std::ptr::null(),
+            p_vertex_input_state : &vertex_input_state,
+            p_viewport_state: &viewport_state,
+            p_multisample_state : &multisample_state,
+            p_stages : mark_stages.as_ptr(),
+            stage_count : mark_stages.len() as u32,
+        },
+        GraphicsPipelineCreateInfo{
+            s_type : StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
+            p_next : std::ptr::null(),
+            flags : PipelineCreateFlags::empty(),
+            base_pipeline_handle : Pipeline::null(),
+            base_pipeline_index : -1,
+            layout : layout,
+            render_pass : render_pass,
+            subpass : 0,
+            p_color_blend_state : &grid_color_blend_state,
+            p_depth_stencil_state : &outline_depth_stencil_state,
+            p_dynamic_state : std::ptr::null(),
+            p_input_assembly_state : &input_assembly_state,
+            p_rasterization_state : &rasterization_state,
+            p_tessellation_state : std::ptr::null(),
+            p_vertex_input_state : &vertex_input_state,
+            p_viewport_state: &viewport_state,
+            p_multisample_state : &multisample_state,
+            p_stages : outline_stages.as_ptr(),
+            stage_count : outline_stages.len() as u32,
+        },
+        GraphicsPipelineCreateInfo{
+            s_type : StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
+            p_next : std::ptr::null(),
+            flags : PipelineCreateFlags::empty(),
+            base_pipeline_handle : Pipeline::null(),
+            base_pipeline_index : -1,
+            layout : layout,
+            render_pass : render_pass,
+            subpass : 0,
+            p_color_blend_state : &grid_color_blend_state,
+            p_depth_stencil_state : &gizmo_depth_stencil_state,
+            p_dynamic_state : std::ptr::null(),
+            p_input_assembly_state : &input_assembly_grid_state,
+            p_rasterization_state : &rasterization_grid_state,
+            p_tessellation_state : std::ptr::null(),
+            p_vertex_input_state : &vertex_input_grid_state,
+            p_viewport_state: &viewport_state,
+            p_multisample_state : &multisample_state,
+            p_stages : gizmo_stages.as_ptr(),
+            stage_count : gizmo_stages.len() as u32,
         }
     ];
-    return device.create_graphics_pipelines(cache, &pipeline_create_infos, None).expect("Failed to create pipelines");
+    return device.create_graphics_pipelines(cache, &pipeline_create_infos, None).map_err(|(_, result)|result.into());
 }
\ No newline at end of file
@@ -1,12 +1,16 @@
 use ash::{Device, vk::{Format, RenderPass, AttachmentDescription, AttachmentDescriptionFlags, ImageLayout, AttachmentLoadOp, AttachmentStoreOp, SampleCountFlags, AttachmentReference, SubpassDescription, SubpassDescriptionFlags, PipelineBindPoint, SubpassDependency, DependencyFlags, SUBPASS_EXTERNAL, PipelineStageFlags, AccessFlags, RenderPassCreateInfo, StructureType, RenderPassCreateFlags}};
 
-pub unsafe fn create_render_pass(device : &Device, format : Format, depth_format : Format) -> RenderPass{
-    let render_pass_attachments = [
+use crate::error::RendererResult;
+
+use super::image::ID_FORMAT;
+
+pub unsafe fn create_render_pass(device : &Device, format : Format, depth_format : Format, final_layout : ImageLayout, id_buffer : bool) -> RendererResult<RenderPass>{
+    let mut render_pass_attachments = vec!(
         AttachmentDescription{
             flags : AttachmentDescriptionFlags::empty(),
             format,
             initial_layout : ImageLayout::UNDEFINED,
-            final_layout : ImageLayout::PRESENT_SRC_KHR,
+            final_layout,
             load_op : AttachmentLoadOp::CLEAR,
             store_op : AttachmentStoreOp::STORE,
             samples : SampleCountFlags::TYPE_1,
@@ -21,16 +25,33 @@ pub unsafe fn create_render_pass(device : &Device, format : Format, depth_format
             load_op : AttachmentLoadOp::CLEAR,
             store_op : AttachmentStoreOp::STORE,
             samples : SampleCountFlags::TYPE_1,
-            stencil_load_op : AttachmentLoadOp::DONT_CARE,
+            stencil_load_op : AttachmentLoadOp::CLEAR,
             stencil_store_op : AttachmentStoreOp::DONT_CARE,
         },
-    ];
-    let color_attachment_references = [
+    );
+    let mut color_attachment_references = vec!(
         AttachmentReference{
             attachment : 0,
             layout : ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
         }
-    ];
+    );
+    if id_buffer{
+        render_pass_attachments.push(AttachmentDescription{
+            flags : AttachmentDescriptionFlags::empty(),
+            format : ID_FORMAT,
+            initial_layout : ImageLayout::UNDEFINED,
+            final_layout : ImageLayout::TRANSFER_SRC_OPTIMAL,
+            load_op : AttachmentLoadOp::CLEAR,
+            store_op : AttachmentStoreOp::STORE,
+            samples : SampleCountFlags::TYPE_1,
+            stencil_load_op : AttachmentLoadOp::DONT_CARE,
+            stencil_store_op : AttachmentStoreOp::DONT_CARE,
+        });
+        color_attachment_references.push(AttachmentReference{
+            attachment : 2,
+            layout : ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        });
+    }
     let depth_attachment_reference = AttachmentReference{
         attachment : 1,
         layout : ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
@@ -71,5 +92,5 @@ pub unsafe fn create_render_pass(device : &Device, format : Format, depth_format
         dependency_count : dependencies.len() as u32,
         p_dependencies : dependencies.as_ptr(),
     };
-    return device.create_render_pass(&render_pass_create_info, None).expect("Failed to create render pass");
+    return Ok(device.create_render_pass(&render_pass_create_info, None)?);
 }
\ No newline at end of file
@@ -1,16 +1,18 @@
 use ash::{vk::{ShaderModule, ShaderModuleCreateInfo, StructureType, ShaderModuleCreateFlags}, Device};
 
-const SHADERS : [&str;4] = [
-    "main.vert","main.frag","grid.vert", "grid.frag"
+use crate::error::{RendererError, RendererResult};
+
+const SHADERS : [&str;8] = [
+    "main.vert","main.frag","grid.vert", "grid.frag", "main_id.vert", "main_id.frag", "outline.frag", "gizmo.frag"
 ];
 
-pub unsafe fn load_shaders(device: &Device) -> Vec<ShaderModule>{
-    let mut shader_dir = std::env::current_exe().unwrap().parent().unwrap().to_path_buf();
+pub unsafe fn load_shaders(device: &Device) -> RendererResult<Vec<ShaderModule>>{
+    let mut shader_dir = std::env::current_exe()?.parent().unwrap().to_path_buf();
     shader_dir.push("./shaders");
     let mut shaders = vec!();
     for shader in SHADERS{
         let path = shader_dir.join(format!("./{}.spv",shader));
-        let file = std::fs::read(path).expect("Failed to load shader");
+        let file = std::fs::read(path).map_err(|error|RendererError::ShaderLoad(shader.to_string(), error))?;
         let shader_create_info = ShaderModuleCreateInfo{
             s_type : StructureType::SHADER_MODULE_CREATE_INFO,
             p_next : std::ptr::null(),
@@ -18,7 +20,7 @@ pub unsafe fn load_shaders(device: &Device) -> Vec<ShaderModule>{
             code_size : file.len(),
             p_code : file.as_ptr() as *const u32,
         };
-        shaders.push(device.create_shader_module(&shader_create_info, None).expect("A shader got corrupted"));
+        shaders.push(device.create_shader_module(&shader_create_info, None)?);
     }
-    return shaders;
+    return Ok(shaders);
 }
\ No newline at end of file
@@ -1,58 +1,100 @@
-use ash::{Instance, vk::{PhysicalDevice, SurfaceKHR, SurfaceFormatKHR, Format, ColorSpaceKHR, FormatFeatureFlags, PresentModeKHR, Extent2D, SurfaceTransformFlagsKHR, SwapchainKHR, SwapchainCreateInfoKHR, StructureType, SwapchainCreateFlagsKHR, CompositeAlphaFlagsKHR, ImageUsageFlags, SharingMode}, extensions::khr::{Surface, Swapchain}};
+use ash::{Instance, vk::{PhysicalDevice, SurfaceKHR, Format, ColorSpaceKHR, FormatFeatureFlags, PresentModeKHR, Extent2D, SurfaceTransformFlagsKHR, SwapchainKHR, ClearValue, ClearColorValue, ClearDepthStencilValue, SwapchainCreateInfoKHR, StructureType, SwapchainCreateFlagsKHR, CompositeAlphaFlagsKHR, ImageUsageFlags, SharingMode}, extensions::khr::{Surface, Swapchain}};
 use winit::dpi::PhysicalSize;
 
+use crate::{error::{RendererError, RendererResult}, config::RendererConfig};
+
 use super::device::QueueInfo;
 
-const SURFACE_FORMATS : [SurfaceFormatKHR;2] = [
-    SurfaceFormatKHR{format : Format::R8G8B8A8_SRGB, color_space : ColorSpaceKHR::SRGB_NONLINEAR},
-    SurfaceFormatKHR{format : Format::B8G8R8A8_SRGB, color_space : ColorSpaceKHR::SRGB_NONLINEAR},
-];
 const DEPTH_FORMATS : [Format; 6] = [
-    Format::D16_UNORM,Format::D16_UNORM_S8_UINT,Format::D32_SFLOAT,Format::D24_UNORM_S8_UINT,Format::D32_SFLOAT_S8_UINT,Format::X8_D24_UNORM_PACK32,
+    Format::D24_UNORM_S8_UINT,Format::D32_SFLOAT_S8_UINT,Format::D16_UNORM_S8_UINT,Format::D16_UNORM,Format::D32_SFLOAT,Format::X8_D24_UNORM_PACK32,
+];
+const REVERSE_Z_DEPTH_FORMATS : [Format; 6] = [
+    Format::D32_SFLOAT_S8_UINT,Format::D32_SFLOAT,Format::D24_UNORM_S8_UINT,Format::X8_D24_UNORM_PACK32,Format::D16_UNORM_S8_UINT,Format::D16_UNORM,
 ];
 
 pub struct SwapchainInfo{
     pub surface_format : Format,
     pub depth_format : Format,
+    pub reverse_z : bool,
+    pub id_buffer : bool,
     pub color_space : ColorSpaceKHR,
     pub present_mode : PresentModeKHR,
     pub extent : Extent2D,
     pub transform : SurfaceTransformFlagsKHR,
     pub min_image_count : u32,
+    pub image_usage : ImageUsageFlags,
 }
 impl SwapchainInfo{
-    pub fn new(instance : &Instance, physical_device : PhysicalDevice, surface_loader : &Surface, surface : SurfaceKHR, window_size : PhysicalSize<u32>) -> Self{
-        let supported_surface_formats = unsafe{surface_loader.get_physical_device_surface_formats(physical_device, surface)}.expect("Failed to get swapchain formats");
-        let supported_surface_present_modes = unsafe{surface_loader.get_physical_device_surface_present_modes(physical_device, surface)}.expect("Failed to get supported present modes");
-        let surface_capabilities = unsafe{surface_loader.get_physical_device_surface_capabilities(physical_device, surface)}.expect("Failed to query swapchain capabilities");
+    pub fn new(instance : &Instance, physical_device : PhysicalDevice, surface_loader : &Surface, surface : SurfaceKHR, window_size : PhysicalSize<u32>, config : &RendererConfig) -> RendererResult<Self>{
+        let supported_surface_formats = unsafe{surface_loader.get_physical_device_surface_formats(physical_device, surface)}?;
+        let supported_surface_present_modes = unsafe{surface_loader.get_physical_device_surface_present_modes(physical_device, surface)}?;
+        let surface_capabilities = unsafe{surface_loader.get_physical_device_surface_capabilities(physical_device, surface)}?;
         let mut surface_format = supported_surface_formats[0];
-        for prefered_surface_format in SURFACE_FORMATS{
+        for prefered_surface_format in config.surface_formats.iter().map(|format|format.to_vk()){
             if supported_surface_formats.contains(&prefered_surface_format){surface_format = prefered_surface_format;break}
         }
-        let mut depth_format = Format::R8_SINT;
-        for is_depth_format in DEPTH_FORMATS{
-            if unsafe{instance.get_physical_device_format_properties(physical_device, is_depth_format).optimal_tiling_features.contains(FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)}{
-                depth_format = is_depth_format;break;
-            }
-        }
-        if depth_format == Format::R8_SINT{panic!("No supported depth format")}
-        let present_mode = if supported_surface_present_modes.contains(&PresentModeKHR::MAILBOX){PresentModeKHR::MAILBOX}else{PresentModeKHR::FIFO};
+        let depth_format = get_depth_format(instance, physical_device, config.reverse_z)?;
+        let present_mode = config.present_modes.iter().map(|present_mode|present_mode.to_vk()).find(|present_mode|supported_surface_present_modes.contains(present_mode)).unwrap_or(PresentModeKHR::FIFO);
         let extent = if surface_capabilities.current_extent.width != u32::MAX{surface_capabilities.current_extent}else{Extent2D{width:window_size.width,height:window_size.height}};
         let transform = surface_capabilities.current_transform;
+        let image_usage = ImageUsageFlags::COLOR_ATTACHMENT | (surface_capabilities.supported_usage_flags & ImageUsageFlags::TRANSFER_SRC);
         let min_image_count = if surface_capabilities.min_image_count + 1 <= surface_capabilities.max_image_count || surface_capabilities.max_image_count == 0{surface_capabilities.min_image_count + 1}else{surface_capabilities.max_image_count};
 
-        return Self{
+        return Ok(Self{
             surface_format : surface_format.format,
             depth_format,
+            reverse_z : config.reverse_z,
+            id_buffer : config.id_buffer,
             color_space : surface_format.color_space,
             present_mode,
             extent,
             transform,
             min_image_count,
+            image_usage,
+        })
+    }
+    pub fn get_clear_values(&self) -> Vec<ClearValue>{
+        let mut clear_values = vec!(
+            ClearValue{color:ClearColorValue{float32:[1.0,1.0,1.0,0.0]}},
+            ClearValue{depth_stencil:ClearDepthStencilValue{depth:if self.reverse_z{0.0}else{1.0},stencil:0}},
+        );
+        if self.id_buffer{clear_values.push(ClearValue{color:ClearColorValue{uint32:[0;4]}})}
+        return clear_values;
+    }
+    pub fn has_stencil(&self) -> bool{
+        return super::image::has_stencil(self.depth_format);
+    }
+    pub fn new_headless(instance : &Instance, physical_device : PhysicalDevice, extent : Extent2D, config : &RendererConfig) -> RendererResult<Self>{
+        let mut surface_format = None;
+        for prefered_surface_format in config.surface_formats.iter().map(|format|format.to_vk()){
+            if unsafe{instance.get_physical_device_format_properties(physical_device, prefered_surface_format.format)}.optimal_tiling_features.contains(FormatFeatureFlags::COLOR_ATTACHMENT){
+                surface_format = Some(prefered_surface_format);break;
+            }
+        }
+        let surface_format = surface_format.ok_or(RendererError::NoFormat("color"))?;
+        return Ok(Self{
+            surface_format : surface_format.format,
+            depth_format : get_depth_format(instance, physical_device, config.reverse_z)?,
+            reverse_z : config.reverse_z,
+            id_buffer : config.id_buffer,
+            color_space : surface_format.color_space,
+            present_mode : PresentModeKHR::FIFO,
+            extent,
+            transform : SurfaceTransformFlagsKHR::IDENTITY,
+            min_image_count : 1,
+            image_usage : ImageUsageFlags::COLOR_ATTACHMENT | ImageUsageFlags::TRANSFER_SRC,
+        })
+    }
+}
+fn get_depth_format(instance : &Instance, physical_device : PhysicalDevice, reverse_z : bool) -> RendererResult<Format>{
+    for is_depth_format in if reverse_z{REVERSE_Z_DEPTH_FORMATS}else{DEPTH_FORMATS}{
+        if unsafe{instance.get_physical_device_format_properties(physical_device, is_depth_format).optimal_tiling_features.contains(FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)}{
+            return Ok(is_depth_format);
         }
     }
+    return Err(RendererError::NoFormat("depth"));
 }
-pub unsafe fn create_swapchain(swapchain_loader : &Swapchain, swapchain_info : &SwapchainInfo, queue_info : &QueueInfo, surface : SurfaceKHR) -> SwapchainKHR{
+pub unsafe fn create_swapchain(swapchain_loader : &Swapchain, swapchain_info : &SwapchainInfo, queue_info : &QueueInfo, surface : SurfaceKHR) -> RendererResult<SwapchainKHR>{
     let queue_families = [queue_info.graphics_family,queue_info.presentation_family];
     let exclusive = queue_families[0] == queue_families[1];
     let swapchain_create_info = SwapchainCreateInfoKHR{
@@ -62,7 +104,7 @@ pub unsafe fn create_swapchain(swapchain_loader : &Swapchain, swapchain_info : &
         clipped : 1,
         composite_alpha : CompositeAlphaFlagsKHR::OPAQUE,
         image_array_layers : 1,
-        image_usage : ImageUsageFlags::COLOR_ATTACHMENT,
+        image_usage : swapchain_info.image_usage,
         old_swapchain : SwapchainKHR::null(),
         surface,
         queue_family_index_count : if exclusive{0}else{2},
@@ -75,5 +117,5 @@ pub unsafe fn create_swapchain(swapchain_loader : &Swapchain, swapchain_info : &
         pre_transform : swapchain_info.transform,
         present_mode : swapchain_info.present_mode,
     };
-    return swapchain_loader.create_swapchain(&swapchain_create_info, None).expect("Failed to create Vulkan swapchain")
+    return Ok(swapchain_loader.create_swapchain(&swapchain_create_info, None)?)
 }
\ No newline at end of file
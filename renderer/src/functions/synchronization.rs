@@ -1,6 +1,8 @@
 use ash::{Device, vk::{Semaphore, SemaphoreCreateInfo, StructureType, SemaphoreCreateFlags, Fence, FenceCreateInfo, FenceCreateFlags}};
 
-pub unsafe fn create_semaphores(device : &Device, count : u32) -> Vec<Semaphore>{
+use crate::error::RendererResult;
+
+pub unsafe fn create_semaphores(device : &Device, count : u32) -> RendererResult<Vec<Semaphore>>{
     let mut semaphores = vec!();
     let semaphore_create_info = SemaphoreCreateInfo{
         s_type : StructureType::SEMAPHORE_CREATE_INFO,
@@ -8,11 +10,11 @@ pub unsafe fn create_semaphores(device : &Device, count : u32) -> Vec<Semaphore>
         flags : SemaphoreCreateFlags::empty(),
     };
     for _ in 0..count{
-        semaphores.push(device.create_semaphore(&semaphore_create_info, None).expect("Failed to create semaphore"));
+        semaphores.push(device.create_semaphore(&semaphore_create_info, None)?);
     }
-    return semaphores;
+    return Ok(semaphores);
 }
-pub unsafe fn create_fences(device : &Device, signaled : bool, count : u32) -> Vec<Fence>{
+pub unsafe fn create_fences(device : &Device, signaled : bool, count : u32) -> RendererResult<Vec<Fence>>{
     let mut fences = vec!();
     let fence_create_info = FenceCreateInfo{
         s_type : StructureType::FENCE_CREATE_INFO,
@@ -20,9 +22,9 @@ pub unsafe fn create_fences(device : &Device, signaled : bool, count : u32) -> V
         flags : if signaled{FenceCreateFlags::SIGNALED}else{FenceCreateFlags::empty()},
     };
     for _ in 0..count{
-        fences.push(device.create_fence(&fence_create_info, None).expect("Failed to create fence"))
+        fences.push(device.create_fence(&fence_create_info, None)?)
     }
-    return fences;
+    return Ok(fences);
 }
 pub struct Synchronizer{
     pub in_flight_fences : Vec<Fence>,
@@ -31,13 +33,13 @@ pub struct Synchronizer{
     pub current_frame : usize,
 }
 impl Synchronizer{
-    pub unsafe fn new(device : &Device, count : u32) -> Self{
-        return Self{
-            image_available_semaphores : create_semaphores(device, count),
-            render_finished_semaphores : create_semaphores(device, count),
-            in_flight_fences : create_fences(device, true, count),
+    pub unsafe fn new(device : &Device, count : u32) -> RendererResult<Self>{
+        return Ok(Self{
+            image_available_semaphores : create_semaphores(device, count)?,
+            render_finished_semaphores : create_semaphores(device, count)?,
+            in_flight_fences : create_fences(device, true, count)?,
             current_frame : 0,
-        }
+        })
     }
     pub unsafe fn destroy(&self, device : &Device){
         for &semaphore in self.image_available_semaphores.iter(){
@@ -1,14 +1,98 @@
-use ash::{Device, vk::{CommandPool, Queue}};
+use std::ops::Range;
 
-use crate::{allocator::{BufferAndAllocation, Allocator}, math::{Vertex, ModelMatrix, InstanceVertex}};
+use ash::{Device, vk::{CommandPool, Queue, CommandBuffer, BufferCopy}};
 
-pub unsafe fn create_vertex_buffers(device : &Device, allocator : &mut Allocator, command_pool : CommandPool, queue : Queue, instance_positions : Vec<ModelMatrix>) -> Vec<(u32,BufferAndAllocation)>{
+use crate::{allocator::{BufferAndAllocation, Allocator, frame::FrameAllocator}, math::{Vertex, InstanceVertex}, error::{RendererError, RendererResult}};
+
+pub unsafe fn create_vertex_buffers(device : &Device, allocator : &mut Allocator, command_pool : CommandPool, queue : Queue) -> RendererResult<Vec<(u32,BufferAndAllocation)>>{
     let vertex_data = InstanceVertex::get_initial_vertex_data();
     let grid_data = Vertex::get_grid();
     let mut buffers = vec!();
-    buffers.push((vertex_data.len() as u32,super::buffer::create_vertex_buffer(device, allocator, command_pool, queue, vertex_data.to_vec())));
-    buffers.push((grid_data.len() as u32, super::buffer::create_vertex_buffer(device, allocator, command_pool, queue, grid_data.to_vec())));
-    buffers.push((instance_positions.len()as u32, super::buffer::create_vertex_buffer(device, allocator, command_pool, queue, instance_positions)));
-    println!("{}",std::mem::size_of::<ModelMatrix>());
-    return buffers;
-}
\ No newline at end of file
+    buffers.push((vertex_data.len() as u32,super::buffer::create_vertex_buffer(device, allocator, command_pool, queue, vertex_data.to_vec())?));
+    buffers.push((grid_data.len() as u32, super::buffer::create_vertex_buffer(device, allocator, command_pool, queue, grid_data.to_vec())?));
+    return Ok(buffers);
+}
+pub fn get_changed_ranges<T : PartialEq>(old : &[T], new : &[T]) -> Vec<Range<usize>>{
+    let mut ranges : Vec<Range<usize>> = vec!();
+    for (i, value) in new.iter().enumerate(){
+        if old.get(i) == Some(value){continue}
+        match ranges.last_mut(){
+            Some(range) if range.end == i=>{range.end += 1}
+            _=>{ranges.push(i..i + 1)}
+        }
+    }
+    return ranges;
+}
+pub struct InstanceBuffer<T>{
+    pub buffer : BufferAndAllocation,
+    pub count : u32,
+    capacity : u32,
+    uploaded : Vec<T>,
+}
+impl<T : Copy + PartialEq> InstanceBuffer<T>{
+    pub unsafe fn new(device : &Device, allocator : &mut Allocator, command_pool : CommandPool, queue : Queue, data : Vec<T>) -> RendererResult<Self>{
+        let capacity = (data.len() as u32).max(1);
+        let buffer = super::buffer::create_device_vertex_buffer(allocator, capacity as u64 * std::mem::size_of::<T>() as u64)?;
+        if !data.is_empty(){
+            if let Err(error) = super::buffer::copy_vertices_to_gpu(device, allocator, command_pool, queue, data.clone(), &buffer){
+                buffer.destroy(allocator);
+                return Err(error);
+            }
+        }
+        return Ok(Self{buffer,count:data.len() as u32,capacity,uploaded:data});
+    }
+    pub unsafe fn update(&mut self, device : &Device, allocator : &mut Allocator, frame_allocator : &mut FrameAllocator, command_buffer : CommandBuffer, data : Vec<T>) -> RendererResult<()>{
+        let element_size = std::mem::size_of::<T>() as u64;
+        if data.len() as u32 > self.capacity{
+            let capacity = (data.len() as u32).next_power_of_two();
+            let buffer = super::buffer::create_device_vertex_buffer(allocator, capacity as u64 * element_size)?;
+            frame_allocator.retire(std::mem::replace(&mut self.buffer, buffer));
+            self.capacity = capacity;
+            self.uploaded.clear();
+        }
+        self.count = data.len() as u32;
+        let ranges = get_changed_ranges(&self.uploaded, &data);
+        if ranges.is_empty(){
+            self.uploaded = data;
+            return Ok(());
+        }
+        let changed = ranges.iter().flat_map(|range|data[range.clone()].iter().copied()).collect::<Vec<T>>();
+        let mut regions = vec!();
+        let mut staged = 0;
+        for range in ranges.iter(){
+            regions.push(BufferCopy{
+                size : range.len() as u64 * element_size,
+                src_offset : staged * element_size,
+                dst_offset : range.start as u64 * element_size,
+            });
+            staged += range.len() as u64;
+        }
+        let staging_size = changed.len() as u64 * element_size;
+        match frame_allocator.allocate(staging_size, std::mem::align_of::<T>() as u64){
+            Some(staging)=>{
+                staging.write(&changed);
+                frame_allocator.flush(allocator, &staging)?;
+                for region in regions.iter_mut(){region.src_offset += staging.offset}
+                super::buffer::record_buffer_upload(device, command_buffer, staging.buffer, self.buffer.buffer, &regions);
+            }
+            None=>{
+                let staging = super::buffer::create_staging_buffer(allocator, staging_size)?;
+                let written = match staging.allocation.mapped_slice::<T>(allocator){
+                    Some(mapped)=>{
+                        mapped.as_mut_ptr().copy_from_nonoverlapping(changed.as_ptr(), changed.len());
+                        allocator.flush_allocation(&staging.allocation, 0, staging_size)
+                    }
+                    None=>{Err(RendererError::NotMapped)}
+                };
+                if written.is_ok(){super::buffer::record_buffer_upload(device, command_buffer, staging.buffer, self.buffer.buffer, &regions)}
+                frame_allocator.retire(staging);
+                written?;
+            }
+        }
+        self.uploaded = data;
+        return Ok(());
+    }
+    pub unsafe fn destroy(&self, allocator : &mut Allocator){
+        self.buffer.destroy(allocator);
+    }
+}
@@ -1,52 +1,132 @@
 pub mod functions;
 pub mod allocator;
 pub mod math;
+pub mod scene;
+pub mod mesh;
+pub mod error;
+pub mod config;
 
-use std::sync::mpsc::{Sender, Receiver};
+use std::{sync::mpsc::{Sender, Receiver}, path::{Path, PathBuf}, time::Instant};
 
-use allocator::{Allocator, BufferAndAllocation};
-use ash::{Entry, Instance, extensions::khr::{Surface, Swapchain}, vk::{SurfaceKHR, SwapchainKHR, ImageView, PhysicalDevice, RenderPass, ShaderModule, Framebuffer, DescriptorSetLayout, PipelineLayout, PipelineCache, DescriptorPool, DescriptorSet, Pipeline, Fence, CommandPool, Queue, CommandBuffer, PipelineStageFlags, SubmitInfo, StructureType, PresentInfoKHR}, Device};
-use functions::{image::ImageAndView, device::QueueInfo, synchronization::Synchronizer, buffer::UniformBufferObject};
-use math::{UniformBuffer, camera::Camera, ModelMatrix};
+use config::RendererConfig;
+
+use allocator::{Allocator, BufferAndAllocation, stats::AllocatorStats, frame::FrameAllocator};
+use error::{RendererError, RendererResult};
+use ash::{Entry, Instance, extensions::{khr::{Surface, Swapchain, GetPhysicalDeviceProperties2}, ext::DebugUtils}, vk::{KhrGetPhysicalDeviceProperties2Fn, ExtMemoryBudgetFn, Extent2D, ImageLayout, SurfaceKHR, SwapchainKHR, Image, ImageView, ImageUsageFlags, PhysicalDevice, RenderPass, ShaderModule, Framebuffer, DescriptorSetLayout, PipelineLayout, PipelineCache, DescriptorPool, DescriptorSet, Pipeline, Fence, CommandPool, Queue, CommandBuffer, CommandBufferUsageFlags, Buffer, PipelineStageFlags, SubmitInfo, StructureType, PresentInfoKHR}, Device};
+use functions::{image::ImageAndView, device::QueueInfo, synchronization::Synchronizer, vertex::InstanceBuffer, swapchain::SwapchainInfo, capture::ImageData};
+use math::{UniformBuffer, camera::{Camera, MovementKey, Projection, ViewPreset}, gizmo::{Gizmo, GizmoMode, GizmoSpace}, ModelMatrix, InstanceVertex, Vertex, BoundingBox};
+use cgmath::Point3;
 use rayon::ThreadPoolBuilder;
-use winit::{event_loop::{EventLoop, ControlFlow}, window::Window, event::{Event, WindowEvent, StartCause, VirtualKeyCode, DeviceEvent, MouseScrollDelta, MouseButton, ElementState}, dpi::PhysicalSize, platform::windows::EventLoopExtWindows};
-#[cfg(target_os="unix")]
-use winit::platform::{unix::EventLoopExtUnix, run_return::EventLoopExtRunReturn};
-#[cfg(target_os="windows")]
-use winit::platform::run_return::EventLoopExtRunReturn;
-const MAX_FRAMES_IN_FLIGHT : usize = 2;
+use scene::{Scene, InstanceHandle, PickResult};
+use mesh::{Mesh, MeshHandle};
+use winit::{event_loop::{EventLoop, ControlFlow}, window::{Window, WindowBuilder}, event::{Event, WindowEvent, StartCause, VirtualKeyCode, DeviceEvent, MouseScrollDelta, MouseButton, ElementState, ModifiersState}, dpi::PhysicalSize, platform::run_return::EventLoopExtRunReturn};
+#[cfg(windows)]
+use winit::platform::windows::EventLoopExtWindows;
+#[cfg(unix)]
+use winit::platform::unix::EventLoopExtUnix;
+
+const CLICK_TOLERANCE : f32 = 3.0;
+const OUTLINE_SCALE : f32 = 1.05;
 
 pub struct Renderer{
     sender : Sender<RenderTask>,
     receiver : Receiver<RenderResult>,
+    next_instance_handle : u64,
 }
 impl Renderer{
-    pub fn new(debug : bool) -> Self{
-        let thread_pool = ThreadPoolBuilder::new().build().expect("Failed to create threadpool");
+    pub fn new(config : RendererConfig) -> RendererResult<Self>{
+        let thread_pool = ThreadPoolBuilder::new().build()?;
         let (sender, receiver_render_thread) = std::sync::mpsc::channel();
         let (sender_render_thread, receiver) = std::sync::mpsc::channel();
         thread_pool.spawn(move ||{
-            println!("Created render thread");
             let mut event_loop : EventLoop<()> = EventLoop::new_any_thread();
-            let window = Window::new(&event_loop).expect("Failed to create render window");
-            let mut renderer = RenderOnThread::new(&window, debug);
+            let window = match WindowBuilder::new().with_title(&config.window_title).with_inner_size(PhysicalSize::new(config.window_size.0, config.window_size.1)).build(&event_loop){
+                Ok(window)=>{window}
+                Err(error)=>{sender_render_thread.send(RenderResult::Error(error.into())).ok(); return}
+            };
+            let window_size = window.inner_size();
+            let mut renderer = match RenderOnThread::new(Some(&window), Extent2D{width:window_size.width,height:window_size.height}, config){
+                Ok(renderer)=>{renderer}
+                Err(error)=>{sender_render_thread.send(RenderResult::Error(error)).ok(); return}
+            };
+            for warning in renderer.warnings.drain(..){sender_render_thread.send(RenderResult::Warning(warning)).ok();}
+            let mut result = RenderResult::Success;
+            let mut modifiers = ModifiersState::empty();
+            let mut click_position = None;
             event_loop.run_return(|event,_,control_flow|{
                 match event{
                     Event::WindowEvent{event,window_id:_}=>{
                         match event{
                             WindowEvent::CloseRequested=>{*control_flow=ControlFlow::Exit}
                             WindowEvent::KeyboardInput{device_id:_, is_synthetic:_, input}=>{
-                                if input.virtual_keycode == Some(VirtualKeyCode::F10){
-                                    renderer.allocator.dump_contents();
+                                let pressed = input.state == ElementState::Pressed;
+                                match input.virtual_keycode{
+                                    Some(VirtualKeyCode::F10) if pressed =>{
+                                        let path = PathBuf::from("allocator_stats.json");
+                                        match renderer.handle_task(RenderTask::DumpAllocatorStats(path.clone())){
+                                            Ok(_)=>{sender_render_thread.send(RenderResult::AllocatorStatsDumped(path)).ok();}
+                                            Err(error)=>{sender_render_thread.send(RenderResult::Error(error)).ok();}
+                                        }
+                                    }
+                                    Some(VirtualKeyCode::Tab) if pressed =>{
+                                        renderer.camera.toggle_mode();
+                                    }
+                                    Some(VirtualKeyCode::Numpad5) if pressed =>{
+                                        renderer.camera.toggle_projection_mode();
+                                    }
+                                    Some(VirtualKeyCode::Numpad7) if pressed =>{
+                                        renderer.camera.set_view_preset(if modifiers.ctrl(){ViewPreset::Bottom}else{ViewPreset::Top});
+                                    }
+                                    Some(VirtualKeyCode::Numpad1) if pressed =>{
+                                        renderer.camera.set_view_preset(if modifiers.ctrl(){ViewPreset::Back}else{ViewPreset::Front});
+                                    }
+                                    Some(VirtualKeyCode::Numpad3) if pressed =>{
+                                        renderer.camera.set_view_preset(if modifiers.ctrl(){ViewPreset::Left}else{ViewPreset::Right});
+                                    }
+                                    Some(VirtualKeyCode::Numpad9) if pressed =>{
+                                        renderer.camera.set_view_preset(ViewPreset::Isometric);
+                                    }
+                                    Some(VirtualKeyCode::T) if pressed =>{
+                                        renderer.set_gizmo_mode(GizmoMode::Translate);
+                                    }
+                                    Some(VirtualKeyCode::R) if pressed =>{
+                                        renderer.set_gizmo_mode(GizmoMode::Rotate);
+                                    }
+                                    Some(VirtualKeyCode::Y) if pressed =>{
+                                        renderer.set_gizmo_mode(GizmoMode::Scale);
+                                    }
+                                    Some(VirtualKeyCode::L) if pressed =>{
+                                        renderer.toggle_gizmo_space();
+                                    }
+                                    Some(VirtualKeyCode::Home) if pressed =>{
+                                        if let Err(error) = renderer.handle_task(RenderTask::FrameAll){sender_render_thread.send(RenderResult::Error(error)).ok();}
+                                    }
+                                    Some(key)=>{
+                                        if let Some(movement_key) = MovementKey::from_key(key){
+                                            renderer.camera.set_movement_key(movement_key, pressed);
+                                        }
+                                    }
+                                    None=>{}
                                 }
                             }
+                            WindowEvent::ModifiersChanged(state)=>{
+                                modifiers = state;
+                                renderer.gizmo.snapping = modifiers.ctrl();
+                            }
+                            WindowEvent::Focused(false)=>{
+                                renderer.camera.release_movement_keys();
+                            }
+                            WindowEvent::CursorMoved{position, ..}=>{
+                                renderer.camera.set_cursor_position((position.x as f32, position.y as f32));
+                                renderer.drag_gizmo((position.x as f32, position.y as f32));
+                            }
                             WindowEvent::MouseWheel{delta, .. }=>{
                                 match delta{
                                     MouseScrollDelta::LineDelta(_,y) =>{
                                         renderer.camera.mouse_zoom(y);
                                     }
                                     MouseScrollDelta::PixelDelta(delta)=>{
-                                        renderer.camera.mouse_zoom(delta.y as f32);
+                                        renderer.camera.mouse_zoom(delta.y as f32 / 100.0);
                                     }
                                 }
                             }
@@ -54,10 +134,31 @@ impl Renderer{
                                 match button{
                                     MouseButton::Left =>{
                                         match state{
-                                            ElementState::Pressed=>{renderer.camera.left_mouse_button_pressed = true}
-                                            ElementState::Released=>{renderer.camera.left_mouse_button_pressed = false}
+                                            ElementState::Pressed=>{
+                                                if renderer.camera.get_cursor_position().map_or(false, |cursor|renderer.begin_gizmo_drag(cursor)){return}
+                                                renderer.camera.left_mouse_button_pressed = true;
+                                                click_position = renderer.camera.get_cursor_position();
+                                            }
+                                            ElementState::Released=>{
+                                                if renderer.end_gizmo_drag(){return}
+                                                renderer.camera.left_mouse_button_pressed = false;
+                                                if let (Some(pressed), Some(released)) = (click_position.take(), renderer.camera.get_cursor_position()){
+                                                    if (pressed.0 - released.0).abs() <= CLICK_TOLERANCE && (pressed.1 - released.1).abs() <= CLICK_TOLERANCE{
+                                                        match renderer.pick(released){
+                                                            Ok(result)=>{
+                                                                renderer.scene.select(result.map(|result|result.handle), modifiers.shift());
+                                                                sender_render_thread.send(RenderResult::Clicked(result)).ok();
+                                                            }
+                                                            Err(error)=>{sender_render_thread.send(RenderResult::Error(error)).ok();}
+                                                        }
+                                                    }
+                                                }
+                                            }
                                         }
                                     }
+                                    MouseButton::Right | MouseButton::Middle =>{
+                                        renderer.camera.pan_mouse_button_pressed = state == ElementState::Pressed;
+                                    }
                                     _=>{}
                                 }
                             }
@@ -65,10 +166,26 @@ impl Renderer{
                         }
                     }
                     Event::RedrawRequested(_) => {
-                        let resized = renderer.draw();
-                        if resized{renderer.recreate_swapchain(window.inner_size());}
+                        let drawn = match renderer.draw(){
+                            Ok(true)=>{renderer.recreate_swapchain(window.inner_size())}
+                            Ok(false)=>{Ok(())}
+                            Err(error)=>{Err(error)}
+                        };
+                        if let Err(error) = drawn{
+                            result = RenderResult::Error(error);
+                            *control_flow=ControlFlow::Exit;
+                        }
                     }
                     Event::MainEventsCleared => {
+                        for task in receiver_render_thread.try_iter(){
+                            match task{
+                                RenderTask::Close=>{*control_flow=ControlFlow::Exit}
+                                _=>{
+                                    if let Err(error) = renderer.handle_task(task){sender_render_thread.send(RenderResult::Error(error)).ok();}
+                                }
+                            }
+                        }
+                        for warning in renderer.warnings.drain(..){sender_render_thread.send(RenderResult::Warning(warning)).ok();}
                         window.request_redraw();
                     }
                     Event::NewEvents(start) =>{
@@ -82,16 +199,6 @@ impl Renderer{
                             DeviceEvent::MouseMotion{delta}=>{
                                 renderer.camera.mouse_movement(delta);
                             }
-                            DeviceEvent::MouseWheel{delta} => {
-                                match delta{
-                                    MouseScrollDelta::LineDelta(_,y) =>{
-                                        renderer.camera.mouse_zoom(y);
-                                    }
-                                    MouseScrollDelta::PixelDelta(delta)=>{
-                                        renderer.camera.mouse_zoom(delta.y as f32);
-                                    }
-                                }
-                            }
                             _=>{}
                         }
                     }
@@ -99,39 +206,175 @@ impl Renderer{
                 }
             });
             drop(renderer);
-            sender_render_thread.send(RenderResult::Success).unwrap();
-            println!("Destroying render thread");
+            sender_render_thread.send(result).ok();
         });
-        return Self{
-            sender,receiver,
+        return Ok(Self{
+            sender,receiver,next_instance_handle:ModelMatrix::get_default().len() as u64,
+        })
+    }
+    pub fn new_headless(config : RendererConfig) -> RendererResult<Self>{
+        let thread_pool = ThreadPoolBuilder::new().build()?;
+        let (sender, receiver_render_thread) = std::sync::mpsc::channel();
+        let (sender_render_thread, receiver) = std::sync::mpsc::channel();
+        thread_pool.spawn(move ||{
+            let result = RenderOnThread::new(None, Extent2D{width:config.window_size.0,height:config.window_size.1}, config).and_then(|mut renderer|renderer.run_headless(&receiver_render_thread, &sender_render_thread));
+            match result{
+                Ok(())=>{sender_render_thread.send(RenderResult::Success).ok();}
+                Err(error)=>{sender_render_thread.send(RenderResult::Error(error)).ok();}
+            }
+        });
+        return Ok(Self{
+            sender,receiver,next_instance_handle:ModelMatrix::get_default().len() as u64,
+        })
+    }
+    pub fn send_task(&self, task : RenderTask) -> RendererResult<()>{
+        return self.sender.send(task).map_err(|_|RendererError::RenderThreadStopped);
+    }
+    pub fn add_instance(&mut self, matrix : ModelMatrix) -> RendererResult<InstanceHandle>{
+        let handle = InstanceHandle(self.next_instance_handle);
+        self.next_instance_handle += 1;
+        self.send_task(RenderTask::AddInstance(handle, matrix))?;
+        return Ok(handle);
+    }
+    pub fn update_instance(&self, handle : InstanceHandle, matrix : ModelMatrix) -> RendererResult<()>{
+        return self.send_task(RenderTask::UpdateInstance(handle, matrix));
+    }
+    pub fn remove_instance(&self, handle : InstanceHandle) -> RendererResult<()>{
+        return self.send_task(RenderTask::RemoveInstance(handle));
+    }
+    pub fn set_camera(&self, eye : Point3<f32>, center : Point3<f32>) -> RendererResult<()>{
+        return self.send_task(RenderTask::SetCamera(eye, center));
+    }
+    pub fn set_view_preset(&self, preset : ViewPreset) -> RendererResult<()>{
+        return self.send_task(RenderTask::SetViewPreset(preset));
+    }
+    pub fn set_projection(&self, projection : Projection) -> RendererResult<()>{
+        return self.send_task(RenderTask::SetProjection(projection));
+    }
+    pub fn frame_all(&self) -> RendererResult<()>{
+        return self.send_task(RenderTask::FrameAll);
+    }
+    pub fn frame_instances(&self, handles : Vec<InstanceHandle>) -> RendererResult<()>{
+        return self.send_task(RenderTask::FrameInstances(handles));
+    }
+    pub fn pick(&self, cursor_position : (f32, f32)) -> RendererResult<Option<PickResult>>{
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.send_task(RenderTask::Pick(cursor_position.0, cursor_position.1, sender))?;
+        return receiver.recv().map_err(|_|RendererError::RenderThreadStopped)?;
+    }
+    pub fn set_selection(&self, handles : Vec<InstanceHandle>) -> RendererResult<()>{
+        return self.send_task(RenderTask::SetSelection(handles));
+    }
+    pub fn select(&self, handle : Option<InstanceHandle>, extend : bool) -> RendererResult<()>{
+        return self.send_task(RenderTask::Select(handle, extend));
+    }
+    pub fn set_gizmo_mode(&self, mode : GizmoMode) -> RendererResult<()>{
+        return self.send_task(RenderTask::SetGizmoMode(mode));
+    }
+    pub fn set_gizmo_space(&self, space : GizmoSpace) -> RendererResult<()>{
+        return self.send_task(RenderTask::SetGizmoSpace(space));
+    }
+    pub fn set_gizmo_snapping(&self, snapping : bool) -> RendererResult<()>{
+        return self.send_task(RenderTask::SetGizmoSnapping(snapping));
+    }
+    pub fn load_mesh(&self, path : PathBuf) -> RendererResult<MeshHandle>{
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.send_task(RenderTask::LoadMesh(path, sender))?;
+        return receiver.recv().map_err(|_|RendererError::RenderThreadStopped)?;
+    }
+    pub fn set_instance_mesh(&self, handle : InstanceHandle, mesh : MeshHandle) -> RendererResult<()>{
+        return self.send_task(RenderTask::SetInstanceMesh(handle, mesh));
+    }
+    pub fn try_receive(&self) -> Option<RenderResult>{
+        return self.receiver.try_recv().ok();
+    }
+    pub fn save_screenshot(&self, path : PathBuf) -> RendererResult<()>{
+        return self.send_task(RenderTask::Screenshot(path));
+    }
+    pub fn read_pixels(&self) -> RendererResult<ImageData>{
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.send_task(RenderTask::ReadPixels(sender))?;
+        return receiver.recv().map_err(|_|RendererError::RenderThreadStopped)?;
+    }
+    pub fn get_allocator_stats(&self) -> RendererResult<AllocatorStats>{
+        let (sender, receiver) = std::sync::mpsc::channel();
+        self.send_task(RenderTask::GetAllocatorStats(sender))?;
+        return receiver.recv().map_err(|_|RendererError::RenderThreadStopped);
+    }
+    pub fn dump_allocator_stats(&self, path : PathBuf) -> RendererResult<()>{
+        return self.send_task(RenderTask::DumpAllocatorStats(path));
+    }
+    pub fn defragment(&self) -> RendererResult<()>{
+        return self.send_task(RenderTask::Defragment);
+    }
+    pub fn await_close_request(self) -> RendererResult<()>{
+        let mut last_error = None;
+        loop{
+            match self.receiver.recv(){
+                Ok(RenderResult::Success)=>{return Ok(())}
+                Ok(RenderResult::Error(error))=>{last_error = Some(error)}
+                Ok(_)=>{}
+                Err(_)=>{return Err(last_error.unwrap_or(RendererError::RenderThreadStopped))}
+            }
         }
     }
-    pub fn await_close_request(self){
-        self.receiver.recv().expect("Failed to block on render complete");
+    pub fn close(self) -> RendererResult<()>{
+        self.send_task(RenderTask::Close).ok();
+        return self.await_close_request();
     }
 }
 pub enum RenderTask{
     Draw,
+    AddInstance(InstanceHandle, ModelMatrix),
+    UpdateInstance(InstanceHandle, ModelMatrix),
+    RemoveInstance(InstanceHandle),
+    SetCamera(Point3<f32>, Point3<f32>),
+    SetViewPreset(ViewPreset),
+    SetProjection(Projection),
+    FrameAll,
+    FrameInstances(Vec<InstanceHandle>),
+    Pick(f32, f32, Sender<RendererResult<Option<PickResult>>>),
+    SetSelection(Vec<InstanceHandle>),
+    Select(Option<InstanceHandle>, bool),
+    SetGizmoMode(GizmoMode),
+    SetGizmoSpace(GizmoSpace),
+    SetGizmoSnapping(bool),
+    LoadMesh(PathBuf, Sender<RendererResult<MeshHandle>>),
+    SetInstanceMesh(InstanceHandle, MeshHandle),
+    Screenshot(PathBuf),
+    ReadPixels(Sender<RendererResult<ImageData>>),
+    GetAllocatorStats(Sender<AllocatorStats>),
+    DumpAllocatorStats(PathBuf),
+    Defragment,
+    Close,
 }
 pub enum RenderResult{
-    Success
+    Success,
+    AllocatorStatsDumped(PathBuf),
+    Clicked(Option<PickResult>),
+    Warning(String),
+    Error(RendererError),
 }
 struct RenderOnThread{
     _entry : Entry,
     instance : Instance,
-    surface_loader : Surface,
-    surface : SurfaceKHR,
+    surface : Option<(Surface, SurfaceKHR)>,
     physical_device : PhysicalDevice,
     queue_info : QueueInfo,
     device : Device,
-    swapchain_loader : Swapchain,
+    swapchain_loader : Option<Swapchain>,
     swapchain : SwapchainKHR,
+    swapchain_info : SwapchainInfo,
+    swapchain_images : Vec<Image>,
     swapchain_image_views : Vec<ImageView>,
+    last_image_index : Option<u32>,
+    last_frame_time : Instant,
+    color_image : Option<ImageAndView>,
     allocator : Allocator,
     depth_image : ImageAndView,
+    id_image : Option<ImageAndView>,
     render_pass : RenderPass,
     framebuffers : Vec<Framebuffer>,
-    uniform_buffer : UniformBufferObject,
     descriptor_set_layout : DescriptorSetLayout,
     descriptor_pool : DescriptorPool,
     descriptor_sets : Vec<DescriptorSet>,
@@ -142,82 +385,246 @@ struct RenderOnThread{
     graphics_command_pool : CommandPool,
     graphics_queue : Queue,
     vertex_buffers : Vec<(u32,BufferAndAllocation)>,
-    drawing_command_buffers : Vec<CommandBuffer>,
+    instance_buffer : InstanceBuffer<ModelMatrix>,
+    selection_buffer : InstanceBuffer<ModelMatrix>,
+    mesh_batches : Vec<(MeshHandle, u32, u32)>,
+    selection_batches : Vec<(MeshHandle, u32, u32)>,
+    meshes : Vec<Mesh>,
+    next_mesh_handle : u64,
+    frame_command_buffers : Vec<CommandBuffer>,
     synchronizer : Synchronizer,
+    frame_allocator : FrameAllocator,
     camera : Camera,
+    gizmo : Gizmo,
+    scene : Scene,
+    config : RendererConfig,
+    warnings : Vec<String>,
 }
 impl RenderOnThread{
-    pub fn new(window : &Window, debug : bool) -> Self{
-        let entry = unsafe{Entry::load()}.expect("Failed to load Vulkan drivers");
-        let instance = unsafe{functions::instance::create_instance(&entry, window, debug)};
-        let surface_loader = Surface::new(&entry, &instance);
-        let surface = unsafe{ash_window::create_surface(&entry, &instance, window, None)}.expect("Failed to create window");
-        let physical_device = functions::device::get_device_handle(&instance, &surface_loader, &surface);
+    pub fn new(window : Option<&Window>, extent : Extent2D, config : RendererConfig) -> RendererResult<Self>{
+        config.validate()?;
+        let entry = unsafe{Entry::load()}?;
+        let mut warnings = vec!();
+        let instance = unsafe{functions::instance::create_instance(&entry, window, &config, &mut warnings)}?;
+        let surface = match window{
+            Some(window)=>{Some((Surface::new(&entry, &instance), unsafe{ash_window::create_surface(&entry, &instance, window, None)}?))}
+            None=>{None}
+        };
+        let surface_handle = surface.as_ref().map(|(surface_loader, surface)|(surface_loader, *surface));
+        let physical_device = functions::device::get_device_handle(&instance, surface_handle, &config.device)?;
         let device_limits = unsafe{instance.get_physical_device_properties(physical_device)}.limits;
-        let queue_info = functions::device::QueueInfo::new(&instance, &surface_loader, &surface, physical_device);
-        let device = unsafe{functions::device::create_device(&instance, physical_device, &queue_info)};
-        let swapchain_loader = Swapchain::new(&instance, &device);
-        let swapchain_info = functions::swapchain::SwapchainInfo::new(&instance, physical_device, &surface_loader, surface, window.inner_size());
-        let swapchain = unsafe{functions::swapchain::create_swapchain(&swapchain_loader, &swapchain_info, &queue_info, surface)};
-        let swapchain_images = unsafe{swapchain_loader.get_swapchain_images(swapchain)}.expect("Failed to get swapchain images");
-        let swapchain_image_views = unsafe{functions::image::create_swapchain_image_views(&device, &swapchain_images, swapchain_info.surface_format)};
-        let mut allocator = unsafe{allocator::Allocator::new(&instance, physical_device, device.clone())};
-        let depth_image = unsafe{functions::image::create_depth_image(&device, &mut allocator, swapchain_info.extent, swapchain_info.depth_format)};
-        let render_pass = unsafe{functions::render_pass::create_render_pass(&device, swapchain_info.surface_format, swapchain_info.depth_format)};
-        let framebuffers = unsafe{functions::framebuffer::create_framebuffers(&device, &swapchain_image_views, depth_image.view, render_pass, swapchain_info.extent)};
-        let uniform_buffer = unsafe{functions::buffer::create_uniform_buffers(&device, &mut allocator, swapchain_image_views.len() as u32, &device_limits)};
-        let descriptor_set_layout = unsafe{functions::descriptor::create_descriptor_set_layout(&device)};
-        let pipeline_layout = unsafe{functions::pipeline::create_pipeline_layout(&device, &descriptor_set_layout)};
-        let pipeline_cache = unsafe{functions::pipeline::create_pipeline_cache(&device)};
-        let descriptor_pool = unsafe{functions::descriptor::create_descriptor_pool(&device, swapchain_image_views.len() as u32)};
-        let descriptor_sets = unsafe{functions::descriptor::create_descriptor_sets(&device, descriptor_set_layout, descriptor_pool, swapchain_image_views.len() as u32, uniform_buffer.buffer.buffer, &device_limits)};
-        let synchronizer = unsafe{Synchronizer::new(&device, swapchain_image_views.len() as u32)};
-        let shaders = unsafe{functions::shader::load_shaders(&device)};
-        let pipelines = unsafe{functions::pipeline::create_pipelines(&device, pipeline_cache, pipeline_layout, render_pass, &shaders, swapchain_info.extent)};
-        let graphics_command_pool = unsafe{functions::command::create_command_pool(&device, queue_info.graphics_family)};
+        let queue_info = functions::device::QueueInfo::new(&instance, surface_handle, physical_device)?;
+        let mut device_extensions = vec!();
+        if surface.is_some(){device_extensions.push(Swapchain::name())}
+        let memory_budget = functions::instance::supports_instance_extension(&entry, KhrGetPhysicalDeviceProperties2Fn::name())? && functions::device::supports_device_extension(&instance, physical_device, ExtMemoryBudgetFn::name())?;
+        if memory_budget{device_extensions.push(ExtMemoryBudgetFn::name())}
+        let device = unsafe{functions::device::create_device(&instance, physical_device, &queue_info, &device_extensions)}?;
+        let memory_budget_loader = if memory_budget{Some(GetPhysicalDeviceProperties2::new(&entry, &instance))}else{None};
+        let mut allocator = unsafe{allocator::Allocator::new(&instance, physical_device, device.clone(), memory_budget_loader)};
+        allocator.set_empty_blocks_kept(config.empty_blocks_kept);
+        if config.validation && functions::instance::supports_instance_extension(&entry, DebugUtils::name())?{
+            allocator.set_debug_utils(Some(DebugUtils::new(&entry, &instance)));
+        }
+        for heap_budget in config.heap_budgets.iter(){
+            allocator.set_heap_budget(heap_budget.heap, Some(heap_budget.size));
+        }
+        let swapchain_loader = surface.as_ref().map(|_|Swapchain::new(&instance, &device));
+        let (swapchain_info, swapchain, swapchain_images, swapchain_image_views, color_image) = match (&swapchain_loader, surface_handle){
+            (Some(swapchain_loader), Some((surface_loader, surface)))=>{
+                let swapchain_info = functions::swapchain::SwapchainInfo::new(&instance, physical_device, surface_loader, surface, PhysicalSize::new(extent.width, extent.height), &config)?;
+                let swapchain = unsafe{functions::swapchain::create_swapchain(swapchain_loader, &swapchain_info, &queue_info, surface)}?;
+                let swapchain_images = unsafe{swapchain_loader.get_swapchain_images(swapchain)}?;
+                let swapchain_image_views = unsafe{functions::image::create_swapchain_image_views(&device, &swapchain_images, swapchain_info.surface_format)}?;
+                (swapchain_info, swapchain, swapchain_images, swapchain_image_views, None)
+            }
+            _=>{
+                let swapchain_info = functions::swapchain::SwapchainInfo::new_headless(&instance, physical_device, extent, &config)?;
+                let color_image = unsafe{functions::image::create_color_image(&device, &mut allocator, swapchain_info.extent, swapchain_info.surface_format)}?;
+                (swapchain_info, SwapchainKHR::null(), vec!(color_image.image.image), vec!(color_image.view), Some(color_image))
+            }
+        };
+        let final_layout = if surface.is_some(){ImageLayout::PRESENT_SRC_KHR}else{ImageLayout::TRANSFER_SRC_OPTIMAL};
+        let depth_image = unsafe{functions::image::create_depth_image(&device, &mut allocator, swapchain_info.extent, swapchain_info.depth_format)}?;
+        let id_image = if swapchain_info.id_buffer{Some(unsafe{functions::image::create_id_image(&device, &mut allocator, swapchain_info.extent)}?)}else{None};
+        let render_pass = unsafe{functions::render_pass::create_render_pass(&device, swapchain_info.surface_format, swapchain_info.depth_format, final_layout, swapchain_info.id_buffer)}?;
+        let framebuffers = unsafe{functions::framebuffer::create_framebuffers(&device, &swapchain_image_views, depth_image.view, id_image.as_ref().map(|id_image|id_image.view), render_pass, swapchain_info.extent)}?;
+        let descriptor_set_layout = unsafe{functions::descriptor::create_descriptor_set_layout(&device)}?;
+        let pipeline_layout = unsafe{functions::pipeline::create_pipeline_layout(&device, &descriptor_set_layout)}?;
+        let pipeline_cache = unsafe{functions::pipeline::create_pipeline_cache(&device, config.get_pipeline_cache_path()?.as_deref())}?;
+        let synchronizer = unsafe{Synchronizer::new(&device, swapchain_image_views.len() as u32)}?;
+        let frame_allocator = unsafe{FrameAllocator::new(&mut allocator, synchronizer.in_flight_fences.len(), config.frame_arena_size, device_limits.min_uniform_buffer_offset_alignment.max(device_limits.non_coherent_atom_size))}?;
+        let descriptor_pool = unsafe{functions::descriptor::create_descriptor_pool(&device, synchronizer.in_flight_fences.len() as u32)}?;
+        let descriptor_sets = unsafe{functions::descriptor::create_descriptor_sets(&device, descriptor_set_layout, descriptor_pool, &frame_allocator.get_buffers())}?;
+        let shaders = unsafe{functions::shader::load_shaders(&device)}?;
+        let pipelines = unsafe{functions::pipeline::create_pipelines(&device, pipeline_cache, pipeline_layout, render_pass, &shaders, &swapchain_info)}?;
+        let graphics_command_pool = unsafe{functions::command::create_command_pool(&device, queue_info.graphics_family)}?;
         let graphics_queue = unsafe{device.get_device_queue(queue_info.graphics_family, 0)};
-        let instance_positions = ModelMatrix::get_default();
-        let vertex_buffers = unsafe{functions::vertex::create_vertex_buffers(&device, &mut allocator, graphics_command_pool, graphics_queue, instance_positions)};
-        let drawing_command_buffers = unsafe{functions::command::create_drawing_command_buffers(&device, graphics_command_pool, pipeline_layout, &pipelines, render_pass, &framebuffers, &descriptor_sets, &vertex_buffers, swapchain_info.extent)};
-        let camera = Camera::new(swapchain_info.extent);
-        return Self{
-            _entry:entry,instance,surface_loader,surface,physical_device,queue_info,device,swapchain_loader,swapchain,swapchain_image_views,allocator,depth_image,
-            render_pass,shaders,framebuffers,uniform_buffer,descriptor_set_layout,pipeline_layout,pipeline_cache,descriptor_pool,descriptor_sets,pipelines,synchronizer,
-            graphics_queue,graphics_command_pool,vertex_buffers,drawing_command_buffers,camera,
+        let scene = Scene::new(ModelMatrix::get_default());
+        let vertex_buffers = unsafe{functions::vertex::create_vertex_buffers(&device, &mut allocator, graphics_command_pool, graphics_queue)}?;
+        let instance_buffer = unsafe{InstanceBuffer::new(&device, &mut allocator, graphics_command_pool, graphics_queue, scene.get_model_matrices())}?;
+        let selection_buffer = unsafe{InstanceBuffer::new(&device, &mut allocator, graphics_command_pool, graphics_queue, vec!())}?;
+        let mesh_batches = scene.get_mesh_batches();
+        let selection_batches = scene.get_selection_batches();
+        let frame_command_buffers = unsafe{functions::command::create_command_buffers(&device, graphics_command_pool, synchronizer.in_flight_fences.len() as u32, false)}?;
+        let mut camera = Camera::new(swapchain_info.extent);
+        camera.sensitivity = config.camera_sensitivity;
+        camera.transition_time = config.camera_transition_time;
+        camera.set_reverse_z(config.reverse_z);
+        let gizmo = Gizmo::new(config.gizmo_snap);
+        return Ok(Self{
+            _entry:entry,instance,surface,physical_device,queue_info,device,swapchain_loader,swapchain,swapchain_info,swapchain_images,swapchain_image_views,last_image_index:None,last_frame_time:Instant::now(),color_image,allocator,depth_image,id_image,
+            render_pass,shaders,framebuffers,descriptor_set_layout,pipeline_layout,pipeline_cache,descriptor_pool,descriptor_sets,pipelines,synchronizer,frame_allocator,
+            graphics_queue,graphics_command_pool,vertex_buffers,instance_buffer,selection_buffer,mesh_batches,selection_batches,meshes:vec!(),next_mesh_handle:1,frame_command_buffers,camera,gizmo,scene,config,warnings,
+        })
+    }
+    pub fn pick(&mut self, cursor_position : (f32, f32)) -> RendererResult<Option<PickResult>>{
+        let triangles : Vec<[f32;3]> = InstanceVertex::get_initial_vertex_data().iter().map(|vertex|vertex.pos).collect();
+        let ray = self.camera.screen_ray(cursor_position);
+        let id_image = match (&self.id_image, self.last_image_index){
+            (Some(id_image), Some(_))=>{id_image.image.image}
+            _=>{return Ok(self.scene.pick(&ray, &triangles))}
+        };
+        let extent = self.swapchain_info.extent;
+        if cursor_position.0 < 0.0 || cursor_position.1 < 0.0 || cursor_position.0 >= extent.width as f32 || cursor_position.1 >= extent.height as f32{return Ok(None)}
+        let id = unsafe{
+            self.device.device_wait_idle()?;
+            functions::capture::read_id(&self.device, &mut self.allocator, self.graphics_command_pool, self.graphics_queue, id_image, cursor_position.0 as u32, cursor_position.1 as u32)
+        }?;
+        let handle = match id.checked_sub(1).and_then(|index|self.scene.get_handle(index as usize)){
+            Some(handle)=>{handle}
+            None=>{return Ok(None)}
+        };
+        return Ok(Some(self.scene.pick_instance(&ray, &triangles, handle)));
+    }
+    pub fn load_mesh(&mut self, path : &Path) -> RendererResult<MeshHandle>{
+        let data = mesh::obj::load_obj(path)?;
+        let handle = MeshHandle(self.next_mesh_handle);
+        let mesh = unsafe{Mesh::new(&self.device, &mut self.allocator, self.graphics_command_pool, self.graphics_queue, handle, &data)}?;
+        self.scene.add_mesh(handle, data.get_triangles());
+        self.meshes.push(mesh);
+        self.next_mesh_handle += 1;
+        self.warnings.extend(data.warnings.iter().map(|warning|format!("{}: {}", path.display(), warning)));
+        return Ok(handle);
+    }
+    fn get_instance_bounds(&self, handle : InstanceHandle) -> BoundingBox{
+        let default_bounds = InstanceVertex::get_initial_bounds();
+        return self.scene.get_instance_mesh(handle).map_or(default_bounds, |mesh|self.scene.get_mesh_bounds(mesh, &default_bounds));
+    }
+    pub fn set_gizmo_mode(&mut self, mode : GizmoMode){
+        if self.gizmo.mode == mode{return}
+        self.gizmo.end_drag();
+        self.gizmo.mode = mode;
+        self.scene.dirty = true;
+    }
+    pub fn set_gizmo_space(&mut self, space : GizmoSpace){
+        if self.gizmo.space == space{return}
+        self.gizmo.end_drag();
+        self.gizmo.space = space;
+        self.scene.dirty = true;
+    }
+    pub fn toggle_gizmo_space(&mut self){
+        self.gizmo.end_drag();
+        self.gizmo.toggle_space();
+        self.scene.dirty = true;
+    }
+    pub fn begin_gizmo_drag(&mut self, cursor_position : (f32, f32)) -> bool{
+        let (handle, instance) = match self.scene.get_active_instance(){
+            Some(active)=>{active}
+            None=>{return false}
+        };
+        let ray = self.camera.screen_ray(cursor_position);
+        let bounds = self.get_instance_bounds(handle);
+        if self.gizmo.begin_drag(&ray, &instance, &bounds).is_none(){return false}
+        self.scene.dirty = true;
+        return true;
+    }
+    pub fn drag_gizmo(&mut self, cursor_position : (f32, f32)){
+        if !self.gizmo.is_dragging(){return}
+        let handle = match self.scene.get_active_instance(){
+            Some((handle, _))=>{handle}
+            None=>{
+                self.gizmo.end_drag();
+                return;
+            }
+        };
+        if let Some(matrix) = self.gizmo.drag(&self.camera.screen_ray(cursor_position)){
+            self.scene.update_instance(handle, matrix);
         }
     }
-    pub fn draw(&mut self) -> bool{ 
+    pub fn end_gizmo_drag(&mut self) -> bool{
+        if !self.gizmo.end_drag(){return false}
+        self.scene.dirty = true;
+        return true;
+    }
+    pub fn draw(&mut self) -> RendererResult<bool>{
+        return self.draw_frame(None);
+    }
+    fn draw_frame(&mut self, capture : Option<Buffer>) -> RendererResult<bool>{
         let wait_fences = [self.synchronizer.in_flight_fences[self.synchronizer.current_frame]];
         unsafe{
-            self.device.wait_for_fences(&wait_fences, true, u64::MAX).expect("Failed to wait for fences");
+            self.device.wait_for_fences(&wait_fences, true, u64::MAX)?;
         }
-        let (image_index, suboptimal) = match unsafe{self.swapchain_loader.acquire_next_image(self.swapchain, u64::MAX, self.synchronizer.image_available_semaphores[self.synchronizer.current_frame], Fence::null())}{
-            Ok(tuple)=>{tuple}
-            Err(error)=>{
-                match error{
-                    ash::vk::Result::ERROR_OUT_OF_DATE_KHR=>{return true}
-                    _=>{panic!("Failed to draw frame");}
+        unsafe{self.frame_allocator.begin_frame(&mut self.allocator, self.synchronizer.current_frame)};
+        let (image_index, suboptimal) = match &self.swapchain_loader{
+            Some(swapchain_loader)=>{
+                match unsafe{swapchain_loader.acquire_next_image(self.swapchain, u64::MAX, self.synchronizer.image_available_semaphores[self.synchronizer.current_frame], Fence::null())}{
+                    Ok(tuple)=>{tuple}
+                    Err(error)=>{
+                        match error{
+                            ash::vk::Result::ERROR_OUT_OF_DATE_KHR if capture.is_none()=>{return Ok(true)}
+                            _=>{return Err(error.into())}
+                        }
+                    }
                 }
             }
+            None=>{(0,false)}
         };
+        let frame_time = Instant::now();
+        self.camera.advance(frame_time.duration_since(self.last_frame_time).as_secs_f32());
+        self.last_frame_time = frame_time;
+        if self.config.auto_clip_planes{
+            let bounds = self.scene.get_bounds(&InstanceVertex::get_initial_bounds()).map_or(Vertex::get_grid_bounds(), |bounds|bounds.union(&Vertex::get_grid_bounds()));
+            self.camera.fit_clip_planes(&bounds);
+        }
         self.camera.update();
-        unsafe{self.update_uniform_buffer(image_index, self.camera.matrix)};
-        let wait_semaphores = [self.synchronizer.image_available_semaphores[self.synchronizer.current_frame]];
+        let command_buffer = self.frame_command_buffers[self.synchronizer.current_frame];
+        unsafe{self.record_frame(command_buffer, image_index)}?;
+        let mut command_buffers = vec!(command_buffer);
+        let capture_command_buffers = match capture{
+            Some(buffer)=>{
+                let layout = if self.color_image.is_some(){ImageLayout::TRANSFER_SRC_OPTIMAL}else{ImageLayout::PRESENT_SRC_KHR};
+                unsafe{functions::capture::record_image_copy(&self.device, self.graphics_command_pool, self.swapchain_images[image_index as usize], layout, self.swapchain_info.extent, buffer)}?
+            }
+            None=>{vec!()}
+        };
+        command_buffers.extend_from_slice(&capture_command_buffers);
+        let presenting = self.swapchain_loader.is_some();
+        let wait_semaphores = if presenting{vec!(self.synchronizer.image_available_semaphores[self.synchronizer.current_frame])}else{vec!()};
         let wait_stages = [PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
-        let signal_semaphores = [self.synchronizer.render_finished_semaphores[self.synchronizer.current_frame]];
+        let signal_semaphores = if presenting{vec!(self.synchronizer.render_finished_semaphores[self.synchronizer.current_frame])}else{vec!()};
         let submit_infos = [SubmitInfo {
             s_type: StructureType::SUBMIT_INFO,
             p_next: std::ptr::null(),
             wait_semaphore_count: wait_semaphores.len() as u32,
             p_wait_semaphores: wait_semaphores.as_ptr(),
             p_wait_dst_stage_mask: wait_stages.as_ptr(),
-            command_buffer_count: 1,
-            p_command_buffers: &self.drawing_command_buffers[image_index as usize],
+            command_buffer_count: command_buffers.len() as u32,
+            p_command_buffers: command_buffers.as_ptr(),
             signal_semaphore_count: signal_semaphores.len() as u32,
             p_signal_semaphores: signal_semaphores.as_ptr(),
         }];
-        unsafe{self.device.reset_fences(&wait_fences)}.expect("Failed to reset fences");
-        unsafe{self.device.queue_submit(self.graphics_queue, &submit_infos, self.synchronizer.in_flight_fences[self.synchronizer.current_frame])}.expect("Failed to submit queue");
+        unsafe{self.device.reset_fences(&wait_fences)}?;
+        if let Err(error) = unsafe{self.device.queue_submit(self.graphics_queue, &submit_infos, wait_fences[0])}{
+            if !capture_command_buffers.is_empty(){unsafe{self.device.free_command_buffers(self.graphics_command_pool, &capture_command_buffers)}}
+            return Err(error.into());
+        }
+        if self.swapchain_loader.is_none(){unsafe{self.finish_capture(&wait_fences, &capture_command_buffers)}?;}
+        self.last_image_index = Some(image_index);
+        self.synchronizer.current_frame = (self.synchronizer.current_frame + 1) % self.config.max_frames_in_flight.min(self.synchronizer.in_flight_fences.len());
+        let swapchain_loader = match &self.swapchain_loader{
+            Some(swapchain_loader)=>{swapchain_loader}
+            None=>{return Ok(false)}
+        };
         let swapchains = [self.swapchain];
         let present_info = PresentInfoKHR {
             s_type: StructureType::PRESENT_INFO_KHR,
@@ -229,35 +636,170 @@ impl RenderOnThread{
             p_image_indices: &image_index,
             p_results: std::ptr::null_mut(),
         };
-        self.synchronizer.current_frame = (self.synchronizer.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
-        match unsafe{self.swapchain_loader.queue_present(self.graphics_queue, &present_info)}{
-            Ok(sub) => {return sub||suboptimal}
+        let presented = unsafe{swapchain_loader.queue_present(self.graphics_queue, &present_info)};
+        unsafe{self.finish_capture(&wait_fences, &capture_command_buffers)}?;
+        match presented{
+            Ok(sub) => {return Ok(sub||suboptimal)}
             Err(error) => {
                 match error{
-                    ash::vk::Result::ERROR_OUT_OF_DATE_KHR => {return true}
-                    _=>{panic!("Queue present failed")}
+                    ash::vk::Result::ERROR_OUT_OF_DATE_KHR => {return Ok(true)}
+                    _=>{return Err(error.into())}
                 }
             }
         };
     }
-    pub fn recreate_swapchain(&mut self, window_size : PhysicalSize<u32>){
-        unsafe{self.destroy_swapchain()}
-        let swapchain_info = functions::swapchain::SwapchainInfo::new(&self.instance, self.physical_device, &self.surface_loader, self.surface, window_size);
-        self.swapchain = unsafe{functions::swapchain::create_swapchain(&self.swapchain_loader, &swapchain_info, &self.queue_info, self.surface)};
-        let swapchain_images = unsafe{self.swapchain_loader.get_swapchain_images(self.swapchain).expect("Failed to get swapchain images")};
-        self.swapchain_image_views = unsafe{functions::image::create_swapchain_image_views(&self.device, &swapchain_images, swapchain_info.surface_format)};
-        self.depth_image = unsafe{functions::image::create_depth_image(&self.device, &mut self.allocator, swapchain_info.extent, swapchain_info.depth_format)};
-        self.framebuffers = unsafe{functions::framebuffer::create_framebuffers(&self.device, &self.swapchain_image_views, self.depth_image.view, self.render_pass, swapchain_info.extent)};
-        self.pipelines = unsafe{functions::pipeline::create_pipelines(&self.device, self.pipeline_cache, self.pipeline_layout, self.render_pass, &self.shaders, swapchain_info.extent)};
-        self.drawing_command_buffers = unsafe{functions::command::create_drawing_command_buffers(&self.device, self.graphics_command_pool, self.pipeline_layout, &self.pipelines, self.render_pass, &self.framebuffers, &self.descriptor_sets, &self.vertex_buffers, swapchain_info.extent)};
+    unsafe fn finish_capture(&self, fences : &[Fence], command_buffers : &Vec<CommandBuffer>) -> RendererResult<()>{
+        if command_buffers.is_empty(){return Ok(())}
+        let waited = self.device.wait_for_fences(fences, true, u64::MAX);
+        self.device.free_command_buffers(self.graphics_command_pool, command_buffers);
+        return Ok(waited?);
+    }
+    pub fn recreate_swapchain(&mut self, window_size : PhysicalSize<u32>) -> RendererResult<()>{
+        let (swapchain_loader, surface_loader, surface) = match (&self.swapchain_loader, &self.surface){
+            (Some(swapchain_loader), Some((surface_loader, surface)))=>{(swapchain_loader.clone(), surface_loader.clone(), *surface)}
+            _=>{return Ok(())}
+        };
+        unsafe{self.destroy_swapchain()}?;
+        let swapchain_info = functions::swapchain::SwapchainInfo::new(&self.instance, self.physical_device, &surface_loader, surface, window_size, &self.config)?;
+        self.swapchain = unsafe{functions::swapchain::create_swapchain(&swapchain_loader, &swapchain_info, &self.queue_info, surface)}?;
+        self.swapchain_images = unsafe{swapchain_loader.get_swapchain_images(self.swapchain)}?;
+        self.swapchain_image_views = unsafe{functions::image::create_swapchain_image_views(&self.device, &self.swapchain_images, swapchain_info.surface_format)}?;
+        self.depth_image = unsafe{functions::image::create_depth_image(&self.device, &mut self.allocator, swapchain_info.extent, swapchain_info.depth_format)}?;
+        self.id_image = if swapchain_info.id_buffer{Some(unsafe{functions::image::create_id_image(&self.device, &mut self.allocator, swapchain_info.extent)}?)}else{None};
+        self.framebuffers = unsafe{functions::framebuffer::create_framebuffers(&self.device, &self.swapchain_image_views, self.depth_image.view, self.id_image.as_ref().map(|id_image|id_image.view), self.render_pass, swapchain_info.extent)}?;
+        self.pipelines = unsafe{functions::pipeline::create_pipelines(&self.device, self.pipeline_cache, self.pipeline_layout, self.render_pass, &self.shaders, &swapchain_info)}?;
         self.camera.correct_perspective(swapchain_info.extent);
+        self.swapchain_info = swapchain_info;
+        self.last_image_index = None;
+        return Ok(());
+    }
+    fn run_headless(&mut self, receiver : &Receiver<RenderTask>, sender : &Sender<RenderResult>) -> RendererResult<()>{
+        for warning in self.warnings.drain(..){sender.send(RenderResult::Warning(warning)).ok();}
+        for task in receiver.iter(){
+            match task{
+                RenderTask::Close=>{break}
+                RenderTask::Draw=>{self.draw()?;}
+                _=>{
+                    if let Err(error) = self.handle_task(task){sender.send(RenderResult::Error(error)).ok();}
+                    for warning in self.warnings.drain(..){sender.send(RenderResult::Warning(warning)).ok();}
+                }
+            }
+        }
+        return Ok(());
+    }
+    pub fn handle_task(&mut self, task : RenderTask) -> RendererResult<()>{
+        match task{
+            RenderTask::Draw | RenderTask::Close=>{}
+            RenderTask::AddInstance(handle, matrix)=>{self.scene.add_instance(handle, matrix)}
+            RenderTask::UpdateInstance(handle, matrix)=>{self.scene.update_instance(handle, matrix);}
+            RenderTask::RemoveInstance(handle)=>{self.scene.remove_instance(handle);}
+            RenderTask::SetCamera(eye, center)=>{self.camera.look_at(eye, center)}
+            RenderTask::SetViewPreset(preset)=>{
+                self.camera.set_view_preset(preset);
+                if self.swapchain_loader.is_none(){self.camera.finish_transition()}
+            }
+            RenderTask::SetProjection(projection)=>{
+                self.camera.set_projection_mode(projection);
+                if self.swapchain_loader.is_none(){self.camera.finish_transition()}
+            }
+            RenderTask::FrameAll=>{
+                let bounds = self.scene.get_bounds(&InstanceVertex::get_initial_bounds()).unwrap_or(Vertex::get_grid_bounds());
+                self.camera.frame_bounds(&bounds);
+                if self.swapchain_loader.is_none(){self.camera.finish_transition()}
+            }
+            RenderTask::FrameInstances(handles)=>{
+                if let Some(bounds) = self.scene.get_instances_bounds(&handles, &InstanceVertex::get_initial_bounds()){
+                    self.camera.frame_bounds(&bounds);
+                    if self.swapchain_loader.is_none(){self.camera.finish_transition()}
+                }
+            }
+            RenderTask::Pick(x, y, reply)=>{reply.send(self.pick((x, y))).ok();}
+            RenderTask::SetSelection(handles)=>{self.scene.set_selection(handles)}
+            RenderTask::Select(handle, extend)=>{self.scene.select(handle, extend)}
+            RenderTask::SetGizmoMode(mode)=>{self.set_gizmo_mode(mode)}
+            RenderTask::SetGizmoSpace(space)=>{self.set_gizmo_space(space)}
+            RenderTask::SetGizmoSnapping(snapping)=>{self.gizmo.snapping = snapping}
+            RenderTask::LoadMesh(path, reply)=>{reply.send(self.load_mesh(&path)).ok();}
+            RenderTask::SetInstanceMesh(handle, mesh)=>{self.scene.set_instance_mesh(handle, mesh);}
+            RenderTask::Screenshot(path)=>{self.capture_frame()?.save(&path)?}
+            RenderTask::ReadPixels(reply)=>{reply.send(self.capture_frame()).ok();}
+            RenderTask::GetAllocatorStats(reply)=>{reply.send(self.allocator.get_stats()).ok();}
+            RenderTask::DumpAllocatorStats(path)=>{std::fs::write(path, self.allocator.get_stats_json())?}
+            RenderTask::Defragment=>{self.defragment()?}
+        }
+        return Ok(());
+    }
+    pub fn capture_frame(&mut self) -> RendererResult<ImageData>{
+        if !self.swapchain_info.image_usage.contains(ImageUsageFlags::TRANSFER_SRC){return Err(RendererError::NoFormat("screenshot"))}
+        functions::capture::get_swizzle(self.swapchain_info.surface_format)?;
+        let extent = self.swapchain_info.extent;
+        let readback_buffer = unsafe{functions::buffer::create_readback_buffer(&mut self.allocator, extent.width as u64 * extent.height as u64 * 4)}?;
+        let drawn = self.draw_frame(Some(readback_buffer.buffer)).and_then(|_|unsafe{functions::capture::read_pixels(&mut self.allocator, &readback_buffer, self.swapchain_info.surface_format, extent)});
+        unsafe{readback_buffer.destroy(&mut self.allocator)};
+        return drawn;
+    }
+    unsafe fn record_frame(&mut self, command_buffer : CommandBuffer, image_index : u32) -> RendererResult<()>{
+        let uniform_size = std::mem::size_of::<UniformBuffer>() as u64;
+        let uniforms = self.frame_allocator.allocate(uniform_size, 1).ok_or(RendererError::FrameArenaExhausted(uniform_size))?;
+        uniforms.write(&[self.camera.matrix]);
+        self.frame_allocator.flush(&self.allocator, &uniforms)?;
+        let gizmo_vertices = match self.scene.get_active_instance(){
+            Some((handle, instance))=>{self.gizmo.get_vertices(&instance.matrix, &self.get_instance_bounds(handle))}
+            None=>{vec!()}
+        };
+        let gizmo_lines = if gizmo_vertices.is_empty(){None}else{
+            let lines_size = std::mem::size_of_val(gizmo_vertices.as_slice()) as u64;
+            let lines = self.frame_allocator.allocate(lines_size, std::mem::align_of::<Vertex>() as u64).ok_or(RendererError::FrameArenaExhausted(lines_size))?;
+            lines.write(&gizmo_vertices);
+            self.frame_allocator.flush(&self.allocator, &lines)?;
+            Some((gizmo_vertices.len() as u32, lines.buffer, lines.offset))
+        };
+        functions::command::begin_primary_command_buffers(&self.device, &[command_buffer], CommandBufferUsageFlags::ONE_TIME_SUBMIT)?;
+        if self.scene.dirty{self.update_instance_buffers(command_buffer)?}
+        let inputs = functions::command::DrawInputs{
+            pipeline_layout : self.pipeline_layout,
+            pipelines : &self.pipelines,
+            render_pass : self.render_pass,
+            descriptor_set : self.descriptor_sets[self.synchronizer.current_frame],
+            uniform_offset : uniforms.offset as u32,
+            vertex_buffers : &self.vertex_buffers,
+            instance_buffer : self.instance_buffer.buffer.buffer,
+            selection_buffer : (self.selection_buffer.count, self.selection_buffer.buffer.buffer),
+            gizmo_lines,
+            meshes : &self.meshes,
+            batches : &self.mesh_batches,
+            selection_batches : &self.selection_batches,
+            extent : self.swapchain_info.extent,
+            clear_values : &self.swapchain_info.get_clear_values(),
+            outline : self.swapchain_info.has_stencil(),
+        };
+        functions::command::record_drawing_commands(&self.device, command_buffer, self.framebuffers[image_index as usize], &inputs);
+        return functions::command::end_command_buffers(&self.device, &[command_buffer]);
     }
-    unsafe fn update_uniform_buffer(&self, current_frame : u32, object : UniformBuffer){
-        self.uniform_buffer.update_uniform_buffer(object, current_frame, &self.device);
+    unsafe fn update_instance_buffers(&mut self, command_buffer : CommandBuffer) -> RendererResult<()>{
+        self.instance_buffer.update(&self.device, &mut self.allocator, &mut self.frame_allocator, command_buffer, self.scene.get_model_matrices())?;
+        self.selection_buffer.update(&self.device, &mut self.allocator, &mut self.frame_allocator, command_buffer, self.scene.get_selection_matrices(&InstanceVertex::get_initial_bounds(), OUTLINE_SCALE))?;
+        self.mesh_batches = self.scene.get_mesh_batches();
+        self.selection_batches = self.scene.get_selection_batches();
+        self.scene.dirty = false;
+        return Ok(());
     }
-    unsafe fn destroy_swapchain(&mut self){
-        self.device.device_wait_idle().expect("Failed to wait for device");
-        self.device.free_command_buffers(self.graphics_command_pool, &self.drawing_command_buffers);
+    pub fn defragment(&mut self) -> RendererResult<()>{
+        unsafe{
+            self.device.device_wait_idle()?;
+            let mut buffers = self.vertex_buffers.iter_mut().map(|(_, buffer)|buffer).collect::<Vec<_>>();
+            buffers.push(&mut self.instance_buffer.buffer);
+            buffers.push(&mut self.selection_buffer.buffer);
+            for mesh in self.meshes.iter_mut(){
+                buffers.push(&mut mesh.vertex_buffer.1);
+                buffers.push(&mut mesh.index_buffer.1);
+            }
+            self.allocator.defragment(&mut buffers, self.graphics_command_pool, self.graphics_queue)?;
+        }
+        return Ok(());
+    }
+    unsafe fn destroy_swapchain(&mut self) -> RendererResult<()>{
+        self.device.device_wait_idle()?;
         for &pipeline in self.pipelines.iter(){
             self.device.destroy_pipeline(pipeline, None);
         }
@@ -265,36 +807,52 @@ impl RenderOnThread{
             self.device.destroy_framebuffer(framebuffer, None);
         }
         self.depth_image.destroy(&mut self.allocator);
-        for &image_view in self.swapchain_image_views.iter(){
-            self.device.destroy_image_view(image_view, None);
+        if let Some(id_image) = self.id_image.take(){id_image.destroy(&mut self.allocator)}
+        match &self.color_image{
+            Some(color_image)=>{color_image.destroy(&mut self.allocator)}
+            None=>{
+                for &image_view in self.swapchain_image_views.iter(){
+                    self.device.destroy_image_view(image_view, None);
+                }
+            }
         }
-        self.swapchain_loader.destroy_swapchain(self.swapchain, None);
+        if let Some(swapchain_loader) = &self.swapchain_loader{
+            swapchain_loader.destroy_swapchain(self.swapchain, None);
+        }
+        return Ok(());
     }
 }
 impl Drop for RenderOnThread{
     fn drop(&mut self) {
         unsafe{
-            self.device.device_wait_idle().expect("Failed to wait for device handle to finish");
+            self.device.device_wait_idle().ok();
 
-            self.destroy_swapchain();
+            self.destroy_swapchain().ok();
             for &shader in self.shaders.iter(){
                 self.device.destroy_shader_module(shader, None);
             }
-            functions::pipeline::save_pipeline_cache(&self.device, self.pipeline_cache);
+            functions::pipeline::save_pipeline_cache(&self.device, self.pipeline_cache, self.config.get_pipeline_cache_path().ok().flatten().as_deref()).ok();
             for buffer in self.vertex_buffers.iter(){
                 buffer.1.destroy(&mut self.allocator);
             }
+            self.instance_buffer.destroy(&mut self.allocator);
+            self.selection_buffer.destroy(&mut self.allocator);
+            for mesh in self.meshes.iter(){
+                mesh.destroy(&mut self.allocator);
+            }
             self.synchronizer.destroy(&self.device);
             self.device.destroy_command_pool(self.graphics_command_pool, None);
             self.device.destroy_descriptor_pool(self.descriptor_pool, None);
             self.device.destroy_pipeline_cache(self.pipeline_cache, None);
             self.device.destroy_pipeline_layout(self.pipeline_layout, None);
             self.device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
-            self.uniform_buffer.buffer.destroy(&mut self.allocator);
+            self.frame_allocator.destroy(&mut self.allocator);
             self.device.destroy_render_pass(self.render_pass, None);
             self.allocator.destroy();
             self.device.destroy_device(None);
-            self.surface_loader.destroy_surface(self.surface, None);
+            if let Some((surface_loader, surface)) = &self.surface{
+                surface_loader.destroy_surface(*surface, None);
+            }
             self.instance.destroy_instance(None);
         }
     }
@@ -1,8 +1,106 @@
 use ash::vk::Extent2D;
-use cgmath::{Matrix4, SquareMatrix, Deg, Point3, Vector3, Quaternion, InnerSpace};
+use cgmath::{Matrix4, SquareMatrix, Deg, Rad, Point3, Vector3, Vector4, InnerSpace, Angle};
+use serde::{Serialize, Deserialize};
+use winit::event::VirtualKeyCode;
 
-use super::UniformBuffer;
+use crate::config::CameraSensitivity;
 
+use super::{UniformBuffer, BoundingBox, ray::Ray};
+
+const MAX_PITCH : f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+const MIN_DISTANCE : f32 = 0.01;
+const MIN_TRANSITION_FOV : f32 = 1.0;
+const DEFAULT_NEAR : f32 = 0.1;
+const DEFAULT_FAR : f32 = 10.0;
+const NEAR_FAR_RATIO : f32 = 0.001;
+const REVERSE_Z_NEAR_FAR_RATIO : f32 = 0.00001;
+const CLIP_PLANE_MARGIN : f32 = 0.05;
+const FRAME_MARGIN : f32 = 1.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Projection{
+    Perspective,
+    Orthographic,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ViewPreset{
+    Top,
+    Bottom,
+    Front,
+    Back,
+    Left,
+    Right,
+    Isometric,
+}
+impl ViewPreset{
+    pub fn get_angles(&self) -> (Rad<f32>, Rad<f32>){
+        let quarter = std::f32::consts::FRAC_PI_2;
+        return match self{
+            Self::Top=>{(Rad(-quarter), Rad(MAX_PITCH))}
+            Self::Bottom=>{(Rad(-quarter), Rad(-MAX_PITCH))}
+            Self::Front=>{(Rad(-quarter), Rad(0.0))}
+            Self::Back=>{(Rad(quarter), Rad(0.0))}
+            Self::Left=>{(Rad(2.0 * quarter), Rad(0.0))}
+            Self::Right=>{(Rad(0.0), Rad(0.0))}
+            Self::Isometric=>{(Rad(quarter / 2.0), Rad(std::f32::consts::FRAC_1_SQRT_2.atan()))}
+        };
+    }
+}
+#[derive(Clone, Copy, Debug)]
+struct CameraState{
+    center : Point3<f32>,
+    yaw : Rad<f32>,
+    pitch : Rad<f32>,
+    distance : f32,
+    orthographic : f32,
+}
+#[derive(Clone, Copy, Debug)]
+struct CameraTransition{
+    from : CameraState,
+    to : CameraState,
+    elapsed : f32,
+    duration : f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraMode{
+    Orbit,
+    Fly,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovementKey{
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Up,
+    Down,
+    Fast,
+}
+impl MovementKey{
+    pub fn from_key(key : VirtualKeyCode) -> Option<Self>{
+        return match key{
+            VirtualKeyCode::W=>{Some(Self::Forward)}
+            VirtualKeyCode::S=>{Some(Self::Backward)}
+            VirtualKeyCode::A=>{Some(Self::Left)}
+            VirtualKeyCode::D=>{Some(Self::Right)}
+            VirtualKeyCode::E=>{Some(Self::Up)}
+            VirtualKeyCode::Q=>{Some(Self::Down)}
+            VirtualKeyCode::LShift | VirtualKeyCode::RShift=>{Some(Self::Fast)}
+            _=>{None}
+        };
+    }
+}
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MovementKeys{
+    pub forward : bool,
+    pub backward : bool,
+    pub left : bool,
+    pub right : bool,
+    pub up : bool,
+    pub down : bool,
+    pub fast : bool,
+}
 pub struct Camera{
     model : Matrix4<f32>,
     view : Matrix4<f32>,
@@ -11,22 +109,36 @@ pub struct Camera{
     far : f32,
     fov : f32,
     aspect : f32,
+    extent : Extent2D,
     eye : Point3<f32>,
     center : Point3<f32>,
     up : Vector3<f32>,
+    yaw : Rad<f32>,
+    pitch : Rad<f32>,
+    distance : f32,
+    cursor_position : Option<(f32, f32)>,
+    mode : CameraMode,
+    movement : MovementKeys,
+    projection_mode : Projection,
+    orthographic : f32,
+    transition : Option<CameraTransition>,
+    reverse_z : bool,
+    pub transition_time : f32,
     pub matrix : UniformBuffer,
+    pub sensitivity : CameraSensitivity,
     pub left_mouse_button_pressed : bool,
+    pub pan_mouse_button_pressed : bool,
 }
 impl Camera{
     pub fn new(extent : Extent2D) -> Self{
-        let near = 0.1;
-        let far = 10.0;
+        let near = DEFAULT_NEAR;
+        let far = DEFAULT_FAR;
         let fov = 70.0;
         let aspect = extent.width as f32 / extent.height as f32;
         let projection = cgmath::perspective(
-            Deg(fov), 
-            aspect, 
-            near, 
+            Deg(fov),
+            aspect,
+            near,
             far,
         );
         let eye = Point3::new(2.0, 2.0, 2.0);
@@ -34,7 +146,7 @@ impl Camera{
         let up = Vector3::new(0.0, 0.0, 1.0);
         let view = Matrix4::look_at_rh(eye,center, up);
         let model = Matrix4::identity();
-        return Self{
+        let mut camera = Self{
             matrix : UniformBuffer{matrix:projection*view*model},
             projection,
             view,
@@ -43,37 +155,359 @@ impl Camera{
             far,
             fov,
             aspect,
+            extent,
             eye,
             center,
             up,
+            yaw : Rad(0.0),
+            pitch : Rad(0.0),
+            distance : 0.0,
+            cursor_position : None,
+            mode : CameraMode::Orbit,
+            movement : MovementKeys::default(),
+            projection_mode : Projection::Perspective,
+            orthographic : 0.0,
+            transition : None,
+            reverse_z : false,
+            transition_time : 0.3,
+            sensitivity : CameraSensitivity::default(),
             left_mouse_button_pressed : false,
-        }
+            pan_mouse_button_pressed : false,
+        };
+        camera.look_at(eye, center);
+        return camera;
     }
     pub fn correct_perspective(&mut self, extent : Extent2D){
+        self.extent = extent;
         self.aspect = extent.width as f32 / extent.height as f32;
-        self.projection = cgmath::perspective(Deg(self.fov), self.aspect, self.near, self.far);
+        self.update_projection();
+    }
+    pub fn get_clip_planes(&self) -> (f32, f32){
+        return (self.near, self.far);
+    }
+    pub fn set_clip_planes(&mut self, near : f32, far : f32){
+        self.near = near;
+        self.far = far;
+        self.update_projection();
+    }
+    pub fn is_reverse_z(&self) -> bool{
+        return self.reverse_z;
+    }
+    pub fn set_reverse_z(&mut self, reverse_z : bool){
+        self.reverse_z = reverse_z;
+        self.update_projection();
+    }
+    pub fn fit_clip_planes(&mut self, bounds : &BoundingBox){
+        let extra_distance = self.distance * (self.get_dolly_factor() - 1.0);
+        let mut min_depth = f32::MAX;
+        let mut max_depth = f32::MIN;
+        for corner in bounds.get_corners(){
+            let depth = -(self.view * corner.to_homogeneous()).z - extra_distance;
+            min_depth = min_depth.min(depth);
+            max_depth = max_depth.max(depth);
+        }
+        if max_depth <= 0.0{
+            self.set_clip_planes(DEFAULT_NEAR, DEFAULT_FAR);
+            return;
+        }
+        let far = max_depth * (1.0 + CLIP_PLANE_MARGIN);
+        let ratio = if self.reverse_z{REVERSE_Z_NEAR_FAR_RATIO}else{NEAR_FAR_RATIO};
+        let near = (min_depth * (1.0 - CLIP_PLANE_MARGIN)).max(far * ratio);
+        self.set_clip_planes(near, far);
+    }
+    pub fn frame_bounds(&mut self, bounds : &BoundingBox){
+        let center = bounds.min + (bounds.max - bounds.min) / 2.0;
+        let radius = (bounds.max - bounds.min).magnitude() / 2.0 * FRAME_MARGIN;
+        let half_height = Rad::from(Deg(self.fov / 2.0));
+        let half_width = Rad((half_height.tan() * self.aspect).atan());
+        let half_fov = if half_width < half_height{half_width}else{half_height};
+        let distance = match self.projection_mode{
+            Projection::Perspective=>{radius / half_fov.sin()}
+            Projection::Orthographic=>{radius / half_fov.tan()}
+        };
+        let mut target = self.get_target_state();
+        target.center = center;
+        target.distance = distance.max(MIN_DISTANCE);
+        self.start_transition(target);
+    }
+    pub fn look_at(&mut self, eye : Point3<f32>, center : Point3<f32>){
+        self.cancel_transition();
+        let offset = eye - center;
+        self.eye = eye;
+        self.center = center;
+        self.distance = offset.magnitude().max(MIN_DISTANCE);
+        self.yaw = Rad(offset.y.atan2(offset.x));
+        self.pitch = Rad((offset.z / self.distance).clamp(-1.0, 1.0).asin().clamp(-MAX_PITCH, MAX_PITCH));
+        self.view = Matrix4::look_at_rh(self.eye, self.center, self.up);
+        self.update_projection();
     }
     pub fn update(&mut self){
         self.matrix.matrix=self.projection*self.view*self.model;
     }
+    pub fn get_eye(&self) -> Point3<f32>{
+        return self.eye;
+    }
+    pub fn get_center(&self) -> Point3<f32>{
+        return self.center;
+    }
+    pub fn get_view(&self) -> Matrix4<f32>{
+        return self.view;
+    }
+    pub fn get_projection(&self) -> Matrix4<f32>{
+        return self.projection;
+    }
+    pub fn get_yaw(&self) -> Rad<f32>{
+        return self.yaw;
+    }
+    pub fn get_pitch(&self) -> Rad<f32>{
+        return self.pitch;
+    }
+    pub fn get_distance(&self) -> f32{
+        return self.distance;
+    }
+    pub fn get_projection_mode(&self) -> Projection{
+        return self.projection_mode;
+    }
+    pub fn is_transitioning(&self) -> bool{
+        return self.transition.is_some();
+    }
+    pub fn set_projection_mode(&mut self, projection : Projection){
+        let mut target = self.get_target_state();
+        target.orthographic = match projection{
+            Projection::Perspective=>{0.0}
+            Projection::Orthographic=>{1.0}
+        };
+        self.projection_mode = projection;
+        self.start_transition(target);
+    }
+    pub fn toggle_projection_mode(&mut self){
+        match self.projection_mode{
+            Projection::Perspective=>{self.set_projection_mode(Projection::Orthographic)}
+            Projection::Orthographic=>{self.set_projection_mode(Projection::Perspective)}
+        }
+    }
+    pub fn set_view_preset(&mut self, preset : ViewPreset){
+        let mut target = self.get_target_state();
+        let (yaw, pitch) = preset.get_angles();
+        target.yaw = yaw;
+        target.pitch = pitch;
+        self.start_transition(target);
+    }
+    pub fn finish_transition(&mut self){
+        if let Some(transition) = self.transition{
+            self.advance(transition.duration - transition.elapsed);
+        }
+    }
+    pub fn get_mode(&self) -> CameraMode{
+        return self.mode;
+    }
+    pub fn set_mode(&mut self, mode : CameraMode){
+        self.mode = mode;
+        self.movement = MovementKeys::default();
+    }
+    pub fn toggle_mode(&mut self){
+        match self.mode{
+            CameraMode::Orbit=>{self.set_mode(CameraMode::Fly)}
+            CameraMode::Fly=>{self.set_mode(CameraMode::Orbit)}
+        }
+    }
+    pub fn set_movement_key(&mut self, key : MovementKey, pressed : bool){
+        match key{
+            MovementKey::Forward=>{self.movement.forward = pressed}
+            MovementKey::Backward=>{self.movement.backward = pressed}
+            MovementKey::Left=>{self.movement.left = pressed}
+            MovementKey::Right=>{self.movement.right = pressed}
+            MovementKey::Up=>{self.movement.up = pressed}
+            MovementKey::Down=>{self.movement.down = pressed}
+            MovementKey::Fast=>{self.movement.fast = pressed}
+        }
+    }
+    pub fn release_movement_keys(&mut self){
+        self.movement = MovementKeys::default();
+    }
+    pub fn advance(&mut self, delta_time : f32){
+        if let Some(mut transition) = self.transition{
+            transition.elapsed += delta_time;
+            let t = (transition.elapsed / transition.duration).min(1.0);
+            let t = t * t * (3.0 - 2.0 * t);
+            let mut yaw_difference = (transition.to.yaw - transition.from.yaw).normalize_signed();
+            if yaw_difference.0 <= -std::f32::consts::PI{yaw_difference.0 += 2.0 * std::f32::consts::PI}
+            self.center = transition.from.center + (transition.to.center - transition.from.center) * t;
+            self.yaw = transition.from.yaw + yaw_difference * t;
+            self.pitch = transition.from.pitch + (transition.to.pitch - transition.from.pitch) * t;
+            self.distance = transition.from.distance + (transition.to.distance - transition.from.distance) * t;
+            self.orthographic = transition.from.orthographic + (transition.to.orthographic - transition.from.orthographic) * t;
+            self.transition = if transition.elapsed >= transition.duration{None}else{Some(transition)};
+            self.update_eye();
+        }
+        if self.mode != CameraMode::Fly{return}
+        let (forward, right, _) = self.get_basis();
+        let axis = |positive : bool, negative : bool|{(positive as i32 - negative as i32) as f32};
+        let direction = forward * axis(self.movement.forward, self.movement.backward) + right * axis(self.movement.right, self.movement.left) + self.up * axis(self.movement.up, self.movement.down);
+        if direction.magnitude2() == 0.0{return}
+        self.cancel_transition();
+        let speed = if self.movement.fast{self.sensitivity.fly_speed * self.sensitivity.fly_fast_multiplier}else{self.sensitivity.fly_speed};
+        let offset = direction.normalize() * speed * delta_time;
+        self.eye += offset;
+        self.center += offset;
+        self.view = Matrix4::look_at_rh(self.eye, self.center, self.up);
+    }
+    pub fn look(&mut self, delta : (f32, f32)){
+        self.cancel_transition();
+        self.yaw -= Rad(delta.0 * self.sensitivity.rotate);
+        self.pitch = Rad((self.pitch.0 + delta.1 * self.sensitivity.rotate).clamp(-MAX_PITCH, MAX_PITCH));
+        self.update_center();
+    }
+    pub fn orbit(&mut self, delta : (f32, f32)){
+        self.cancel_transition();
+        self.yaw -= Rad(delta.0 * self.sensitivity.rotate);
+        self.pitch = Rad((self.pitch.0 + delta.1 * self.sensitivity.rotate).clamp(-MAX_PITCH, MAX_PITCH));
+        self.update_eye();
+    }
+    pub fn pan(&mut self, delta : (f32, f32)){
+        self.cancel_transition();
+        let (_, right, up) = self.get_basis();
+        let units_per_pixel = 2.0 * self.distance * Rad::from(Deg(self.fov / 2.0)).tan() / self.extent.height.max(1) as f32;
+        let offset = (right * -delta.0 + up * -delta.1) * units_per_pixel * self.sensitivity.pan;
+        self.center += offset;
+        self.update_eye();
+    }
+    pub fn dolly(&mut self, amount : f32, cursor_position : Option<(f32, f32)>){
+        self.cancel_transition();
+        let scale = (1.0 - self.sensitivity.zoom).powf(amount);
+        let scale = scale.max(MIN_DISTANCE / self.distance);
+        let (forward, right, up) = self.get_basis();
+        let target = match cursor_position{
+            Some((x, y))=>{
+                let half_height = Rad::from(Deg(self.fov / 2.0)).tan();
+                let ndc_x = 2.0 * x / self.extent.width.max(1) as f32 - 1.0;
+                let ndc_y = 2.0 * y / self.extent.height.max(1) as f32 - 1.0;
+                let offset = right * (ndc_x * half_height * self.aspect) + up * (ndc_y * half_height);
+                let (origin, direction) = if self.orthographic >= 1.0{(self.eye + offset * self.distance, forward)}else{(self.eye, forward + offset)};
+                origin + direction * self.distance
+            }
+            None=>{self.center}
+        };
+        self.center = target + (self.center - target) * scale;
+        self.distance *= scale;
+        self.update_eye();
+    }
+    pub fn set_cursor_position(&mut self, position : (f32, f32)){
+        self.cursor_position = Some(position);
+    }
+    pub fn get_cursor_position(&self) -> Option<(f32, f32)>{
+        return self.cursor_position;
+    }
+    pub fn screen_ray(&self, cursor_position : (f32, f32)) -> Ray{
+        let x = 2.0 * cursor_position.0 / self.extent.width.max(1) as f32 - 1.0;
+        let y = 2.0 * cursor_position.1 / self.extent.height.max(1) as f32 - 1.0;
+        let inverse = (self.projection * self.view).invert().unwrap_or(Matrix4::identity());
+        let unproject = |depth : f32|{
+            let point = inverse * Vector4::new(x, y, depth, 1.0);
+            Point3::new(point.x / point.w, point.y / point.w, point.z / point.w)
+        };
+        let origin = unproject(if self.reverse_z{1.0}else{0.0});
+        return Ray::new(origin, unproject(0.5) - origin);
+    }
     pub fn mouse_movement(&mut self, delta : (f64,f64)){
-        if self.left_mouse_button_pressed{
-            let alpha = Quaternion::new(0.0, self.eye.x-self.center.x, self.eye.y-self.center.y,self.eye.z-self.center.z).normalize();
-            let sin_value = (delta.0 as f32)*0.2;
-            let cos_value = (1.0-sin_value.powf(2.0)).sqrt();
-            let mut q = sin_value * alpha;
-            q.s += cos_value;
-            let beta = Quaternion::new(0.0, self.eye.x, self.eye.y, self.eye.z);
-            let eye = (q*beta*q.conjugate()).v;
-            println!("{:?}",eye);
-            self.eye = Point3::new(eye.x, eye.y, eye.z);
-            self.view = Matrix4::look_at_rh(self.eye,self.center, self.up);
+        let delta = (delta.0 as f32, delta.1 as f32);
+        if self.mode == CameraMode::Fly{
+            if self.left_mouse_button_pressed || self.pan_mouse_button_pressed{self.look(delta)}
+        }
+        else if self.left_mouse_button_pressed{
+            self.orbit(delta);
+        }
+        else if self.pan_mouse_button_pressed{
+            self.pan(delta);
         }
     }
     pub fn mouse_zoom(&mut self, delta : f32){
-        if self.fov + delta/100.0 < 180.0 && self.fov + delta/100.0 > 0.0{
-            self.fov += delta/100.0;
+        self.dolly(delta, self.cursor_position);
+    }
+    fn get_basis(&self) -> (Vector3<f32>, Vector3<f32>, Vector3<f32>){
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(self.up).normalize();
+        let up = right.cross(forward);
+        return (forward, right, up);
+    }
+    fn get_target_state(&self) -> CameraState{
+        if let Some(transition) = &self.transition{return transition.to}
+        return CameraState{center:self.center,yaw:self.yaw,pitch:self.pitch,distance:self.distance,orthographic:self.orthographic};
+    }
+    fn start_transition(&mut self, target : CameraState){
+        let from = CameraState{center:self.center,yaw:self.yaw,pitch:self.pitch,distance:self.distance,orthographic:self.orthographic};
+        self.transition = Some(CameraTransition{from,to:target,elapsed:0.0,duration:self.transition_time});
+        if self.transition_time <= 0.0{self.advance(0.0)}
+    }
+    fn cancel_transition(&mut self){
+        if self.transition.take().is_some(){
+            self.orthographic = match self.projection_mode{
+                Projection::Perspective=>{0.0}
+                Projection::Orthographic=>{1.0}
+            };
+            self.update_eye();
+        }
+    }
+    fn get_half_height(&self) -> f32{
+        let perspective = Rad::from(Deg(self.fov / 2.0)).tan();
+        if self.orthographic <= 0.0 || self.orthographic >= 1.0{return perspective}
+        let narrow = Rad::from(Deg(MIN_TRANSITION_FOV / 2.0)).tan();
+        return perspective + (narrow - perspective) * self.orthographic;
+    }
+    fn get_dolly_factor(&self) -> f32{
+        return Rad::from(Deg(self.fov / 2.0)).tan() / self.get_half_height();
+    }
+    fn get_offset(&self) -> Vector3<f32>{
+        return Vector3::new(self.pitch.cos() * self.yaw.cos(), self.pitch.cos() * self.yaw.sin(), self.pitch.sin()) * self.distance * self.get_dolly_factor();
+    }
+    fn update_eye(&mut self){
+        self.eye = self.center + self.get_offset();
+        self.view = Matrix4::look_at_rh(self.eye, self.center, self.up);
+        self.update_projection();
+    }
+    fn update_center(&mut self){
+        self.center = self.eye - self.get_offset();
+        self.view = Matrix4::look_at_rh(self.eye, self.center, self.up);
+        self.update_projection();
+    }
+    fn update_projection(&mut self){
+        if self.orthographic >= 1.0{
+            let half_height = self.distance * Rad::from(Deg(self.fov / 2.0)).tan();
+            let half_width = half_height * self.aspect;
+            self.projection = if self.reverse_z{
+                let depth = 1.0 / (self.far - self.near);
+                Matrix4::new(
+                    1.0 / half_width, 0.0, 0.0, 0.0,
+                    0.0, 1.0 / half_height, 0.0, 0.0,
+                    0.0, 0.0, depth, 0.0,
+                    0.0, 0.0, self.far * depth, 1.0,
+                )
+            }
+            else{
+                get_depth_correction() * cgmath::ortho(-half_width, half_width, -half_height, half_height, self.near, self.far)
+            };
+            return;
+        }
+        let half_height = self.get_half_height();
+        let extra_distance = self.distance * (self.get_dolly_factor() - 1.0);
+        self.projection = if self.reverse_z{
+            Matrix4::new(
+                1.0 / (half_height * self.aspect), 0.0, 0.0, 0.0,
+                0.0, 1.0 / half_height, 0.0, 0.0,
+                0.0, 0.0, 0.0, -1.0,
+                0.0, 0.0, self.near + extra_distance, 0.0,
+            )
+        }
+        else{
+            get_depth_correction() * cgmath::perspective(Rad(2.0 * half_height.atan()), self.aspect, self.near + extra_distance, self.far + extra_distance)
         };
-        self.projection = cgmath::perspective(Deg(self.fov), self.aspect, self.near, self.far);
     }
-}
\ No newline at end of file
+}
+fn get_depth_correction() -> Matrix4<f32>{
+    return Matrix4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.0,
+        0.0, 0.0, 0.5, 1.0,
+    );
+}
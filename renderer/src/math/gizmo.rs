@@ -0,0 +1,204 @@
+use cgmath::{Matrix4, Point3, Vector3, Deg, Rad, InnerSpace, EuclideanSpace, MetricSpace, Zero};
+use serde::{Serialize, Deserialize};
+
+use crate::config::GizmoSnap;
+
+use super::{BoundingBox, ModelMatrix, Vertex, AXIS_COLORS, ray::Ray};
+
+const GIZMO_SIZE : f32 = 1.25;
+const MIN_GIZMO_SIZE : f32 = 0.1;
+const HANDLE_SIZE : f32 = 0.1;
+const HANDLE_TOLERANCE : f32 = 0.08;
+const RING_SEGMENTS : usize = 48;
+const MIN_SCALE : f32 = 0.01;
+const EPSILON : f32 = 1e-6;
+const ACTIVE_COLOR : [f32;3] = [1.0,0.8,0.0];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GizmoMode{
+    Translate,
+    Rotate,
+    Scale,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GizmoSpace{
+    World,
+    Local,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GizmoAxis{
+    X,
+    Y,
+    Z,
+}
+impl GizmoAxis{
+    pub const ALL : [Self;3] = [Self::X, Self::Y, Self::Z];
+    pub fn get_index(&self) -> usize{
+        match self{
+            Self::X=>{return 0}
+            Self::Y=>{return 1}
+            Self::Z=>{return 2}
+        }
+    }
+}
+#[derive(Clone, Copy, Debug)]
+struct GizmoDrag{
+    axis : GizmoAxis,
+    matrix : Matrix4<f32>,
+    origin : Point3<f32>,
+    direction : Vector3<f32>,
+    start : f32,
+    start_vector : Vector3<f32>,
+}
+pub struct Gizmo{
+    pub mode : GizmoMode,
+    pub space : GizmoSpace,
+    pub snap : GizmoSnap,
+    pub snapping : bool,
+    drag : Option<GizmoDrag>,
+}
+impl Gizmo{
+    pub fn new(snap : GizmoSnap) -> Self{
+        return Self{
+            mode : GizmoMode::Translate,
+            space : GizmoSpace::World,
+            snap,
+            snapping : false,
+            drag : None,
+        }
+    }
+    pub fn toggle_space(&mut self){
+        self.space = match self.space{
+            GizmoSpace::World=>{GizmoSpace::Local}
+            GizmoSpace::Local=>{GizmoSpace::World}
+        };
+    }
+    pub fn get_origin(matrix : &Matrix4<f32>) -> Point3<f32>{
+        return Point3::from_vec(matrix.w.truncate());
+    }
+    pub fn get_axes(&self, matrix : &Matrix4<f32>) -> [Vector3<f32>;3]{
+        let world = [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()];
+        if self.space == GizmoSpace::World{return world}
+        let columns = [matrix.x.truncate(), matrix.y.truncate(), matrix.z.truncate()];
+        return [0, 1, 2].map(|i|if columns[i].magnitude2() > EPSILON{columns[i].normalize()}else{world[i]});
+    }
+    pub fn get_size(matrix : &Matrix4<f32>, mesh_bounds : &BoundingBox) -> f32{
+        let origin = Self::get_origin(matrix);
+        let radius = mesh_bounds.transform(matrix).get_corners().iter().map(|corner|corner.distance(origin)).fold(0.0, f32::max);
+        return (radius * GIZMO_SIZE).max(MIN_GIZMO_SIZE);
+    }
+    pub fn hit_test(&self, ray : &Ray, matrix : &Matrix4<f32>, mesh_bounds : &BoundingBox) -> Option<GizmoAxis>{
+        let origin = Self::get_origin(matrix);
+        let axes = self.get_axes(matrix);
+        let size = Self::get_size(matrix, mesh_bounds);
+        let tolerance = size * HANDLE_TOLERANCE;
+        let mut closest : Option<(f32, GizmoAxis)> = None;
+        for axis in GizmoAxis::ALL{
+            let direction = axes[axis.get_index()];
+            let hit = match self.mode{
+                GizmoMode::Translate | GizmoMode::Scale=>{
+                    ray.closest_to_line(origin, direction).filter(|&(distance, position)|(0.0..=size * (1.0 + HANDLE_SIZE)).contains(&position) && ray.at(distance).distance(origin + direction * position) <= tolerance).map(|(distance, _)|distance)
+                }
+                GizmoMode::Rotate=>{
+                    ray.intersect_plane(origin, direction).filter(|&distance|(ray.at(distance).distance(origin) - size).abs() <= tolerance)
+                }
+            };
+            if let Some(distance) = hit{
+                if closest.map_or(true, |closest|distance < closest.0){closest = Some((distance, axis))}
+            }
+        }
+        return closest.map(|(_, axis)|axis);
+    }
+    pub fn begin_drag(&mut self, ray : &Ray, matrix : &ModelMatrix, mesh_bounds : &BoundingBox) -> Option<GizmoAxis>{
+        let axis = self.hit_test(ray, &matrix.matrix, mesh_bounds)?;
+        let origin = Self::get_origin(&matrix.matrix);
+        let direction = self.get_axes(&matrix.matrix)[axis.get_index()];
+        let (start, start_vector) = match self.mode{
+            GizmoMode::Translate | GizmoMode::Scale=>{(ray.closest_to_line(origin, direction)?.1, Vector3::zero())}
+            GizmoMode::Rotate=>{(0.0, ray.at(ray.intersect_plane(origin, direction)?) - origin)}
+        };
+        self.drag = Some(GizmoDrag{axis,matrix:matrix.matrix,origin,direction,start,start_vector});
+        return Some(axis);
+    }
+    pub fn drag(&self, ray : &Ray) -> Option<ModelMatrix>{
+        let drag = self.drag?;
+        let to_origin = Matrix4::from_translation(drag.origin.to_vec());
+        let from_origin = Matrix4::from_translation(-drag.origin.to_vec());
+        let matrix = match self.mode{
+            GizmoMode::Translate=>{
+                let offset = self.snap_value(ray.closest_to_line(drag.origin, drag.direction)?.1 - drag.start, self.snap.translate);
+                Matrix4::from_translation(drag.direction * offset) * drag.matrix
+            }
+            GizmoMode::Rotate=>{
+                let vector = ray.at(ray.intersect_plane(drag.origin, drag.direction)?) - drag.origin;
+                let angle = Deg::from(Rad(drag.start_vector.cross(vector).dot(drag.direction).atan2(drag.start_vector.dot(vector))));
+                let angle = Deg(self.snap_value(angle.0, self.snap.rotate));
+                to_origin * Matrix4::from_axis_angle(drag.direction, angle) * from_origin * drag.matrix
+            }
+            GizmoMode::Scale=>{
+                if drag.start.abs() < EPSILON{return None}
+                let ratio = ray.closest_to_line(drag.origin, drag.direction)?.1 / drag.start;
+                let mut factors = [1.0;3];
+                factors[drag.axis.get_index()] = (1.0 + self.snap_value(ratio - 1.0, self.snap.scale)).max(MIN_SCALE);
+                let scale = Matrix4::from_nonuniform_scale(factors[0], factors[1], factors[2]);
+                match self.space{
+                    GizmoSpace::Local=>{drag.matrix * scale}
+                    GizmoSpace::World=>{to_origin * scale * from_origin * drag.matrix}
+                }
+            }
+        };
+        return Some(ModelMatrix{matrix});
+    }
+    pub fn end_drag(&mut self) -> bool{
+        return self.drag.take().is_some();
+    }
+    pub fn is_dragging(&self) -> bool{
+        return self.drag.is_some();
+    }
+    pub fn get_drag_axis(&self) -> Option<GizmoAxis>{
+        return self.drag.map(|drag|drag.axis);
+    }
+    pub fn get_vertices(&self, matrix : &Matrix4<f32>, mesh_bounds : &BoundingBox) -> Vec<Vertex>{
+        let origin = Self::get_origin(matrix);
+        let axes = self.get_axes(matrix);
+        let size = Self::get_size(matrix, mesh_bounds);
+        let mut vertices = vec!();
+        for axis in GizmoAxis::ALL{
+            let i = axis.get_index();
+            let color = if self.get_drag_axis() == Some(axis){ACTIVE_COLOR}else{AXIS_COLORS[i]};
+            let direction = axes[i];
+            let side = axes[(i + 1) % 3];
+            let up = axes[(i + 2) % 3];
+            let mut line = |a : Point3<f32>, b : Point3<f32>|{
+                vertices.push(Vertex{pos:a.into(),color});
+                vertices.push(Vertex{pos:b.into(),color});
+            };
+            let end = origin + direction * size;
+            let handle = size * HANDLE_SIZE * 0.5;
+            match self.mode{
+                GizmoMode::Translate=>{
+                    line(origin, end);
+                    let tip = origin + direction * size * (1.0 + HANDLE_SIZE);
+                    for offset in [side, -side, up, -up]{line(end + offset * handle, tip)}
+                }
+                GizmoMode::Scale=>{
+                    line(origin, end);
+                    let corners = [side + up, side - up, -side - up, -side + up].map(|offset|end + offset * handle);
+                    for j in 0..corners.len(){line(corners[j], corners[(j + 1) % corners.len()])}
+                }
+                GizmoMode::Rotate=>{
+                    let points : Vec<Point3<f32>> = (0..=RING_SEGMENTS).map(|j|{
+                        let angle = j as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                        origin + (side * angle.cos() + up * angle.sin()) * size
+                    }).collect();
+                    for pair in points.windows(2){line(pair[0], pair[1])}
+                }
+            }
+        }
+        return vertices;
+    }
+    fn snap_value(&self, value : f32, step : f32) -> f32{
+        if !self.snapping{return value}
+        return (value / step).round() * step;
+    }
+}
@@ -1,9 +1,53 @@
 pub mod camera;
+pub mod ray;
+pub mod gizmo;
 
 use ash::vk::{DescriptorSetLayoutBinding, DescriptorType, ShaderStageFlags, VertexInputBindingDescription, VertexInputRate, VertexInputAttributeDescription, Format};
-use cgmath::{Matrix4, Vector4, SquareMatrix};
+use cgmath::{Matrix4, Vector4, SquareMatrix, Point3, Transform};
 use memoffset::offset_of;
 
+pub const AXIS_COLORS : [[f32;3];3] = [[0.9,0.2,0.2],[0.2,0.8,0.2],[0.2,0.4,0.9]];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox{
+    pub min : Point3<f32>,
+    pub max : Point3<f32>,
+}
+impl BoundingBox{
+    pub fn from_points(points : impl IntoIterator<Item = Point3<f32>>) -> Option<Self>{
+        let mut bounds : Option<Self> = None;
+        for point in points{
+            bounds = Some(match bounds{
+                Some(bounds)=>{bounds.extend(point)}
+                None=>{Self{min:point,max:point}}
+            });
+        }
+        return bounds;
+    }
+    pub fn extend(&self, point : Point3<f32>) -> Self{
+        return Self{
+            min : Point3::new(self.min.x.min(point.x), self.min.y.min(point.y), self.min.z.min(point.z)),
+            max : Point3::new(self.max.x.max(point.x), self.max.y.max(point.y), self.max.z.max(point.z)),
+        };
+    }
+    pub fn union(&self, other : &Self) -> Self{
+        return self.extend(other.min).extend(other.max);
+    }
+    pub fn get_corners(&self) -> [Point3<f32>; 8]{
+        let mut corners = [self.min; 8];
+        for (i, corner) in corners.iter_mut().enumerate(){
+            if i & 1 != 0{corner.x = self.max.x}
+            if i & 2 != 0{corner.y = self.max.y}
+            if i & 4 != 0{corner.z = self.max.z}
+        }
+        return corners;
+    }
+    pub fn transform(&self, matrix : &Matrix4<f32>) -> Self{
+        let corners = self.get_corners().map(|corner|matrix.transform_point(corner));
+        return Self::from_points(corners).unwrap_or(*self);
+    }
+}
+#[derive(Clone, Copy, PartialEq)]
 pub struct ModelMatrix{
     pub matrix : Matrix4<f32>,
 }
@@ -74,6 +118,9 @@ impl InstanceVertex{
             },
         );
     }
+    pub fn get_initial_bounds() -> BoundingBox{
+        return BoundingBox::from_points(Self::get_initial_vertex_data().iter().map(|vertex|Point3::from(vertex.pos))).unwrap_or(BoundingBox{min:Point3::new(0.0,0.0,0.0),max:Point3::new(0.0,0.0,0.0)});
+    }
     pub fn get_initial_vertex_data() -> Vec<Self>{
         return vec!(
             Self{pos : [0.0,0.0,0.0], color : [1.0,1.0,0.0]},
@@ -94,14 +141,17 @@ pub struct Vertex{
 impl Vertex{
     pub fn get_grid() -> [Self;6]{
         return [
-                Vertex{pos:[1.0,0.0,0.0],color:[0.0,0.0,0.0]},
-                Vertex{pos:[-1.0,0.0,0.0],color:[0.0,0.0,0.0]},
-                Vertex{pos:[0.0,1.0,0.0],color:[0.0,0.0,0.0]},
-                Vertex{pos:[0.0,-1.0,0.0],color:[0.0,0.0,0.0]},
-                Vertex{pos:[0.0,0.0,1.0],color:[0.0,0.0,0.0]},
-                Vertex{pos:[0.0,0.0,-1.0],color:[0.0,0.0,0.0]},
+                Vertex{pos:[1.0,0.0,0.0],color:AXIS_COLORS[0]},
+                Vertex{pos:[-1.0,0.0,0.0],color:AXIS_COLORS[0]},
+                Vertex{pos:[0.0,1.0,0.0],color:AXIS_COLORS[1]},
+                Vertex{pos:[0.0,-1.0,0.0],color:AXIS_COLORS[1]},
+                Vertex{pos:[0.0,0.0,1.0],color:AXIS_COLORS[2]},
+                Vertex{pos:[0.0,0.0,-1.0],color:AXIS_COLORS[2]},
         ];
     }
+    pub fn get_grid_bounds() -> BoundingBox{
+        return BoundingBox::from_points(Self::get_grid().iter().map(|vertex|Point3::from(vertex.pos))).unwrap_or(BoundingBox{min:Point3::new(0.0,0.0,0.0),max:Point3::new(0.0,0.0,0.0)});
+    }
     pub fn get_bindings() -> Vec<VertexInputBindingDescription>{
         return vec!(
             VertexInputBindingDescription{
@@ -138,7 +188,7 @@ impl UniformBuffer{
             DescriptorSetLayoutBinding{
                 binding : 0,
                 descriptor_count : 1,
-                descriptor_type : DescriptorType::UNIFORM_BUFFER,
+                descriptor_type : DescriptorType::UNIFORM_BUFFER_DYNAMIC,
                 p_immutable_samplers : std::ptr::null(),
                 stage_flags : ShaderStageFlags::VERTEX,
             }
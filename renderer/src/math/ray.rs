@@ -0,0 +1,75 @@
+use cgmath::{Matrix4, Point3, Vector3, InnerSpace, Transform};
+
+use super::BoundingBox;
+
+const EPSILON : f32 = 1e-7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray{
+    pub origin : Point3<f32>,
+    pub direction : Vector3<f32>,
+}
+impl Ray{
+    pub fn new(origin : Point3<f32>, direction : Vector3<f32>) -> Self{
+        return Self{origin,direction:direction.normalize()};
+    }
+    pub fn at(&self, distance : f32) -> Point3<f32>{
+        return self.origin + self.direction * distance;
+    }
+    pub fn intersect_triangle(&self, a : Point3<f32>, b : Point3<f32>, c : Point3<f32>) -> Option<f32>{
+        let edge_ab = b - a;
+        let edge_ac = c - a;
+        let p = self.direction.cross(edge_ac);
+        let determinant = edge_ab.dot(p);
+        if determinant.abs() < EPSILON{return None}
+        let inverse_determinant = 1.0 / determinant;
+        let t = self.origin - a;
+        let u = t.dot(p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u){return None}
+        let q = t.cross(edge_ab);
+        let v = self.direction.dot(q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0{return None}
+        let distance = edge_ac.dot(q) * inverse_determinant;
+        if distance < 0.0{return None}
+        return Some(distance);
+    }
+    pub fn intersect_bounds(&self, bounds : &BoundingBox) -> Option<f32>{
+        let mut near = 0.0f32;
+        let mut far = f32::MAX;
+        for axis in 0..3{
+            let inverse_direction = 1.0 / self.direction[axis];
+            let mut t0 = (bounds.min[axis] - self.origin[axis]) * inverse_direction;
+            let mut t1 = (bounds.max[axis] - self.origin[axis]) * inverse_direction;
+            if t0.is_nan() || t1.is_nan(){
+                if self.origin[axis] < bounds.min[axis] || self.origin[axis] > bounds.max[axis]{return None}
+                continue;
+            }
+            if t0 > t1{std::mem::swap(&mut t0, &mut t1)}
+            near = near.max(t0);
+            far = far.min(t1);
+            if near > far{return None}
+        }
+        return Some(near);
+    }
+    pub fn intersect_plane(&self, point : Point3<f32>, normal : Vector3<f32>) -> Option<f32>{
+        let denominator = self.direction.dot(normal);
+        if denominator.abs() < EPSILON{return None}
+        let distance = (point - self.origin).dot(normal) / denominator;
+        if distance < 0.0{return None}
+        return Some(distance);
+    }
+    pub fn closest_to_line(&self, point : Point3<f32>, direction : Vector3<f32>) -> Option<(f32, f32)>{
+        let offset = self.origin - point;
+        let alignment = self.direction.dot(direction);
+        let denominator = 1.0 - alignment * alignment;
+        if denominator < EPSILON{return None}
+        let ray_offset = self.direction.dot(offset);
+        let line_offset = direction.dot(offset);
+        let distance = (alignment * line_offset - ray_offset) / denominator;
+        if distance < 0.0{return None}
+        return Some((distance, (line_offset - alignment * ray_offset) / denominator));
+    }
+    pub fn transform(&self, matrix : &Matrix4<f32>) -> Self{
+        return Self{origin:matrix.transform_point(self.origin),direction:matrix.transform_vector(self.direction)};
+    }
+}
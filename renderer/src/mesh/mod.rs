@@ -0,0 +1,63 @@
+pub mod obj;
+
+use ash::{Device, vk::{CommandPool, Queue}};
+use cgmath::Point3;
+
+use crate::{allocator::{Allocator, BufferAndAllocation}, math::{BoundingBox, InstanceVertex}, error::RendererResult};
+
+pub const DEFAULT_MESH : MeshHandle = MeshHandle(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MeshHandle(pub u64);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshGroup{
+    pub name : String,
+    pub material : Option<String>,
+    pub first_index : u32,
+    pub index_count : u32,
+}
+#[derive(Clone, Default)]
+pub struct MeshData{
+    pub vertices : Vec<InstanceVertex>,
+    // Only positions and colors are uploaded by Mesh::new; normals and uvs are kept for callers of load_obj/parse_obj.
+    pub normals : Vec<[f32;3]>,
+    pub uvs : Vec<[f32;2]>,
+    pub indices : Vec<u32>,
+    pub groups : Vec<MeshGroup>,
+    pub warnings : Vec<String>,
+}
+impl MeshData{
+    pub fn get_bounds(&self) -> Option<BoundingBox>{
+        return BoundingBox::from_points(self.vertices.iter().map(|vertex|Point3::from(vertex.pos)));
+    }
+    pub fn get_triangles(&self) -> Vec<[f32;3]>{
+        return self.indices.iter().map(|&index|self.vertices[index as usize].pos).collect();
+    }
+}
+pub struct Mesh{
+    pub handle : MeshHandle,
+    pub vertex_buffer : (u32,BufferAndAllocation),
+    pub index_buffer : (u32,BufferAndAllocation),
+}
+impl Mesh{
+    pub unsafe fn new(device : &Device, allocator : &mut Allocator, command_pool : CommandPool, queue : Queue, handle : MeshHandle, data : &MeshData) -> RendererResult<Self>{
+        let vertex_buffer = crate::functions::buffer::create_vertex_buffer(device, allocator, command_pool, queue, data.vertices.clone())?;
+        let index_buffer = match crate::functions::buffer::create_vertex_buffer(device, allocator, command_pool, queue, data.indices.clone()){
+            Ok(index_buffer)=>{index_buffer}
+            Err(error)=>{
+                vertex_buffer.destroy(allocator);
+                return Err(error);
+            }
+        };
+        return Ok(Self{
+            handle,
+            vertex_buffer : (data.vertices.len() as u32, vertex_buffer),
+            index_buffer : (data.indices.len() as u32, index_buffer),
+        });
+    }
+    pub unsafe fn destroy(&self, allocator : &mut Allocator){
+        self.vertex_buffer.1.destroy(allocator);
+        self.index_buffer.1.destroy(allocator);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{collections::HashMap, path::Path, str::SplitWhitespace};
+
+use cgmath::{Vector3, InnerSpace};
+
+use crate::{math::InstanceVertex, error::{RendererError, RendererResult}};
+
+use super::{MeshData, MeshGroup};
+
+const DEFAULT_COLOR : [f32;3] = [1.0,1.0,1.0];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct CornerKey{
+    position : usize,
+    uv : Option<usize>,
+    normal : Option<usize>,
+    color : [u32;3],
+}
+
+pub fn load_obj(path : &Path) -> RendererResult<MeshData>{
+    let source = std::fs::read_to_string(path)?;
+    let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    return parse_obj(&source, |library|Ok(std::fs::read_to_string(directory.join(library))?));
+}
+pub fn parse_mtl(source : &str) -> RendererResult<HashMap<String, [f32;3]>>{
+    let mut colors = HashMap::new();
+    let mut material = None;
+    for (i, line) in source.lines().enumerate(){
+        let mut tokens = get_tokens(line);
+        match tokens.next(){
+            Some("newmtl")=>{material = Some(get_name(tokens))}
+            Some("Kd")=>{
+                let name = material.clone().ok_or_else(||RendererError::MeshParse(i + 1, String::from("Kd before newmtl")))?;
+                let color = parse_floats(tokens, 3, i + 1)?;
+                colors.insert(name, [color[0], color[1], color[2]]);
+            }
+            _=>{}
+        }
+    }
+    return Ok(colors);
+}
+pub fn parse_obj(source : &str, mut load_material_library : impl FnMut(&str) -> RendererResult<String>) -> RendererResult<MeshData>{
+    let mut positions : Vec<([f32;3], Option<[f32;3]>)> = vec!();
+    let mut normals : Vec<[f32;3]> = vec!();
+    let mut uvs : Vec<[f32;2]> = vec!();
+    let mut materials = HashMap::new();
+    let mut material : Option<String> = None;
+    let mut name = String::new();
+    let mut mesh = MeshData::default();
+    let mut generated_normals = vec!();
+    let mut vertex_indices : HashMap<CornerKey, u32> = HashMap::new();
+    for (i, line) in source.lines().enumerate(){
+        let line_number = i + 1;
+        let mut tokens = get_tokens(line);
+        match tokens.next(){
+            Some("v")=>{
+                let values = parse_floats(tokens, 3, line_number)?;
+                let color = if values.len() >= 6{Some([values[3], values[4], values[5]])}else{None};
+                positions.push(([values[0], values[1], values[2]], color));
+            }
+            Some("vn")=>{
+                let values = parse_floats(tokens, 3, line_number)?;
+                normals.push([values[0], values[1], values[2]]);
+            }
+            Some("vt")=>{
+                let values = parse_floats(tokens, 1, line_number)?;
+                uvs.push([values[0], values.get(1).copied().unwrap_or(0.0)]);
+            }
+            Some("mtllib")=>{
+                for library in tokens{
+                    match load_material_library(library).and_then(|source|parse_mtl(&source)){
+                        Ok(library_materials)=>{materials.extend(library_materials)}
+                        Err(error)=>{mesh.warnings.push(format!("failed to load material library {}, using default colors: {}", library, error))}
+                    }
+                }
+            }
+            Some("usemtl")=>{
+                material = Some(get_name(tokens));
+                start_group(&mut mesh, &name, &material);
+            }
+            Some("o") | Some("g")=>{
+                name = get_name(tokens);
+                start_group(&mut mesh, &name, &material);
+            }
+            Some("f")=>{
+                let corners = tokens.map(|corner|parse_corner(corner, [positions.len(), uvs.len(), normals.len()], line_number)).collect::<RendererResult<Vec<_>>>()?;
+                if corners.len() < 3{return Err(RendererError::MeshParse(line_number, String::from("face needs at least three vertices")))}
+                if mesh.groups.is_empty(){start_group(&mut mesh, &name, &material)}
+                let material_color = material.as_ref().and_then(|material|materials.get(material)).copied();
+                let mut face = vec!();
+                for (position, uv, normal) in corners{
+                    let color = material_color.or(positions[position].1).unwrap_or(DEFAULT_COLOR);
+                    let index = *vertex_indices.entry(CornerKey{position,uv,normal,color:color.map(f32::to_bits)}).or_insert_with(||{
+                        mesh.vertices.push(InstanceVertex{pos:positions[position].0,color});
+                        mesh.normals.push(normal.map_or([0.0;3], |normal|normals[normal]));
+                        mesh.uvs.push(uv.map_or([0.0;2], |uv|uvs[uv]));
+                        generated_normals.push(normal.is_none());
+                        return mesh.vertices.len() as u32 - 1;
+                    });
+                    face.push(index);
+                }
+                for j in 1..face.len() - 1{
+                    let triangle = [face[0], face[j], face[j + 1]];
+                    let [a, b, c] = triangle.map(|index|Vector3::from(mesh.vertices[index as usize].pos));
+                    let face_normal = (b - a).cross(c - a);
+                    for index in triangle{
+                        if generated_normals[index as usize]{
+                            let normal = Vector3::from(mesh.normals[index as usize]) + face_normal;
+                            mesh.normals[index as usize] = normal.into();
+                        }
+                    }
+                    mesh.indices.extend_from_slice(&triangle);
+                }
+            }
+            _=>{}
+        }
+    }
+    if mesh.indices.is_empty(){return Err(RendererError::MeshParse(source.lines().count(), String::from("mesh has no faces")))}
+    for (normal, generated) in mesh.normals.iter_mut().zip(generated_normals){
+        let vector = Vector3::from(*normal);
+        if generated && vector.magnitude2() > 0.0{*normal = vector.normalize().into()}
+    }
+    let index_count = mesh.indices.len() as u32;
+    let ends : Vec<u32> = mesh.groups.iter().skip(1).map(|group|group.first_index).chain(std::iter::once(index_count)).collect();
+    for (group, end) in mesh.groups.iter_mut().zip(ends){
+        group.index_count = end - group.first_index;
+    }
+    mesh.groups.retain(|group|group.index_count > 0);
+    return Ok(mesh);
+}
+fn get_tokens(line : &str) -> SplitWhitespace<'_>{
+    return line.split('#').next().unwrap_or("").split_whitespace();
+}
+fn get_name(tokens : SplitWhitespace<'_>) -> String{
+    return tokens.collect::<Vec<_>>().join(" ");
+}
+fn start_group(mesh : &mut MeshData, name : &str, material : &Option<String>){
+    let first_index = mesh.indices.len() as u32;
+    if let Some(group) = mesh.groups.last_mut(){
+        if group.first_index == first_index{
+            group.name = String::from(name);
+            group.material = material.clone();
+            return;
+        }
+    }
+    mesh.groups.push(MeshGroup{name:String::from(name),material:material.clone(),first_index,index_count:0});
+}
+fn parse_floats(tokens : SplitWhitespace<'_>, count : usize, line : usize) -> RendererResult<Vec<f32>>{
+    let values = tokens.map(|token|token.parse::<f32>().map_err(|_|RendererError::MeshParse(line, format!("invalid number \"{}\"", token)))).collect::<RendererResult<Vec<_>>>()?;
+    if values.len() < count{return Err(RendererError::MeshParse(line, format!("expected {} values, found {}", count, values.len())))}
+    return Ok(values);
+}
+fn parse_index(token : &str, count : usize, line : usize) -> RendererResult<usize>{
+    let index = token.parse::<i64>().map_err(|_|RendererError::MeshParse(line, format!("invalid index \"{}\"", token)))?;
+    let resolved = if index < 0{count as i64 + index}else{index - 1};
+    if resolved < 0 || resolved >= count as i64{return Err(RendererError::MeshParse(line, format!("index {} is out of range", index)))}
+    return Ok(resolved as usize);
+}
+fn parse_corner(corner : &str, counts : [usize;3], line : usize) -> RendererResult<(usize, Option<usize>, Option<usize>)>{
+    let mut parts = corner.split('/');
+    let position = parse_index(parts.next().unwrap_or(""), counts[0], line)?;
+    let uv = match parts.next(){
+        Some(part) if !part.is_empty()=>{Some(parse_index(part, counts[1], line)?)}
+        _=>{None}
+    };
+    let normal = match parts.next(){
+        Some(part) if !part.is_empty()=>{Some(parse_index(part, counts[2], line)?)}
+        _=>{None}
+    };
+    return Ok((position, uv, normal));
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use cgmath::{Point3, Transform, Matrix4, EuclideanSpace};
+
+use crate::{math::{ModelMatrix, BoundingBox, ray::Ray}, mesh::{MeshHandle, DEFAULT_MESH}};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct InstanceHandle(pub u64);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PickResult{
+    pub handle : InstanceHandle,
+    pub point : Point3<f32>,
+}
+pub struct Scene{
+    instances : Vec<(InstanceHandle, ModelMatrix, MeshHandle)>,
+    meshes : HashMap<MeshHandle, (BoundingBox, Vec<[f32;3]>)>,
+    selection : Vec<InstanceHandle>,
+    pub dirty : bool,
+}
+impl Scene{
+    pub fn new(instances : Vec<ModelMatrix>) -> Self{
+        let instances = instances.into_iter().enumerate().map(|(i, matrix)|(InstanceHandle(i as u64), matrix, DEFAULT_MESH)).collect();
+        return Self{
+            instances,
+            meshes : HashMap::new(),
+            selection : vec!(),
+            dirty : false,
+        }
+    }
+    pub fn add_instance(&mut self, handle : InstanceHandle, matrix : ModelMatrix){
+        if self.update_instance(handle, matrix){return}
+        let index = self.instances.partition_point(|instance|instance.2 <= DEFAULT_MESH);
+        self.instances.insert(index, (handle, matrix, DEFAULT_MESH));
+        self.dirty = true;
+    }
+    pub fn add_mesh(&mut self, handle : MeshHandle, triangles : Vec<[f32;3]>){
+        if let Some(bounds) = BoundingBox::from_points(triangles.iter().map(|&position|Point3::from(position))){
+            self.meshes.insert(handle, (bounds, triangles));
+        }
+    }
+    pub fn set_instance_mesh(&mut self, handle : InstanceHandle, mesh : MeshHandle) -> bool{
+        if mesh != DEFAULT_MESH && !self.meshes.contains_key(&mesh){return false}
+        let instance = match self.instances.iter().position(|instance|instance.0 == handle){
+            Some(i)=>{self.instances.remove(i)}
+            None=>{return false}
+        };
+        let index = self.instances.partition_point(|instance|instance.2 <= mesh);
+        self.instances.insert(index, (instance.0, instance.1, mesh));
+        self.dirty = true;
+        return true;
+    }
+    pub fn get_instance_mesh(&self, handle : InstanceHandle) -> Option<MeshHandle>{
+        return self.instances.iter().find(|instance|instance.0 == handle).map(|instance|instance.2);
+    }
+    pub fn get_mesh_bounds(&self, mesh : MeshHandle, default_bounds : &BoundingBox) -> BoundingBox{
+        return self.meshes.get(&mesh).map_or(*default_bounds, |geometry|geometry.0);
+    }
+    pub fn get_mesh_batches(&self) -> Vec<(MeshHandle, u32, u32)>{
+        return get_batches(self.instances.iter().map(|instance|instance.2));
+    }
+    pub fn get_selection_batches(&self) -> Vec<(MeshHandle, u32, u32)>{
+        return get_batches(self.selection.iter().filter_map(|&handle|self.get_instance_mesh(handle)));
+    }
+    pub fn update_instance(&mut self, handle : InstanceHandle, matrix : ModelMatrix) -> bool{
+        for instance in self.instances.iter_mut(){
+            if instance.0 == handle{
+                instance.1 = matrix;
+                self.dirty = true;
+                return true;
+            }
+        }
+        return false;
+    }
+    pub fn remove_instance(&mut self, handle : InstanceHandle) -> bool{
+        for i in 0..self.instances.len(){
+            if self.instances[i].0 == handle{
+                self.instances.remove(i);
+                self.selection.retain(|&selected|selected != handle);
+                self.dirty = true;
+                return true;
+            }
+        }
+        return false;
+    }
+    pub fn set_selection(&mut self, handles : Vec<InstanceHandle>){
+        let mut selection : Vec<InstanceHandle> = vec!();
+        for handle in handles{
+            if self.get_instance(handle).is_some() && !selection.contains(&handle){selection.push(handle)}
+        }
+        if selection != self.selection{
+            self.selection = selection;
+            self.dirty = true;
+        }
+    }
+    pub fn select(&mut self, handle : Option<InstanceHandle>, extend : bool){
+        let mut selection = if extend{self.selection.clone()}else{vec!()};
+        if let Some(handle) = handle{
+            match selection.iter().position(|&selected|selected == handle){
+                Some(i) if extend=>{selection.remove(i);}
+                Some(_)=>{}
+                None=>{selection.push(handle)}
+            }
+        }
+        self.set_selection(selection);
+    }
+    pub fn get_selection(&self) -> &[InstanceHandle]{
+        return &self.selection;
+    }
+    pub fn get_active_instance(&self) -> Option<(InstanceHandle, ModelMatrix)>{
+        let handle = *self.selection.last()?;
+        return self.get_instance(handle).map(|matrix|(handle, matrix));
+    }
+    pub fn is_selected(&self, handle : InstanceHandle) -> bool{
+        return self.selection.contains(&handle);
+    }
+    pub fn get_selection_matrices(&self, default_bounds : &BoundingBox, outline_scale : f32) -> Vec<ModelMatrix>{
+        let selected : Vec<(InstanceHandle, ModelMatrix, MeshHandle)> = self.selection.iter().filter_map(|&handle|self.instances.iter().find(|instance|instance.0 == handle).copied()).collect();
+        let outlines = selected.iter().map(|instance|{
+            let bounds = self.get_mesh_bounds(instance.2, default_bounds);
+            let center = bounds.min.midpoint(bounds.max).to_vec();
+            let outline = Matrix4::from_translation(center) * Matrix4::from_scale(outline_scale) * Matrix4::from_translation(-center);
+            return ModelMatrix{matrix:instance.1.matrix * outline};
+        });
+        return selected.iter().map(|instance|instance.1).chain(outlines).collect();
+    }
+    pub fn get_instance(&self, handle : InstanceHandle) -> Option<ModelMatrix>{
+        return self.instances.iter().find(|instance|instance.0 == handle).map(|instance|instance.1);
+    }
+    pub fn get_handle(&self, index : usize) -> Option<InstanceHandle>{
+        return self.instances.get(index).map(|instance|instance.0);
+    }
+    pub fn get_model_matrices(&self) -> Vec<ModelMatrix>{
+        return self.instances.iter().map(|instance|instance.1).collect();
+    }
+    pub fn get_bounds(&self, default_bounds : &BoundingBox) -> Option<BoundingBox>{
+        return self.instances.iter().map(|instance|self.get_mesh_bounds(instance.2, default_bounds).transform(&instance.1.matrix)).reduce(|bounds, other|bounds.union(&other));
+    }
+    pub fn get_instances_bounds(&self, handles : &[InstanceHandle], default_bounds : &BoundingBox) -> Option<BoundingBox>{
+        return self.instances.iter().filter(|instance|handles.contains(&instance.0)).map(|instance|self.get_mesh_bounds(instance.2, default_bounds).transform(&instance.1.matrix)).reduce(|bounds, other|bounds.union(&other));
+    }
+    pub fn pick_instance(&self, ray : &Ray, default_triangles : &[[f32;3]], handle : InstanceHandle) -> PickResult{
+        let mut closest = None;
+        if let Some(&(_, matrix, mesh)) = self.instances.iter().find(|instance|instance.0 == handle){
+            let triangles = self.get_mesh_triangles(mesh, default_triangles);
+            for triangle in triangles.chunks_exact(3){
+                let [a, b, c] = [0, 1, 2].map(|i|matrix.matrix.transform_point(Point3::from(triangle[i])));
+                if let Some(distance) = ray.intersect_triangle(a, b, c){
+                    if closest.is_none_or(|closest|distance < closest){closest = Some(distance)}
+                }
+            }
+            if closest.is_none(){
+                closest = BoundingBox::from_points(triangles.iter().map(|&position|Point3::from(position))).and_then(|bounds|ray.intersect_bounds(&bounds.transform(&matrix.matrix)));
+            }
+        }
+        return PickResult{handle,point:ray.at(closest.unwrap_or(0.0))};
+    }
+    pub fn pick(&self, ray : &Ray, default_triangles : &[[f32;3]]) -> Option<PickResult>{
+        let default_bounds = BoundingBox::from_points(default_triangles.iter().map(|&position|Point3::from(position)));
+        let mut closest : Option<(f32, InstanceHandle)> = None;
+        for (handle, matrix, mesh) in self.instances.iter(){
+            let mesh_bounds = match self.meshes.get(mesh).map(|geometry|geometry.0).or(default_bounds){
+                Some(bounds)=>{bounds}
+                None=>{continue}
+            };
+            let bounds_distance = match ray.intersect_bounds(&mesh_bounds.transform(&matrix.matrix)){
+                Some(distance)=>{distance}
+                None=>{continue}
+            };
+            if closest.is_some_and(|closest|closest.0 < bounds_distance){continue}
+            for triangle in self.get_mesh_triangles(*mesh, default_triangles).chunks_exact(3){
+                let [a, b, c] = [0, 1, 2].map(|i|matrix.matrix.transform_point(Point3::from(triangle[i])));
+                if let Some(distance) = ray.intersect_triangle(a, b, c){
+                    if closest.is_none_or(|closest|distance < closest.0){closest = Some((distance, *handle))}
+                }
+            }
+        }
+        return closest.map(|(distance, handle)|PickResult{handle,point:ray.at(distance)});
+    }
+    fn get_mesh_triangles<'a>(&'a self, mesh : MeshHandle, default_triangles : &'a [[f32;3]]) -> &'a [[f32;3]]{
+        return self.meshes.get(&mesh).map_or(default_triangles, |geometry|&geometry.1);
+    }
+    pub fn len(&self) -> usize{
+        return self.instances.len();
+    }
+    pub fn is_empty(&self) -> bool{
+        return self.instances.is_empty();
+    }
+}
+fn get_batches(meshes : impl Iterator<Item = MeshHandle>) -> Vec<(MeshHandle, u32, u32)>{
+    let mut batches : Vec<(MeshHandle, u32, u32)> = vec!();
+    for (i, mesh) in meshes.enumerate(){
+        match batches.last_mut(){
+            Some(batch) if batch.0 == mesh=>{batch.2 += 1}
+            _=>{batches.push((mesh, i as u32, 1))}
+        }
+    }
+    return batches;
+}
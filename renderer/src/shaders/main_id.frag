@@ -0,0 +1,12 @@
+#version 450
+
+layout(location = 0) in vec3 fragColor;
+layout(location = 1) flat in uint inInstance;
+
+layout(location = 0) out vec4 outColor;
+layout(location = 1) out uint outInstance;
+
+void main() {
+    outColor = vec4(fragColor, 1.0);
+    outInstance = inInstance;
+}
\ No newline at end of file
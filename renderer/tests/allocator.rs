@@ -0,0 +1,461 @@
+use std::cell::RefCell;
+
+use ash::vk::{Handle, DeviceMemory, Image, MemoryPropertyFlags, PhysicalDeviceMemoryProperties, MemoryType, MemoryHeap, MemoryHeapFlags};
+use renderer::{allocator::{Allocator, MemoryRegionPointer, frame::LinearAllocator, defragment::{plan_defragmentation, DefragmentationBlock, DefragmentationAllocation, DefragmentationMove}, memory_backend::{MemoryBackend, DedicatedResource}, memory_regions::{FreeList, MemoryRegion, ResourceKind}}, error::{RendererError, RendererResult}};
+
+#[derive(Default)]
+struct MockBackend{
+    next_handle : RefCell<u64>,
+    live : RefCell<Vec<(DeviceMemory, u64)>>,
+    dedicated : RefCell<Vec<DedicatedResource>>,
+    mapped : RefCell<Vec<(DeviceMemory, Vec<u8>)>>,
+    flushed : RefCell<Vec<(DeviceMemory, u64, u64)>>,
+}
+impl MockBackend{
+    fn live_blocks(&self) -> usize{
+        return self.live.borrow().len();
+    }
+}
+impl MemoryBackend for MockBackend{
+    unsafe fn allocate_memory(&self, size : u64, _memory_type : u32, dedicated : Option<DedicatedResource>) -> RendererResult<DeviceMemory>{
+        self.dedicated.borrow_mut().extend(dedicated);
+        *self.next_handle.borrow_mut() += 1;
+        let memory = DeviceMemory::from_raw(*self.next_handle.borrow());
+        self.live.borrow_mut().push((memory, size));
+        return Ok(memory);
+    }
+    unsafe fn free_memory(&self, memory : DeviceMemory){
+        let mut live = self.live.borrow_mut();
+        let index = live.iter().position(|&(live_memory, _)|live_memory == memory).expect("Freed memory that was never allocated");
+        live.swap_remove(index);
+        assert!(self.mapped.borrow().iter().all(|(mapped_memory, _)|*mapped_memory != memory), "Freed memory that is still mapped");
+    }
+    unsafe fn map_memory(&self, memory : DeviceMemory) -> RendererResult<*mut u8>{
+        let size = self.live.borrow().iter().find(|(live_memory, _)|*live_memory == memory).expect("Mapped memory that was never allocated").1;
+        let mut mapped = self.mapped.borrow_mut();
+        assert!(mapped.iter().all(|(mapped_memory, _)|*mapped_memory != memory), "Mapped memory twice");
+        mapped.push((memory, vec!(0; size as usize)));
+        return Ok(mapped.last_mut().unwrap().1.as_mut_ptr());
+    }
+    unsafe fn unmap_memory(&self, memory : DeviceMemory){
+        self.mapped.borrow_mut().retain(|(mapped_memory, _)|*mapped_memory != memory);
+    }
+    unsafe fn flush_memory(&self, memory : DeviceMemory, offset : u64, size : u64) -> RendererResult<()>{
+        self.flushed.borrow_mut().push((memory, offset, size));
+        return Ok(());
+    }
+    unsafe fn invalidate_memory(&self, _memory : DeviceMemory, _offset : u64, _size : u64) -> RendererResult<()>{
+        return Ok(());
+    }
+}
+fn memory_properties_with_heaps(device_heap_size : u64, host_heap_size : u64) -> PhysicalDeviceMemoryProperties{
+    let mut properties = PhysicalDeviceMemoryProperties::default();
+    properties.memory_type_count = 3;
+    properties.memory_types[0] = MemoryType{property_flags:MemoryPropertyFlags::DEVICE_LOCAL,heap_index:0};
+    properties.memory_types[1] = MemoryType{property_flags:MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,heap_index:1};
+    properties.memory_types[2] = MemoryType{property_flags:MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_CACHED,heap_index:1};
+    properties.memory_heap_count = 2;
+    properties.memory_heaps[0] = MemoryHeap{size:device_heap_size,flags:MemoryHeapFlags::DEVICE_LOCAL};
+    properties.memory_heaps[1] = MemoryHeap{size:host_heap_size,flags:MemoryHeapFlags::empty()};
+    return properties;
+}
+fn memory_properties() -> PhysicalDeviceMemoryProperties{
+    return memory_properties_with_heaps(1 << 30, 1 << 30);
+}
+fn mock_allocator() -> Allocator<MockBackend>{
+    return Allocator::with_backend(MockBackend::default(), memory_properties());
+}
+fn allocate(allocator : &mut Allocator<MockBackend>, size : u64, alignment : u64) -> MemoryRegionPointer{
+    return allocate_kind(allocator, size, alignment, ResourceKind::Linear);
+}
+fn allocate_kind(allocator : &mut Allocator<MockBackend>, size : u64, alignment : u64, kind : ResourceKind) -> MemoryRegionPointer{
+    return unsafe{allocator.create_allocation(size, alignment, kind, MemoryPropertyFlags::DEVICE_LOCAL, u32::MAX)}.expect("Allocation failed");
+}
+fn region(allocator : &Allocator<MockBackend>, allocation : &MemoryRegionPointer) -> (DeviceMemory, MemoryRegion){
+    let map_data = unsafe{allocator.get_memory_map_data(allocation)};
+    return (map_data.memory, MemoryRegion{offset:map_data.offset,size:map_data.size});
+}
+fn overlaps(a : &MemoryRegion, b : &MemoryRegion) -> bool{
+    return a.offset < b.offset + b.size && b.offset < a.offset + a.size;
+}
+
+#[test]
+fn free_list_respects_alignment(){
+    let mut free_list = FreeList::new(1024);
+    let first = free_list.allocate(3, 1, ResourceKind::Linear, 1).unwrap();
+    let second = free_list.allocate(64, 256, ResourceKind::Linear, 1).unwrap();
+    assert_eq!(first, MemoryRegion{offset:0,size:3});
+    assert_eq!(second.offset, 256);
+    assert_eq!(free_list.get_free_size(), 1024 - 3 - 64);
+    assert!(free_list.allocate(1024, 1, ResourceKind::Linear, 1).is_none());
+}
+#[test]
+fn free_list_coalesces_neighbours(){
+    let mut free_list = FreeList::new(300);
+    let regions = [free_list.allocate(100, 1, ResourceKind::Linear, 1).unwrap(), free_list.allocate(100, 1, ResourceKind::Linear, 1).unwrap(), free_list.allocate(100, 1, ResourceKind::Linear, 1).unwrap()];
+    assert_eq!(free_list.get_free_range_count(), 0);
+    free_list.free(regions[0]);
+    free_list.free(regions[2]);
+    assert_eq!(free_list.get_free_range_count(), 2);
+    free_list.free(regions[1]);
+    assert_eq!(free_list.get_free_range_count(), 1);
+    assert!(free_list.is_empty());
+    assert_eq!(free_list.allocate(300, 1, ResourceKind::Linear, 1), Some(MemoryRegion{offset:0,size:300}));
+}
+#[test]
+fn free_list_prefers_best_fit(){
+    let mut free_list = FreeList::new(1000);
+    let regions = (0..5).map(|_|free_list.allocate(200, 1, ResourceKind::Linear, 1).unwrap()).collect::<Vec<_>>();
+    free_list.free(regions[1]);
+    free_list.free(MemoryRegion{offset:regions[3].offset,size:100});
+    let small = free_list.allocate(50, 1, ResourceKind::Linear, 1).unwrap();
+    assert_eq!(small.offset, regions[3].offset);
+}
+#[test]
+fn free_list_pads_between_linear_and_optimal(){
+    let mut free_list = FreeList::new(4096);
+    let buffer = free_list.allocate(100, 1, ResourceKind::Linear, 1024).unwrap();
+    let image = free_list.allocate(100, 1, ResourceKind::Optimal, 1024).unwrap();
+    let second_buffer = free_list.allocate(100, 1, ResourceKind::Linear, 1024).unwrap();
+    assert_eq!(buffer.offset, 0);
+    assert_eq!(image.offset, 1024);
+    assert_eq!(second_buffer.offset, 100);
+    let second_image = free_list.allocate(100, 1, ResourceKind::Optimal, 1024).unwrap();
+    assert_eq!(second_image.offset, 1124);
+}
+#[test]
+fn free_list_keeps_optimal_off_the_page_of_a_following_buffer(){
+    let mut free_list = FreeList::new(4096);
+    let first = free_list.allocate(1500, 1, ResourceKind::Linear, 1024).unwrap();
+    free_list.allocate(100, 1, ResourceKind::Linear, 1024).unwrap();
+    free_list.free(first);
+    let large_image = free_list.allocate(1200, 1, ResourceKind::Optimal, 1024).unwrap();
+    assert_eq!(large_image.offset, 2048);
+    let small_image = free_list.allocate(1000, 1, ResourceKind::Optimal, 1024).unwrap();
+    assert_eq!(small_image.offset, 0);
+    assert!(free_list.allocate(900, 1, ResourceKind::Optimal, 1024).is_none());
+    assert_eq!(free_list.allocate(300, 1, ResourceKind::Linear, 1024), Some(MemoryRegion{offset:1600,size:300}));
+    assert_eq!(free_list.allocate(400, 1, ResourceKind::Linear, 1024), Some(MemoryRegion{offset:1024,size:400}));
+}
+#[test]
+fn allocator_separates_adjacent_buffers_and_images(){
+    let mut allocator = mock_allocator();
+    allocator.set_buffer_image_granularity(4096);
+    let buffer = allocate_kind(&mut allocator, 256, 16, ResourceKind::Linear);
+    let image = allocate_kind(&mut allocator, 256, 256, ResourceKind::Optimal);
+    let (buffer_memory, buffer_region) = region(&allocator, &buffer);
+    let (image_memory, image_region) = region(&allocator, &image);
+    assert_eq!(buffer_memory, image_memory);
+    assert_ne!((buffer_region.offset + buffer_region.size - 1) / 4096, image_region.offset / 4096);
+    let other_image = allocate_kind(&mut allocator, 256, 256, ResourceKind::Optimal);
+    assert_eq!(region(&allocator, &other_image).1.offset, image_region.offset + 256);
+    allocator.destroy();
+}
+#[test]
+fn allocations_share_a_block(){
+    let mut allocator = mock_allocator();
+    let first = allocate(&mut allocator, 1024, 256);
+    let second = allocate(&mut allocator, 1024, 256);
+    assert_eq!(allocator.device.live_blocks(), 1);
+    let (first_memory, first_region) = region(&allocator, &first);
+    let (second_memory, second_region) = region(&allocator, &second);
+    assert_eq!(first_memory, second_memory);
+    assert!(!overlaps(&first_region, &second_region));
+    allocator.destroy();
+    assert_eq!(allocator.device.live_blocks(), 0);
+}
+#[test]
+fn handles_stay_valid_after_free(){
+    let mut allocator = mock_allocator();
+    let allocations = (0..4).map(|_|allocate(&mut allocator, 4096, 16)).collect::<Vec<_>>();
+    let before = allocations.iter().map(|allocation|region(&allocator, allocation)).collect::<Vec<_>>();
+    unsafe{allocator.destroy_allocation(&allocations[1])};
+    assert_eq!(region(&allocator, &allocations[0]), before[0]);
+    assert_eq!(region(&allocator, &allocations[2]), before[2]);
+    assert_eq!(region(&allocator, &allocations[3]), before[3]);
+    let reused = allocate(&mut allocator, 4096, 16);
+    assert_eq!(region(&allocator, &reused), before[1]);
+    allocator.destroy();
+}
+#[test]
+fn large_allocations_get_their_own_block(){
+    let mut allocator = mock_allocator();
+    let block_size = allocator.get_block_size(0);
+    allocate(&mut allocator, 1024, 1);
+    let large = allocate(&mut allocator, block_size * 2, 1);
+    assert_eq!(allocator.device.live_blocks(), 2);
+    assert_eq!(region(&allocator, &large).1, MemoryRegion{offset:0,size:block_size * 2});
+    allocator.destroy();
+}
+#[test]
+fn unsupported_memory_type_fails(){
+    let mut allocator = mock_allocator();
+    let result = unsafe{allocator.create_allocation(64, 1, ResourceKind::Linear, MemoryPropertyFlags::LAZILY_ALLOCATED, u32::MAX)};
+    assert!(result.is_err());
+    assert_eq!(allocator.device.live_blocks(), 0);
+}
+#[test]
+fn random_allocations_never_overlap(){
+    let mut allocator = mock_allocator();
+    let mut seed = 0x2545F4914F6CDD1Du64;
+    let mut next = ||{seed ^= seed << 13; seed ^= seed >> 7; seed ^= seed << 17; seed};
+    let mut live : Vec<MemoryRegionPointer> = vec!();
+    for _ in 0..2000{
+        if live.len() > 0 && next() % 3 == 0{
+            let index = (next() % live.len() as u64) as usize;
+            let allocation = live.swap_remove(index);
+            unsafe{allocator.destroy_allocation(&allocation)};
+        }
+        else{
+            let size = 1 + next() % (1 << 20);
+            let alignment = 1 << (next() % 9);
+            let allocation = allocate(&mut allocator, size, alignment);
+            let (_, new_region) = region(&allocator, &allocation);
+            assert_eq!(new_region.offset % alignment, 0);
+            live.push(allocation);
+        }
+    }
+    let regions = live.iter().map(|allocation|region(&allocator, allocation)).collect::<Vec<_>>();
+    for (i, (memory, a)) in regions.iter().enumerate(){
+        for (other_memory, b) in regions[i+1..].iter(){
+            assert!(memory != other_memory || !overlaps(a, b), "{:?} overlaps {:?}", a, b);
+        }
+    }
+    allocator.destroy();
+    assert_eq!(allocator.device.live_blocks(), 0);
+}
+#[test]
+fn block_size_scales_with_heap(){
+    let allocator = Allocator::with_backend(MockBackend::default(), memory_properties_with_heaps(8 << 30, 256 << 20));
+    assert_eq!(allocator.get_block_size(0), 256 << 20);
+    assert_eq!(allocator.get_block_size(1), 32 << 20);
+}
+#[test]
+fn empty_blocks_are_released(){
+    let mut allocator = mock_allocator();
+    allocator.set_empty_blocks_kept(0);
+    let allocation = allocate(&mut allocator, 1024, 1);
+    assert_eq!(allocator.get_heap_usage(0), allocator.get_block_size(0));
+    unsafe{allocator.destroy_allocation(&allocation)};
+    assert_eq!(allocator.device.live_blocks(), 0);
+    assert_eq!(allocator.get_heap_usage(0), 0);
+}
+#[test]
+fn empty_blocks_are_kept_for_hysteresis(){
+    let mut allocator = mock_allocator();
+    allocator.set_empty_blocks_kept(1);
+    let block_size = allocator.get_block_size(0);
+    let first = allocate(&mut allocator, block_size, 1);
+    let second = allocate(&mut allocator, block_size, 1);
+    assert_eq!(allocator.device.live_blocks(), 2);
+    unsafe{allocator.destroy_allocation(&first)};
+    assert_eq!(allocator.device.live_blocks(), 2);
+    unsafe{allocator.destroy_allocation(&second)};
+    assert_eq!(allocator.device.live_blocks(), 1);
+    allocate(&mut allocator, 1024, 1);
+    assert_eq!(allocator.device.live_blocks(), 1);
+    allocator.destroy();
+}
+#[test]
+fn heap_budget_limits_allocations(){
+    let mut allocator = mock_allocator();
+    let block_size = allocator.get_block_size(0);
+    let budget = block_size + 4096;
+    allocator.set_heap_budget(0, Some(budget));
+    let first = allocate(&mut allocator, block_size, 1);
+    let clamped = allocate(&mut allocator, 1024, 1);
+    assert_eq!(allocator.get_heap_usage(0), budget);
+    assert_ne!(region(&allocator, &first).0, region(&allocator, &clamped).0);
+    let result = unsafe{allocator.create_allocation(8192, 1, ResourceKind::Linear, MemoryPropertyFlags::DEVICE_LOCAL, u32::MAX)};
+    assert!(matches!(result, Err(RendererError::OutOfBudget(0))));
+    assert_eq!(allocator.device.live_blocks(), 2);
+    unsafe{allocator.destroy_allocation(&first)};
+    allocate(&mut allocator, 2048, 1);
+    allocator.destroy();
+}
+#[test]
+fn stats_track_blocks_and_fragmentation(){
+    let mut allocator = mock_allocator();
+    let allocations = (0..4).map(|_|allocate(&mut allocator, 1024, 1)).collect::<Vec<_>>();
+    unsafe{allocator.destroy_allocation(&allocations[1])};
+    let stats = allocator.get_stats();
+    let block_size = allocator.get_block_size(0);
+    assert_eq!(stats.total.block_count, 1);
+    assert_eq!(stats.total.allocation_count, 3);
+    assert_eq!(stats.total.reserved_bytes, block_size);
+    assert_eq!(stats.total.used_bytes, 3072);
+    assert_eq!(stats.total.largest_free_range, block_size - 4096);
+    assert!(stats.total.fragmentation > 0.0 && stats.total.fragmentation < 0.01);
+    assert_eq!(stats.memory_types[0].stats, stats.total);
+    assert_eq!(stats.heaps[0].stats, stats.total);
+    assert_eq!(stats.memory_types[1].stats.block_count, 0);
+    allocator.destroy();
+}
+#[test]
+fn stats_json_lists_suballocations(){
+    let mut allocator = mock_allocator();
+    allocate(&mut allocator, 1024, 1);
+    allocate(&mut allocator, 512, 1);
+    let json : serde_json::Value = serde_json::from_str(&allocator.get_stats_json()).expect("Stats are not valid JSON");
+    assert_eq!(json["Total"]["AllocationCount"], 2);
+    assert_eq!(json["MemoryInfo"]["Heap 0"]["MemoryPools"]["Type 0"]["Stats"]["BlockCount"], 1);
+    let suballocations = json["DefaultPools"]["Type 0"]["Blocks"]["0"]["Suballocations"].as_array().unwrap();
+    assert_eq!(suballocations.len(), 3);
+    assert_eq!(suballocations[1]["Offset"], 1024);
+    assert_eq!(suballocations[2]["Type"], "FREE");
+    allocator.destroy();
+}
+#[test]
+fn dedicated_allocations_are_separate(){
+    let mut allocator = mock_allocator();
+    let shared = allocate(&mut allocator, 1024, 1);
+    let image = DedicatedResource::Image(Image::from_raw(7));
+    let dedicated = unsafe{allocator.create_dedicated_allocation(4096, MemoryPropertyFlags::DEVICE_LOCAL, u32::MAX, image)}.expect("Dedicated allocation failed");
+    assert_eq!(*allocator.device.dedicated.borrow(), vec!(image));
+    assert_eq!(allocator.device.live_blocks(), 2);
+    assert_ne!(region(&allocator, &shared).0, region(&allocator, &dedicated).0);
+    assert_eq!(region(&allocator, &dedicated).1, MemoryRegion{offset:0,size:4096});
+    allocate(&mut allocator, 1024, 1);
+    assert_eq!(allocator.device.live_blocks(), 2);
+    let stats = allocator.get_stats();
+    assert_eq!(stats.total.block_count, 1);
+    assert_eq!(stats.total.allocation_count, 2);
+    assert_eq!(stats.total.dedicated_allocation_count, 1);
+    assert_eq!(stats.total.dedicated_bytes, 4096);
+    unsafe{allocator.destroy_allocation(&dedicated)};
+    assert_eq!(allocator.device.live_blocks(), 1);
+    assert_eq!(allocator.get_stats().total.dedicated_allocation_count, 0);
+    allocator.destroy();
+}
+#[test]
+fn large_resources_prefer_dedicated_allocations(){
+    let allocator = mock_allocator();
+    let block_size = allocator.get_block_size(0);
+    assert!(!allocator.prefers_dedicated_allocation(block_size / 4, MemoryPropertyFlags::DEVICE_LOCAL, u32::MAX));
+    assert!(allocator.prefers_dedicated_allocation(block_size, MemoryPropertyFlags::DEVICE_LOCAL, u32::MAX));
+}
+#[test]
+fn host_visible_blocks_are_mapped_once(){
+    let mut allocator = mock_allocator();
+    let first = unsafe{allocator.create_allocation(64, 16, ResourceKind::Linear, MemoryPropertyFlags::HOST_VISIBLE, u32::MAX)}.expect("Allocation failed");
+    let second = unsafe{allocator.create_allocation(64, 16, ResourceKind::Linear, MemoryPropertyFlags::HOST_VISIBLE, u32::MAX)}.expect("Allocation failed");
+    assert_eq!(allocator.device.mapped.borrow().len(), 1);
+    let device_local = allocate(&mut allocator, 64, 16);
+    assert!(unsafe{device_local.mapped_slice::<u32>(&mut allocator)}.is_none());
+    unsafe{first.mapped_slice::<u32>(&mut allocator)}.unwrap().copy_from_slice(&[1; 16]);
+    unsafe{second.mapped_slice::<u32>(&mut allocator)}.unwrap().copy_from_slice(&[2; 16]);
+    assert_eq!(unsafe{first.mapped_slice::<u32>(&mut allocator)}.unwrap(), &[1; 16]);
+    let map_data = unsafe{allocator.get_memory_map_data(&second)};
+    assert_eq!(allocator.device.mapped.borrow()[0].1[map_data.offset as usize], 2);
+    allocator.destroy();
+    assert!(allocator.device.mapped.borrow().is_empty());
+}
+#[test]
+fn non_coherent_flushes_are_atom_aligned(){
+    let mut allocator = mock_allocator();
+    allocator.set_non_coherent_atom_size(256);
+    let coherent = unsafe{allocator.create_allocation(100, 4, ResourceKind::Linear, MemoryPropertyFlags::HOST_COHERENT, u32::MAX)}.expect("Allocation failed");
+    unsafe{allocator.flush_allocation(&coherent, 0, 100)}.unwrap();
+    assert!(allocator.device.flushed.borrow().is_empty());
+    let first = unsafe{allocator.create_allocation(100, 4, ResourceKind::Linear, MemoryPropertyFlags::HOST_CACHED, u32::MAX)}.expect("Allocation failed");
+    let second = unsafe{allocator.create_allocation(100, 4, ResourceKind::Linear, MemoryPropertyFlags::HOST_CACHED, u32::MAX)}.expect("Allocation failed");
+    let map_data = unsafe{allocator.get_memory_map_data(&second)};
+    assert_eq!(map_data.offset, 100);
+    unsafe{allocator.flush_allocation(&first, 0, 100)}.unwrap();
+    unsafe{allocator.flush_allocation(&second, 50, 100)}.unwrap();
+    assert_eq!(*allocator.device.flushed.borrow(), vec!((map_data.memory, 0, 256), (map_data.memory, 0, 256)));
+    let block_size = allocator.get_block_size(2);
+    let last = unsafe{allocator.create_allocation(block_size - 200, 4, ResourceKind::Linear, MemoryPropertyFlags::HOST_CACHED, u32::MAX)}.expect("Allocation failed");
+    unsafe{allocator.flush_allocation(&last, 0, block_size - 200)}.unwrap();
+    assert_eq!(allocator.device.flushed.borrow()[2], (map_data.memory, 0, block_size));
+    allocator.destroy();
+}
+#[test]
+fn preferred_memory_flags_fall_back_to_required(){
+    let allocator = mock_allocator();
+    assert_eq!(allocator.get_preferred_memory_property_flags(MemoryPropertyFlags::HOST_VISIBLE, MemoryPropertyFlags::HOST_COHERENT, u32::MAX), MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT);
+    assert_eq!(allocator.get_preferred_memory_property_flags(MemoryPropertyFlags::HOST_VISIBLE, MemoryPropertyFlags::HOST_COHERENT, 1 << 2), MemoryPropertyFlags::HOST_VISIBLE);
+    assert_eq!(allocator.get_preferred_memory_property_flags(MemoryPropertyFlags::DEVICE_LOCAL, MemoryPropertyFlags::HOST_VISIBLE, u32::MAX), MemoryPropertyFlags::DEVICE_LOCAL);
+}
+#[test]
+fn free_list_reserves_exact_ranges(){
+    let mut free_list = FreeList::new(1024);
+    assert!(free_list.reserve(MemoryRegion{offset:256,size:256}, ResourceKind::Linear));
+    assert!(!free_list.reserve(MemoryRegion{offset:384,size:256}, ResourceKind::Linear));
+    assert_eq!(free_list.get_free_ranges(), vec!(MemoryRegion{offset:0,size:256}, MemoryRegion{offset:512,size:512}));
+    assert_eq!(free_list.get_used_ranges(), vec!((MemoryRegion{offset:256,size:256}, ResourceKind::Linear)));
+}
+fn defragmentation_block(memory_type : u32, size : u64, used : &[(u64, u64)]) -> DefragmentationBlock{
+    return DefragmentationBlock{memory_type,size,used:used.iter().map(|&(offset, size)|(MemoryRegion{offset,size}, ResourceKind::Linear)).collect()};
+}
+fn movable(block : usize, offset : u64, size : u64) -> DefragmentationAllocation{
+    return DefragmentationAllocation{block,region:MemoryRegion{offset,size},alignment:16,kind:ResourceKind::Linear};
+}
+#[test]
+fn defragmentation_empties_sparse_blocks(){
+    let blocks = [
+        defragmentation_block(0, 1024, &[(0, 256), (512, 256)]),
+        defragmentation_block(0, 1024, &[(0, 128), (256, 128)]),
+    ];
+    let allocations = [movable(1, 0, 128), movable(1, 256, 128)];
+    let moves = plan_defragmentation(&blocks, &allocations, 1);
+    assert_eq!(moves.len(), 2);
+    assert!(moves.iter().all(|planned_move|planned_move.block == 0));
+    let mut destinations = moves.iter().map(|planned_move|MemoryRegion{offset:planned_move.offset,size:128}).collect::<Vec<_>>();
+    destinations.extend([MemoryRegion{offset:0,size:256}, MemoryRegion{offset:512,size:256}]);
+    for (i, a) in destinations.iter().enumerate(){
+        for b in destinations[i + 1..].iter(){assert!(!overlaps(a, b))}
+    }
+}
+#[test]
+fn defragmentation_keeps_fixed_allocations_and_memory_types(){
+    let blocks = [
+        defragmentation_block(0, 1024, &[(0, 512), (512, 256)]),
+        defragmentation_block(1, 1024, &[(0, 128)]),
+        defragmentation_block(0, 1024, &[(512, 256)]),
+    ];
+    assert!(plan_defragmentation(&blocks, &[movable(1, 0, 128)], 1).is_empty());
+    let allocations = [movable(1, 0, 128), movable(2, 512, 256)];
+    assert_eq!(plan_defragmentation(&blocks, &allocations, 1), vec!(DefragmentationMove{allocation:1,block:0,offset:768}));
+}
+#[test]
+fn linear_allocator_bumps_and_resets(){
+    let mut linear = LinearAllocator::new(1024);
+    assert_eq!(linear.allocate(100, 1), Some(0));
+    assert_eq!(linear.allocate(100, 256), Some(256));
+    assert_eq!(linear.get_used_size(), 356);
+    assert_eq!(linear.allocate(700, 1), None);
+    assert_eq!(linear.allocate(668, 1), Some(356));
+    assert_eq!(linear.allocate(1, 1), None);
+    linear.reset();
+    assert_eq!(linear.get_used_size(), 0);
+    assert_eq!(linear.allocate(1024, 64), Some(0));
+}
+#[test]
+fn destroy_reports_live_allocations(){
+    let mut allocator = mock_allocator();
+    let freed = allocate(&mut allocator, 64, 1);
+    let leaked = allocate(&mut allocator, 128, 1);
+    allocator.set_allocation_name(&freed, "freed buffer");
+    allocator.set_allocation_name(&leaked, "leaked buffer");
+    assert_eq!(allocator.get_allocation_name(&leaked), Some("leaked buffer"));
+    unsafe{allocator.destroy_allocation(&freed)};
+    let live_allocations = allocator.destroy();
+    assert_eq!(live_allocations.len(), 1);
+    assert_eq!(live_allocations[0].name, "leaked buffer");
+    assert_eq!(live_allocations[0].region.size, 128);
+    assert!(live_allocations[0].to_string().contains("leaked buffer"));
+    assert!(allocator.get_live_allocations().is_empty());
+}
+#[test]
+fn stats_json_includes_allocation_names(){
+    let mut allocator = mock_allocator();
+    let allocation = allocate(&mut allocator, 256, 1);
+    allocator.set_allocation_name(&allocation, "instance buffer");
+    let json : serde_json::Value = serde_json::from_str(&allocator.get_stats_json()).unwrap();
+    let suballocations = json["DefaultPools"]["Type 0"]["Blocks"]["0"]["Suballocations"].as_array().unwrap();
+    assert_eq!(suballocations[0]["Name"], "instance buffer");
+    assert!(suballocations[1].get("Name").is_none());
+    unsafe{allocator.destroy_allocation(&allocation)};
+    assert!(allocator.destroy().is_empty());
+}
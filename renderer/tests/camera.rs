@@ -0,0 +1,314 @@
+use ash::vk::Extent2D;
+use cgmath::{Matrix4, Point3, Vector3, Vector4, InnerSpace, MetricSpace};
+
+use renderer::math::{BoundingBox, camera::{Camera, CameraMode, MovementKey, Projection, ViewPreset}};
+
+const EXTENT : Extent2D = Extent2D{width:800,height:600};
+
+fn assert_matrix_eq(a : Matrix4<f32>, b : Matrix4<f32>){
+    let a : &[f32; 16] = a.as_ref();
+    let b : &[f32; 16] = b.as_ref();
+    for (a, b) in a.iter().zip(b.iter()){
+        assert!((a - b).abs() < 1e-4, "{:?} != {:?}", a, b);
+    }
+}
+fn project(camera : &Camera, point : Point3<f32>) -> (f32, f32){
+    let clip = camera.get_projection() * camera.get_view() * Vector4::new(point.x, point.y, point.z, 1.0);
+    return (clip.x / clip.w, clip.y / clip.w);
+}
+fn depth(camera : &Camera, point : Point3<f32>) -> f32{
+    let clip = camera.get_projection() * camera.get_view() * Vector4::new(point.x, point.y, point.z, 1.0);
+    return clip.z / clip.w;
+}
+
+#[test]
+fn look_at_matches_view(){
+    let mut camera = Camera::new(EXTENT);
+    camera.look_at(Point3::new(3.0, 0.5, 0.5), Point3::new(0.0, 0.0, 0.0));
+    assert_matrix_eq(camera.get_view(), Matrix4::look_at_rh(Point3::new(3.0, 0.5, 0.5), Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)));
+}
+#[test]
+fn orbit_keeps_distance(){
+    let mut camera = Camera::new(EXTENT);
+    let distance = camera.get_distance();
+    camera.orbit((120.0, -40.0));
+    let eye = camera.get_eye();
+    assert!((eye.distance(camera.get_center()) - distance).abs() < 1e-4);
+    assert_matrix_eq(camera.get_view(), Matrix4::look_at_rh(eye, camera.get_center(), Vector3::new(0.0, 0.0, 1.0)));
+}
+#[test]
+fn orbit_clamps_pitch(){
+    let mut camera = Camera::new(EXTENT);
+    for _ in 0..100{
+        camera.orbit((0.0, 10000.0));
+    }
+    assert!(camera.get_pitch().0 < std::f32::consts::FRAC_PI_2);
+    let view = camera.get_view();
+    let view : &[f32; 16] = view.as_ref();
+    assert!(view.iter().all(|value|value.is_finite()));
+    camera.orbit((0.0, -20000.0));
+    assert!(camera.get_pitch().0 > -std::f32::consts::FRAC_PI_2);
+    assert!(camera.get_eye().z < 0.0);
+}
+#[test]
+fn pan_moves_eye_and_center(){
+    let mut camera = Camera::new(EXTENT);
+    let eye = camera.get_eye();
+    let center = camera.get_center();
+    camera.pan((50.0, 20.0));
+    let eye_offset = camera.get_eye() - eye;
+    let center_offset = camera.get_center() - center;
+    assert!(center_offset.magnitude() > 0.0);
+    assert!((eye_offset - center_offset).magnitude() < 1e-4);
+    assert!(center_offset.dot(center - eye).abs() < 1e-4);
+}
+#[test]
+fn pan_follows_cursor(){
+    let mut camera = Camera::new(EXTENT);
+    let origin = project(&camera, Point3::new(0.0, 0.0, 0.0));
+    camera.pan((80.0, -30.0));
+    let moved = project(&camera, Point3::new(0.0, 0.0, 0.0));
+    let pixels = ((moved.0 - origin.0) * EXTENT.width as f32 / 2.0, (moved.1 - origin.1) * EXTENT.height as f32 / 2.0);
+    assert!((pixels.0 - 80.0).abs() < 0.5 && (pixels.1 + 30.0).abs() < 0.5, "{:?}", pixels);
+}
+#[test]
+fn dolly_toward_cursor(){
+    let mut camera = Camera::new(EXTENT);
+    let cursor = (600.0, 150.0);
+    let ndc = (2.0 * cursor.0 / EXTENT.width as f32 - 1.0, 2.0 * cursor.1 / EXTENT.height as f32 - 1.0);
+    let distance = camera.get_distance();
+    let inverse = cgmath::SquareMatrix::invert(&(camera.get_projection() * camera.get_view())).unwrap();
+    let far = inverse * Vector4::new(ndc.0, ndc.1, 1.0, 1.0);
+    let direction = (Point3::new(far.x / far.w, far.y / far.w, far.z / far.w) - camera.get_eye()).normalize();
+    let eye = camera.get_eye();
+    camera.dolly(3.0, Some(cursor));
+    assert!(camera.get_distance() < distance);
+    let before = project(&camera, eye + direction * distance);
+    assert!((before.0 - ndc.0).abs() < 1e-3 && (before.1 - ndc.1).abs() < 1e-3, "{:?} {:?}", before, ndc);
+}
+#[test]
+fn orthographic_dolly_toward_cursor(){
+    let mut camera = Camera::new(EXTENT);
+    camera.set_projection_mode(Projection::Orthographic);
+    camera.finish_transition();
+    let cursor = (600.0, 150.0);
+    let ndc = (2.0 * cursor.0 / EXTENT.width as f32 - 1.0, 2.0 * cursor.1 / EXTENT.height as f32 - 1.0);
+    let inverse = cgmath::SquareMatrix::invert(&(camera.get_projection() * camera.get_view())).unwrap();
+    let point = inverse * Vector4::new(ndc.0, ndc.1, 0.5, 1.0);
+    let point = Point3::new(point.x / point.w, point.y / point.w, point.z / point.w);
+    let distance = camera.get_distance();
+    camera.dolly(3.0, Some(cursor));
+    assert!(camera.get_distance() < distance);
+    let after = project(&camera, point);
+    assert!((after.0 - ndc.0).abs() < 1e-3 && (after.1 - ndc.1).abs() < 1e-3, "{:?} {:?}", after, ndc);
+}
+#[test]
+fn dolly_without_cursor_keeps_center(){
+    let mut camera = Camera::new(EXTENT);
+    let distance = camera.get_distance();
+    camera.dolly(1.0, None);
+    assert_eq!(camera.get_center(), Point3::new(0.0, 0.0, 0.0));
+    assert!((camera.get_distance() - distance * 0.9).abs() < 1e-4);
+    camera.dolly(-1.0, None);
+    assert!((camera.get_distance() - distance).abs() < 1e-4);
+    camera.dolly(1000.0, None);
+    assert!(camera.get_distance() > 0.0);
+}
+#[test]
+fn toggle_mode_preserves_view(){
+    let mut camera = Camera::new(EXTENT);
+    camera.orbit((40.0, 10.0));
+    let view = camera.get_view();
+    camera.toggle_mode();
+    assert_eq!(camera.get_mode(), CameraMode::Fly);
+    assert_matrix_eq(camera.get_view(), view);
+    camera.toggle_mode();
+    assert_eq!(camera.get_mode(), CameraMode::Orbit);
+    assert_matrix_eq(camera.get_view(), view);
+}
+#[test]
+fn fly_moves_with_delta_time(){
+    let mut camera = Camera::new(EXTENT);
+    camera.set_mode(CameraMode::Fly);
+    let eye = camera.get_eye();
+    let forward = (camera.get_center() - eye).normalize();
+    camera.set_movement_key(MovementKey::Forward, true);
+    camera.advance(0.5);
+    let offset = camera.get_eye() - eye;
+    assert!((offset - forward * camera.sensitivity.fly_speed * 0.5).magnitude() < 1e-4);
+    camera.set_movement_key(MovementKey::Fast, true);
+    let eye = camera.get_eye();
+    camera.advance(0.5);
+    let fast_offset = camera.get_eye() - eye;
+    assert!((fast_offset.magnitude() - offset.magnitude() * camera.sensitivity.fly_fast_multiplier).abs() < 1e-4);
+    assert!((camera.get_center() - camera.get_eye()).normalize().dot(forward) > 0.9999);
+}
+#[test]
+fn fly_ignored_in_orbit_mode(){
+    let mut camera = Camera::new(EXTENT);
+    let view = camera.get_view();
+    camera.set_movement_key(MovementKey::Forward, true);
+    camera.advance(1.0);
+    assert_matrix_eq(camera.get_view(), view);
+}
+#[test]
+fn fly_look_keeps_eye(){
+    let mut camera = Camera::new(EXTENT);
+    camera.set_mode(CameraMode::Fly);
+    let eye = camera.get_eye();
+    let center = camera.get_center();
+    camera.look((100.0, -50.0));
+    assert!(camera.get_eye().distance(eye) < 1e-5);
+    assert!(camera.get_center().distance(center) > 0.1);
+    camera.set_movement_key(MovementKey::Up, true);
+    camera.set_movement_key(MovementKey::Left, true);
+    camera.advance(1.0);
+    let offset = camera.get_eye() - eye;
+    assert!((offset.magnitude() - camera.sensitivity.fly_speed).abs() < 1e-4);
+    assert!(offset.z > 0.0);
+}
+#[test]
+fn orthographic_matches_perspective_scale(){
+    let mut camera = Camera::new(EXTENT);
+    camera.transition_time = 0.0;
+    let point = camera.get_center() + Vector3::new(0.3, -0.3, 0.2);
+    let point = point - (camera.get_center() - camera.get_eye()).normalize() * (point - camera.get_center()).dot((camera.get_center() - camera.get_eye()).normalize());
+    let perspective = project(&camera, point);
+    camera.set_projection_mode(Projection::Orthographic);
+    assert!(!camera.is_transitioning());
+    let orthographic = project(&camera, point);
+    assert!((perspective.0 - orthographic.0).abs() < 1e-4 && (perspective.1 - orthographic.1).abs() < 1e-4);
+    assert_eq!(camera.get_projection()[3][3], 1.0);
+    camera.dolly(2.0, None);
+    let zoomed = project(&camera, point);
+    assert!(zoomed.0.abs() > orthographic.0.abs());
+}
+#[test]
+fn projection_transition_keeps_center_plane(){
+    let mut camera = Camera::new(EXTENT);
+    let forward = (camera.get_center() - camera.get_eye()).normalize();
+    let point = camera.get_center() + forward.cross(Vector3::new(0.0, 0.0, 1.0)).normalize() * 0.4;
+    let start = project(&camera, point);
+    camera.set_projection_mode(Projection::Orthographic);
+    for _ in 0..3{
+        camera.advance(camera.transition_time / 4.0);
+        assert!(camera.is_transitioning());
+        let current = project(&camera, point);
+        assert!((current.0 - start.0).abs() < 1e-3 && (current.1 - start.1).abs() < 1e-3, "{:?} {:?}", current, start);
+    }
+    camera.advance(camera.transition_time);
+    assert!(!camera.is_transitioning());
+    assert_eq!(camera.get_projection_mode(), Projection::Orthographic);
+    let end = project(&camera, point);
+    assert!((end.0 - start.0).abs() < 1e-3 && (end.1 - start.1).abs() < 1e-3);
+}
+#[test]
+fn view_presets(){
+    let mut camera = Camera::new(EXTENT);
+    let expected = [
+        (ViewPreset::Front, Vector3::new(0.0, -1.0, 0.0)),
+        (ViewPreset::Back, Vector3::new(0.0, 1.0, 0.0)),
+        (ViewPreset::Right, Vector3::new(1.0, 0.0, 0.0)),
+        (ViewPreset::Left, Vector3::new(-1.0, 0.0, 0.0)),
+        (ViewPreset::Top, Vector3::new(0.0, 0.0, 1.0)),
+        (ViewPreset::Bottom, Vector3::new(0.0, 0.0, -1.0)),
+        (ViewPreset::Isometric, Vector3::new(1.0, 1.0, 1.0).normalize()),
+    ];
+    for (preset, direction) in expected{
+        let distance = camera.get_distance();
+        camera.set_view_preset(preset);
+        camera.finish_transition();
+        let offset = camera.get_eye() - camera.get_center();
+        assert!((offset.magnitude() - distance).abs() < 1e-4);
+        assert!(offset.normalize().dot(direction) > 0.999, "{:?}", preset);
+    }
+}
+#[test]
+fn preset_transition_is_animated(){
+    let mut camera = Camera::new(EXTENT);
+    camera.look_at(Point3::new(0.0, -2.0, 0.0), Point3::new(0.0, 0.0, 0.0));
+    camera.set_view_preset(ViewPreset::Right);
+    camera.advance(camera.transition_time / 2.0);
+    let offset = (camera.get_eye() - camera.get_center()).normalize();
+    assert!((offset.dot(Vector3::new(1.0, -1.0, 0.0).normalize()) - 1.0).abs() < 1e-4);
+    camera.orbit((1.0, 0.0));
+    assert!(!camera.is_transitioning());
+}
+#[test]
+fn standard_depth_range(){
+    let mut camera = Camera::new(EXTENT);
+    camera.set_clip_planes(0.5, 20.0);
+    let forward = (camera.get_center() - camera.get_eye()).normalize();
+    assert!((depth(&camera, camera.get_eye() + forward * 0.5) - 0.0).abs() < 1e-4);
+    assert!((depth(&camera, camera.get_eye() + forward * 20.0) - 1.0).abs() < 1e-4);
+}
+#[test]
+fn reverse_z_depth_range(){
+    let mut camera = Camera::new(EXTENT);
+    camera.set_reverse_z(true);
+    camera.set_clip_planes(0.5, 20.0);
+    let forward = (camera.get_center() - camera.get_eye()).normalize();
+    assert!((depth(&camera, camera.get_eye() + forward * 0.5) - 1.0).abs() < 1e-4);
+    let far = depth(&camera, camera.get_eye() + forward * 1000.0);
+    assert!(far > 0.0 && far < 1e-3);
+    assert!(depth(&camera, camera.get_eye() + forward * 2.0) > depth(&camera, camera.get_eye() + forward * 3.0));
+    camera.set_projection_mode(Projection::Orthographic);
+    camera.finish_transition();
+    assert!((depth(&camera, camera.get_eye() + forward * 0.5) - 1.0).abs() < 1e-4);
+    assert!(depth(&camera, camera.get_eye() + forward * 20.0).abs() < 1e-4);
+}
+#[test]
+fn fit_clip_planes_to_bounds(){
+    let mut camera = Camera::new(EXTENT);
+    let bounds = BoundingBox{min:Point3::new(-20.0, -20.0, -1.0), max:Point3::new(-10.0, -10.0, 1.0)};
+    camera.fit_clip_planes(&bounds);
+    let (near, far) = camera.get_clip_planes();
+    for corner in bounds.get_corners(){
+        let depth = depth(&camera, corner);
+        assert!((0.0..=1.0).contains(&depth), "{:?} {} {} {}", corner, depth, near, far);
+    }
+    assert!(far > 10.0 && near > 0.1);
+    camera.fit_clip_planes(&BoundingBox{min:Point3::new(-1.0, -1.0, -1.0), max:Point3::new(1.0, 1.0, 1.0)});
+    let (near, far) = camera.get_clip_planes();
+    assert!(near > 0.0 && near < far);
+    camera.fit_clip_planes(&BoundingBox{min:Point3::new(5.0, 5.0, 5.0), max:Point3::new(6.0, 6.0, 6.0)});
+    assert_eq!(camera.get_clip_planes(), (0.1, 10.0));
+}
+#[test]
+fn bounding_box_transform(){
+    let bounds = BoundingBox::from_points([Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 2.0, 3.0)]).unwrap();
+    let moved = bounds.transform(&(Matrix4::from_translation(Vector3::new(1.0, 0.0, 0.0)) * Matrix4::from_scale(2.0)));
+    assert_eq!(moved, BoundingBox{min:Point3::new(1.0, 0.0, 0.0), max:Point3::new(3.0, 4.0, 6.0)});
+    let union = bounds.union(&moved);
+    assert_eq!(union, BoundingBox{min:Point3::new(0.0, 0.0, 0.0), max:Point3::new(3.0, 4.0, 6.0)});
+    assert!(BoundingBox::from_points([]).is_none());
+}
+#[test]
+fn frame_bounds_fits_viewport(){
+    let bounds = BoundingBox{min:Point3::new(10.0, -2.0, 0.0), max:Point3::new(14.0, 2.0, 3.0)};
+    for projection in [Projection::Perspective, Projection::Orthographic]{
+        let mut camera = Camera::new(EXTENT);
+        camera.set_projection_mode(projection);
+        camera.finish_transition();
+        camera.frame_bounds(&bounds);
+        camera.finish_transition();
+        assert_eq!(camera.get_center(), Point3::new(12.0, 0.0, 1.5));
+        camera.fit_clip_planes(&bounds);
+        let mut extent : (f32, f32) = (0.0, 0.0);
+        for corner in bounds.get_corners(){
+            let (x, y) = project(&camera, corner);
+            assert!(x.abs() < 1.0 && y.abs() < 1.0, "{:?} {:?}", projection, (x, y));
+            extent = (extent.0.max(x.abs()), extent.1.max(y.abs()));
+        }
+        assert!(extent.0 > 0.5 || extent.1 > 0.5, "{:?} {:?}", projection, extent);
+    }
+}
+#[test]
+fn frame_bounds_keeps_direction(){
+    let mut camera = Camera::new(EXTENT);
+    let direction = (camera.get_eye() - camera.get_center()).normalize();
+    camera.frame_bounds(&BoundingBox{min:Point3::new(-50.0, -50.0, -50.0), max:Point3::new(50.0, 50.0, 50.0)});
+    camera.finish_transition();
+    assert!((camera.get_eye() - camera.get_center()).normalize().dot(direction) > 0.9999);
+    assert!(camera.get_distance() > 86.0);
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use renderer::config::{RendererConfig, PresentMode, DeviceSelection, MIN_FRAME_ARENA_SIZE};
+
+fn write_config(name : &str, contents : &str) -> PathBuf{
+    let path = std::env::temp_dir().join(format!("drawr-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).expect("Failed to write config");
+    return path;
+}
+
+#[test]
+fn load_toml(){
+    let path = write_config("config.toml", "window_title = \"Viewer\"\npresent_modes = [\"Fifo\"]\ndevice = { Name = \"llvmpipe\" }\n");
+    let config = RendererConfig::load(&path).expect("Failed to load config");
+    std::fs::remove_file(&path).ok();
+    assert_eq!(config.window_title, "Viewer");
+    assert_eq!(config.present_modes, vec!(PresentMode::Fifo));
+    assert_eq!(config.device, DeviceSelection::Name(String::from("llvmpipe")));
+    assert_eq!(config.max_frames_in_flight, RendererConfig::default().max_frames_in_flight);
+}
+#[test]
+fn load_ron(){
+    let path = write_config("config.ron", "(window_size : (1280, 720), max_frames_in_flight : 3, device : Index(1), validation : true)");
+    let config = RendererConfig::load(&path).expect("Failed to load config");
+    std::fs::remove_file(&path).ok();
+    assert_eq!(config.window_size, (1280, 720));
+    assert_eq!(config.max_frames_in_flight, 3);
+    assert_eq!(config.device, DeviceSelection::Index(1));
+    assert!(config.validation);
+}
+#[test]
+fn rejects_invalid_config(){
+    assert!(RendererConfig::builder().max_frames_in_flight(0).build().is_err());
+    assert!(RendererConfig::builder().window_size(0, 600).build().is_err());
+    assert!(RendererConfig::builder().present_modes(vec!()).build().is_err());
+    assert!(RendererConfig::builder().frame_arena_size(0).build().is_err());
+    assert!(RendererConfig::builder().frame_arena_size(MIN_FRAME_ARENA_SIZE - 1).build().is_err());
+    let path = write_config("invalid.toml", "surface_formats = []\n");
+    assert!(RendererConfig::load(&path).is_err());
+    std::fs::remove_file(&path).ok();
+}
+#[test]
+fn reject_invalid_camera_zoom(){
+    let mut config = RendererConfig::default();
+    config.camera_sensitivity.zoom = 1.0;
+    assert!(config.validate().is_err());
+}
+#[test]
+fn reject_invalid_gizmo_snap(){
+    let mut config = RendererConfig::default();
+    config.gizmo_snap.rotate = 0.0;
+    assert!(config.validate().is_err());
+}
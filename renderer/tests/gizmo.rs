@@ -0,0 +1,102 @@
+use cgmath::{Matrix4, Point3, Vector3, Deg, Transform, MetricSpace, SquareMatrix};
+
+use renderer::{config::GizmoSnap, math::{BoundingBox, ModelMatrix, InstanceVertex, AXIS_COLORS, ray::Ray, gizmo::{Gizmo, GizmoMode, GizmoSpace, GizmoAxis}}};
+
+fn down(x : f32, y : f32) -> Ray{
+    return Ray::new(Point3::new(x, y, 10.0), Vector3::new(0.0, 0.0, -1.0));
+}
+fn assert_point_eq(actual : Point3<f32>, expected : Point3<f32>){
+    assert!(actual.distance(expected) < 1e-4, "{:?} != {:?}", actual, expected);
+}
+fn bounds() -> BoundingBox{
+    return InstanceVertex::get_initial_bounds();
+}
+
+#[test]
+fn hit_test_translate_axes(){
+    let gizmo = Gizmo::new(GizmoSnap::default());
+    let identity = Matrix4::identity();
+    assert_eq!(gizmo.hit_test(&down(1.0, 0.0), &identity, &bounds()), Some(GizmoAxis::X));
+    assert_eq!(gizmo.hit_test(&down(0.0, 1.0), &identity, &bounds()), Some(GizmoAxis::Y));
+    assert_eq!(gizmo.hit_test(&down(5.0, 5.0), &identity, &bounds()), None);
+    assert_eq!(gizmo.hit_test(&down(-1.0, 0.0), &identity, &bounds()), None);
+    let side = Ray::new(Point3::new(10.0, 0.0, 1.0), Vector3::new(-1.0, 0.0, 0.0));
+    assert_eq!(gizmo.hit_test(&side, &identity, &bounds()), Some(GizmoAxis::Z));
+}
+#[test]
+fn translate_drag_with_snapping(){
+    let mut gizmo = Gizmo::new(GizmoSnap::default());
+    let instance = ModelMatrix{matrix:Matrix4::identity()};
+    assert_eq!(gizmo.begin_drag(&down(1.0, 0.0), &instance, &bounds()), Some(GizmoAxis::X));
+    assert!(gizmo.is_dragging());
+    let moved = gizmo.drag(&down(1.6, 0.3)).expect("Drag should produce a matrix");
+    assert_point_eq(moved.matrix.transform_point(Point3::new(0.0, 0.0, 0.0)), Point3::new(0.6, 0.0, 0.0));
+    gizmo.snapping = true;
+    let snapped = gizmo.drag(&down(1.6, 0.3)).expect("Drag should produce a matrix");
+    assert_point_eq(snapped.matrix.transform_point(Point3::new(0.0, 0.0, 0.0)), Point3::new(0.5, 0.0, 0.0));
+    assert!(gizmo.end_drag());
+    assert!(!gizmo.end_drag());
+    assert!(gizmo.drag(&down(1.6, 0.3)).is_none());
+}
+#[test]
+fn rotate_drag_about_ring_axis(){
+    let mut gizmo = Gizmo::new(GizmoSnap::default());
+    gizmo.mode = GizmoMode::Rotate;
+    let instance = ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(1.0, 1.0, 0.0))};
+    let size = Gizmo::get_size(&instance.matrix, &bounds());
+    assert_eq!(gizmo.begin_drag(&down(1.0 + size, 1.0), &instance, &bounds()), Some(GizmoAxis::Z));
+    let rotated = gizmo.drag(&down(1.0, 1.0 + size)).expect("Drag should produce a matrix");
+    assert_point_eq(rotated.matrix.transform_point(Point3::new(0.0, 0.0, 0.0)), Point3::new(1.0, 1.0, 0.0));
+    assert_point_eq(rotated.matrix.transform_point(Point3::new(1.0, 0.0, 0.0)), Point3::new(1.0, 2.0, 0.0));
+    gizmo.snapping = true;
+    let (sin, cos) = Deg(50.0f32).0.to_radians().sin_cos();
+    let snapped = gizmo.drag(&down(1.0 + cos, 1.0 + sin)).expect("Drag should produce a matrix");
+    let expected = Matrix4::from_translation(Vector3::new(1.0, 1.0, 0.0)) * Matrix4::from_angle_z(Deg(45.0));
+    assert_point_eq(snapped.matrix.transform_point(Point3::new(1.0, 0.0, 0.0)), expected.transform_point(Point3::new(1.0, 0.0, 0.0)));
+}
+#[test]
+fn scale_drag_in_local_and_world_space(){
+    let instance = ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(2.0, 0.0, 0.0)) * Matrix4::from_angle_z(Deg(90.0))};
+    let size = Gizmo::get_size(&instance.matrix, &bounds());
+    let mut gizmo = Gizmo::new(GizmoSnap::default());
+    gizmo.mode = GizmoMode::Scale;
+    gizmo.space = GizmoSpace::Local;
+    assert_eq!(gizmo.begin_drag(&down(2.0, size * 0.5), &instance, &bounds()), Some(GizmoAxis::X));
+    let scaled = gizmo.drag(&down(2.0, size)).expect("Drag should produce a matrix");
+    assert_point_eq(scaled.matrix.transform_point(Point3::new(1.0, 0.0, 0.0)), Point3::new(2.0, 2.0, 0.0));
+    assert_point_eq(scaled.matrix.transform_point(Point3::new(0.0, 1.0, 0.0)), Point3::new(1.0, 0.0, 0.0));
+    gizmo.end_drag();
+    gizmo.space = GizmoSpace::World;
+    assert_eq!(gizmo.begin_drag(&down(2.0, size * 0.5), &instance, &bounds()), Some(GizmoAxis::Y));
+    let scaled = gizmo.drag(&down(2.0, size)).expect("Drag should produce a matrix");
+    assert_point_eq(scaled.matrix.transform_point(Point3::new(1.0, 0.0, 0.0)), Point3::new(2.0, 2.0, 0.0));
+    let collapsed = gizmo.drag(&down(2.0, -size)).expect("Drag should produce a matrix");
+    assert!(collapsed.matrix.transform_point(Point3::new(1.0, 0.0, 0.0)).y > 0.0);
+}
+#[test]
+fn local_axes_follow_instance_rotation(){
+    let matrix = Matrix4::from_angle_z(Deg(90.0)) * Matrix4::from_scale(3.0);
+    let mut gizmo = Gizmo::new(GizmoSnap::default());
+    assert_eq!(gizmo.get_axes(&matrix)[0], Vector3::unit_x());
+    gizmo.toggle_space();
+    assert_eq!(gizmo.space, GizmoSpace::Local);
+    let axes = gizmo.get_axes(&matrix);
+    assert_point_eq(Point3::new(0.0, 0.0, 0.0) + axes[0], Point3::new(0.0, 1.0, 0.0));
+    assert_point_eq(Point3::new(0.0, 0.0, 0.0) + axes[1], Point3::new(-1.0, 0.0, 0.0));
+}
+#[test]
+fn gizmo_vertices_use_axis_colors(){
+    let mut gizmo = Gizmo::new(GizmoSnap::default());
+    let identity = Matrix4::identity();
+    let vertices = gizmo.get_vertices(&identity, &bounds());
+    assert_eq!(vertices.len(), 30);
+    for (i, axis) in vertices.chunks(10).enumerate(){
+        assert!(axis.iter().all(|vertex|vertex.color == AXIS_COLORS[i]));
+    }
+    gizmo.mode = GizmoMode::Rotate;
+    assert_eq!(gizmo.get_vertices(&identity, &bounds()).len(), 288);
+    gizmo.mode = GizmoMode::Translate;
+    gizmo.begin_drag(&down(0.0, 1.0), &ModelMatrix{matrix:identity}, &bounds());
+    let vertices = gizmo.get_vertices(&identity, &bounds());
+    assert!(vertices[10..20].iter().all(|vertex|vertex.color != AXIS_COLORS[1]));
+}
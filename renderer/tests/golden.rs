@@ -0,0 +1,256 @@
+use std::{io::Write, path::{Path, PathBuf}};
+
+use cgmath::{Matrix4, Point3, Vector3};
+use renderer::{Renderer, RenderTask, config::RendererConfig, functions::capture::ImageData, math::{ModelMatrix, gizmo::GizmoMode}, scene::InstanceHandle, error::RendererResult};
+
+const WIDTH : u32 = 256;
+const HEIGHT : u32 = 256;
+const CHANNEL_TOLERANCE : u8 = 3;
+const MAX_MISMATCHED_PIXELS : usize = 16;
+
+fn golden_dir() -> PathBuf{
+    return Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+}
+fn output_dir() -> PathBuf{
+    return Path::new(env!("CARGO_MANIFEST_DIR")).join("../target/golden");
+}
+fn copy_shaders(){
+    let shader_dir = std::env::current_exe().unwrap().parent().unwrap().join("shaders");
+    std::fs::create_dir_all(&shader_dir).expect("Failed to create shader directory");
+    for entry in std::fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("src/shaders")).expect("Failed to read shader sources"){
+        let path = entry.expect("Failed to read shader source").path();
+        if path.extension().map_or(false, |extension|extension == "spv"){
+            std::fs::copy(&path, shader_dir.join(path.file_name().unwrap())).expect("Failed to copy shader");
+        }
+    }
+}
+fn create_renderer() -> Option<Renderer>{
+    return create_renderer_with_config(RendererConfig::builder().window_size(WIDTH, HEIGHT).pipeline_cache(None).build().expect("Invalid test config"));
+}
+fn create_renderer_with_config(config : RendererConfig) -> Option<Renderer>{
+    if !renderer::functions::device::is_vulkan_available(){
+        if std::env::var_os("GOLDEN_REQUIRE_VULKAN").is_some(){panic!("GOLDEN_REQUIRE_VULKAN is set but no Vulkan ICD is installed")}
+        writeln!(std::io::stderr(), "Skipping golden image test: no Vulkan ICD is installed").ok();
+        return None;
+    }
+    copy_shaders();
+    return Some(Renderer::new_headless(config).expect("Failed to start the render thread"));
+}
+fn load_png(path : &Path) -> Option<ImageData>{
+    let decoder = png::Decoder::new(std::fs::File::open(path).ok()?);
+    let mut reader = decoder.read_info().expect("Failed to read reference header");
+    let mut pixels = vec!(0;reader.output_buffer_size());
+    let info = reader.next_frame(&mut pixels).expect("Failed to decode reference image");
+    assert_eq!(info.color_type, png::ColorType::Rgba, "Reference images must be stored as RGBA8");
+    pixels.truncate(info.buffer_size());
+    return Some(ImageData{width:info.width,height:info.height,pixels});
+}
+fn compare(name : &str, actual : &ImageData){
+    let reference_path = golden_dir().join(format!("{}.png",name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some(){
+        std::fs::create_dir_all(golden_dir()).expect("Failed to create golden directory");
+        actual.save(&reference_path).expect("Failed to write reference image");
+        eprintln!("Wrote reference image {}, review it before committing", reference_path.display());
+        return;
+    }
+    let reference = match load_png(&reference_path){
+        Some(reference)=>{reference}
+        None=>{
+            std::fs::create_dir_all(output_dir()).expect("Failed to create output directory");
+            let actual_path = output_dir().join(format!("{}.actual.png",name));
+            actual.save(&actual_path).expect("Failed to write rendered image");
+            panic!("Missing reference image {}, see {} and rerun with UPDATE_GOLDEN=1 to accept it", reference_path.display(), actual_path.display());
+        }
+    };
+    assert_eq!((reference.width, reference.height), (actual.width, actual.height), "Reference image {} has a different size", name);
+    let mut diff = ImageData{width:actual.width,height:actual.height,pixels:vec!(0;actual.pixels.len())};
+    let mut mismatched = 0;
+    for ((expected, found), diff) in reference.pixels.chunks_exact(4).zip(actual.pixels.chunks_exact(4)).zip(diff.pixels.chunks_exact_mut(4)){
+        let matches = expected.iter().zip(found.iter()).all(|(&a,&b)|a.abs_diff(b) <= CHANNEL_TOLERANCE);
+        if matches{
+            diff.copy_from_slice(&[found[0]/4,found[1]/4,found[2]/4,255]);
+        }
+        else{
+            mismatched += 1;
+            diff.copy_from_slice(&[255,0,255,255]);
+        }
+    }
+    if mismatched > MAX_MISMATCHED_PIXELS{
+        std::fs::create_dir_all(output_dir()).expect("Failed to create output directory");
+        let actual_path = output_dir().join(format!("{}.actual.png",name));
+        let diff_path = output_dir().join(format!("{}.diff.png",name));
+        actual.save(&actual_path).expect("Failed to write rendered image");
+        diff.save(&diff_path).expect("Failed to write diff image");
+        panic!("{} pixels of {} differ from the reference, see {} and {}", mismatched, name, actual_path.display(), diff_path.display());
+    }
+}
+fn render(renderer : &Renderer, eye : Point3<f32>) -> RendererResult<ImageData>{
+    renderer.set_camera(eye, Point3::new(0.0, 0.0, 0.0))?;
+    renderer.send_task(RenderTask::Draw)?;
+    return renderer.read_pixels();
+}
+
+#[test]
+fn default_scene() -> RendererResult<()>{
+    let renderer = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    let image = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    renderer.close()?;
+    compare("default_scene", &image);
+    return Ok(());
+}
+#[test]
+fn default_scene_side_view() -> RendererResult<()>{
+    let renderer = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    let image = render(&renderer, Point3::new(3.0, 0.5, 0.5))?;
+    renderer.close()?;
+    compare("default_scene_side_view", &image);
+    return Ok(());
+}
+#[test]
+fn added_instances() -> RendererResult<()>{
+    let mut renderer = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    renderer.add_instance(ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(-1.0, 0.0, 0.0))})?;
+    let moved = renderer.add_instance(ModelMatrix{matrix:Matrix4::from_scale(2.0)})?;
+    renderer.update_instance(moved, ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(0.0, -1.0, 0.0))})?;
+    let image = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    renderer.close()?;
+    compare("added_instances", &image);
+    return Ok(());
+}
+#[test]
+fn incremental_instance_updates_match_fresh_upload() -> RendererResult<()>{
+    let mut updated = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    let first = updated.add_instance(ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(-1.0, 0.0, 0.0))})?;
+    let second = updated.add_instance(ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(1.0, 0.0, 0.0))})?;
+    for step in 0..8{
+        updated.update_instance(first, ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(-1.0, step as f32 * 0.25, 0.0))})?;
+        updated.send_task(RenderTask::Draw)?;
+    }
+    updated.update_instance(second, ModelMatrix{matrix:Matrix4::from_scale(0.5)})?;
+    let updated_image = render(&updated, Point3::new(2.0, 2.0, 2.0))?;
+    updated.close()?;
+    let mut fresh = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    fresh.add_instance(ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(-1.0, 1.75, 0.0))})?;
+    fresh.add_instance(ModelMatrix{matrix:Matrix4::from_scale(0.5)})?;
+    let fresh_image = render(&fresh, Point3::new(2.0, 2.0, 2.0))?;
+    fresh.close()?;
+    assert!(updated_image.pixels == fresh_image.pixels, "Instance matrices uploaded across frames differ from a single upload");
+    return Ok(());
+}
+#[test]
+fn reverse_z_matches_standard_depth() -> RendererResult<()>{
+    let renderer = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    let standard = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    renderer.close()?;
+    let renderer = match create_renderer_with_config(RendererConfig::builder().window_size(WIDTH, HEIGHT).pipeline_cache(None).reverse_z(true).build().expect("Invalid test config")){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    let reverse_z = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    renderer.close()?;
+    let mismatched = standard.pixels.chunks_exact(4).zip(reverse_z.pixels.chunks_exact(4)).filter(|(a, b)|a.iter().zip(b.iter()).any(|(&a,&b)|a.abs_diff(b) > CHANNEL_TOLERANCE)).count();
+    assert!(mismatched <= MAX_MISMATCHED_PIXELS, "{} pixels differ between standard and reverse-Z depth", mismatched);
+    return Ok(());
+}
+#[test]
+fn distant_instance_is_not_clipped() -> RendererResult<()>{
+    let mut renderer = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    let empty = render(&renderer, Point3::new(30.0, 30.0, 30.0))?;
+    renderer.add_instance(ModelMatrix{matrix:Matrix4::from_scale(8.0)})?;
+    let scaled = render(&renderer, Point3::new(30.0, 30.0, 30.0))?;
+    renderer.close()?;
+    assert_ne!(empty.pixels, scaled.pixels);
+    return Ok(());
+}
+#[test]
+fn frame_distant_instance() -> RendererResult<()>{
+    let mut renderer = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    let distant = renderer.add_instance(ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(0.0, 200.0, 0.0))})?;
+    renderer.set_camera(Point3::new(2.0, 2.0, 2.0), Point3::new(0.0, 0.0, 0.0))?;
+    renderer.send_task(RenderTask::Draw)?;
+    let unframed = renderer.read_pixels()?;
+    renderer.frame_instances(vec!(distant))?;
+    renderer.send_task(RenderTask::Draw)?;
+    let framed = renderer.read_pixels()?;
+    renderer.close()?;
+    assert_ne!(unframed.pixels, framed.pixels);
+    return Ok(());
+}
+#[test]
+fn id_buffer_picking_matches_ray_casting() -> RendererResult<()>{
+    let mut results = vec!();
+    for id_buffer in [false, true]{
+        let mut renderer = match create_renderer_with_config(RendererConfig::builder().window_size(WIDTH, HEIGHT).pipeline_cache(None).id_buffer(id_buffer).build().expect("Invalid test config")){Some(renderer)=>{renderer} None=>{return Ok(())}};
+        let moved = renderer.add_instance(ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(-1.0, 0.0, 0.0))})?;
+        let image = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+        let yellow = image.pixels.chunks_exact(4).position(|pixel|pixel[0] > 200 && pixel[1] > 200 && pixel[2] < 50).expect("Scene should contain yellow geometry");
+        let white = image.pixels.chunks_exact(4).position(|pixel|pixel.iter().all(|&channel|channel > 250)).expect("Scene should contain background");
+        let position = |index : usize|((index as u32 % WIDTH) as f32 + 0.5, (index as u32 / WIDTH) as f32 + 0.5);
+        let hit = renderer.pick(position(yellow))?.expect("Yellow pixel should resolve to an instance");
+        assert!(hit.handle == moved || hit.handle.0 == 0);
+        assert_eq!(renderer.pick(position(white))?, None);
+        renderer.close()?;
+        results.push(hit.handle);
+    }
+    assert_eq!(results[0], results[1]);
+    return Ok(());
+}
+#[test]
+fn selection_outline() -> RendererResult<()>{
+    let renderer = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    let unselected = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    renderer.set_selection(vec!(InstanceHandle(0)))?;
+    let selected = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    renderer.set_selection(vec!())?;
+    let deselected = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    renderer.close()?;
+    assert_ne!(unselected.pixels, selected.pixels);
+    assert_eq!(unselected.pixels, deselected.pixels);
+    let orange = selected.pixels.chunks_exact(4).filter(|pixel|pixel[0] > 200 && (100..200).contains(&pixel[1]) && pixel[2] < 50).count();
+    assert!(orange > 0, "Selected instance should have an outline");
+    return Ok(());
+}
+#[test]
+fn selection_draws_gizmo() -> RendererResult<()>{
+    let renderer = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    renderer.set_selection(vec!(InstanceHandle(0)))?;
+    let translate = render(&renderer, Point3::new(3.0, 3.0, 3.0))?;
+    renderer.set_gizmo_mode(GizmoMode::Rotate)?;
+    let rotate = render(&renderer, Point3::new(3.0, 3.0, 3.0))?;
+    renderer.close()?;
+    assert_ne!(translate.pixels, rotate.pixels);
+    let green = translate.pixels.chunks_exact(4).filter(|pixel|pixel[1] > 150 && pixel[0] < 150 && pixel[2] < 150).count();
+    assert!(green > 0, "Gizmo should draw the Y axis handle");
+    return Ok(());
+}
+#[test]
+fn loaded_mesh_replaces_instance_geometry() -> RendererResult<()>{
+    let renderer = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    let path = std::env::temp_dir().join(format!("drawr-{}-plane.obj", std::process::id()));
+    std::fs::write(&path, "v -1 -1 0\nv 1 -1 0\nv 1 1 0\nv -1 1 0\nf 1 2 3 4\n")?;
+    let default = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    let mesh = renderer.load_mesh(path.clone());
+    std::fs::remove_file(&path).ok();
+    renderer.set_instance_mesh(InstanceHandle(0), mesh?)?;
+    let loaded = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    assert!(renderer.load_mesh(path).is_err());
+    renderer.close()?;
+    assert_ne!(default.pixels, loaded.pixels);
+    return Ok(());
+}
+#[test]
+fn vertex_and_material_colors_are_drawn() -> RendererResult<()>{
+    let renderer = match create_renderer(){Some(renderer)=>{renderer} None=>{return Ok(())}};
+    let directory = std::env::temp_dir().join(format!("drawr-{}-colors", std::process::id()));
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(directory.join("blue.mtl"), "newmtl blue\nKd 0 0 1\n")?;
+    std::fs::write(directory.join("plane.obj"), "mtllib blue.mtl\nusemtl blue\nv -1 -1 0\nv 1 -1 0\nv 1 1 0\nv -1 1 0\nf 1 2 3 4\n")?;
+    let default = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    let mesh = renderer.load_mesh(directory.join("plane.obj"));
+    std::fs::remove_dir_all(&directory).ok();
+    renderer.set_instance_mesh(InstanceHandle(0), mesh?)?;
+    let loaded = render(&renderer, Point3::new(2.0, 2.0, 2.0))?;
+    renderer.close()?;
+    let yellow = default.pixels.chunks_exact(4).filter(|pixel|pixel[0] > 200 && pixel[1] > 200 && pixel[2] < 50).count();
+    assert!(yellow > 0, "Default instance should use its yellow vertex color");
+    let blue = loaded.pixels.chunks_exact(4).filter(|pixel|pixel[0] < 50 && pixel[1] < 50 && pixel[2] > 200).count();
+    assert!(blue > 0, "Loaded mesh should use its material color");
+    return Ok(());
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use renderer::{mesh::obj::{parse_obj, parse_mtl}, error::RendererError};
+
+const CUBE_FACE : &str = "
+# quad split into two triangles
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+";
+
+fn no_materials(library : &str) -> Result<String, RendererError>{
+    panic!("Unexpected material library {}", library);
+}
+
+#[test]
+fn triangulates_polygons(){
+    let mesh = parse_obj(CUBE_FACE, no_materials).expect("Valid OBJ");
+    assert_eq!(mesh.vertices.len(), 4);
+    assert_eq!(mesh.indices, vec!(0, 1, 2, 0, 2, 3));
+    assert_eq!(mesh.uvs[2], [1.0, 1.0]);
+    assert!(mesh.normals.iter().all(|&normal|normal == [0.0, 0.0, 1.0]));
+    assert_eq!(mesh.groups.len(), 1);
+    assert_eq!(mesh.groups[0].index_count, 6);
+    let bounds = mesh.get_bounds().expect("Mesh has vertices");
+    assert_eq!(bounds.max.x, 1.0);
+    assert_eq!(mesh.get_triangles().len(), 6);
+}
+#[test]
+fn shares_identical_corners_and_resolves_negative_indices(){
+    let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 1 1 0\nf 2 4 3\n";
+    let mesh = parse_obj(source, no_materials).expect("Valid OBJ");
+    assert_eq!(mesh.vertices.len(), 4);
+    assert_eq!(mesh.indices, vec!(0, 1, 2, 1, 3, 2));
+    for normal in mesh.normals.iter(){
+        assert!((normal[2] - 1.0).abs() < 1e-5, "Generated normal {:?} should face +Z", normal);
+    }
+}
+#[test]
+fn splits_groups_and_applies_material_colors(){
+    let source = "mtllib scene.mtl\no first\nusemtl red\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\ng second part\nusemtl blue\nf 1 3 2\nusemtl missing\nf 3 2 1\n";
+    let mut requested = vec!();
+    let mesh = parse_obj(source, |library|{
+        requested.push(String::from(library));
+        return Ok(String::from("newmtl red\nKd 1 0 0\n\nnewmtl blue\nKd 0 0 1\n"));
+    }).expect("Valid OBJ");
+    assert_eq!(requested, vec!(String::from("scene.mtl")));
+    let names : Vec<(&str, Option<&str>, u32, u32)> = mesh.groups.iter().map(|group|(group.name.as_str(), group.material.as_deref(), group.first_index, group.index_count)).collect();
+    assert_eq!(names, vec!(("first", Some("red"), 0, 3), ("second part", Some("blue"), 3, 3), ("second part", Some("missing"), 6, 3)));
+    assert_eq!(mesh.vertices[mesh.indices[0] as usize].color, [1.0, 0.0, 0.0]);
+    assert_eq!(mesh.vertices[mesh.indices[3] as usize].color, [0.0, 0.0, 1.0]);
+    assert_eq!(mesh.vertices[mesh.indices[6] as usize].color, [1.0, 1.0, 1.0]);
+    assert_eq!(mesh.vertices.len(), 9);
+}
+#[test]
+fn missing_material_library_uses_default_color(){
+    let source = "mtllib missing.mtl\nusemtl red\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+    let mesh = parse_obj(source, |_|Err(RendererError::Io(std::io::Error::from(std::io::ErrorKind::NotFound)))).expect("Missing libraries should not fail the load");
+    assert!(mesh.vertices.iter().all(|vertex|vertex.color == [1.0, 1.0, 1.0]));
+    assert_eq!(mesh.groups[0].material.as_deref(), Some("red"));
+    assert_eq!(mesh.warnings.len(), 1);
+    assert!(mesh.warnings[0].contains("missing.mtl"));
+}
+#[test]
+fn malformed_material_library_uses_default_color(){
+    let source = "mtllib broken.mtl\nusemtl red\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+    let mesh = parse_obj(source, |_|Ok(String::from("Kd 1 0 0\n"))).expect("Malformed libraries should not fail the load");
+    assert!(mesh.vertices.iter().all(|vertex|vertex.color == [1.0, 1.0, 1.0]));
+    assert_eq!(mesh.warnings.len(), 1);
+    assert!(mesh.warnings[0].contains("broken.mtl"));
+}
+#[test]
+fn vertex_colors_are_used_without_materials(){
+    let mesh = parse_obj("v 0 0 0 0 1 0\nv 1 0 0 0 1 0\nv 0 1 0 0 1 0\nf 1 2 3\n", no_materials).expect("Valid OBJ");
+    assert!(mesh.vertices.iter().all(|vertex|vertex.color == [0.0, 1.0, 0.0]));
+}
+#[test]
+fn parses_material_library(){
+    let materials = parse_mtl("# comment\nnewmtl paint\nNs 10\nKd 0.5 0.25 1\n").expect("Valid MTL");
+    assert_eq!(materials, HashMap::from([(String::from("paint"), [0.5, 0.25, 1.0])]));
+    assert!(parse_mtl("Kd 1 1 1\n").is_err());
+}
+#[test]
+fn rejects_invalid_obj(){
+    match parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 5\n", no_materials){
+        Err(RendererError::MeshParse(line, _))=>{assert_eq!(line, 3)}
+        _=>{panic!("Out of range index should fail")}
+    }
+    assert!(parse_obj("v 0 0\n", no_materials).is_err());
+    assert!(parse_obj("v 0 0 0\nv 1 0 0\nf 1 2\n", no_materials).is_err());
+    assert!(parse_obj("v 0 0 0\n", no_materials).is_err());
+    assert!(parse_obj("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1/x 2 3\n", no_materials).is_err());
+}
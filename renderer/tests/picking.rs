@@ -0,0 +1,123 @@
+use ash::vk::Extent2D;
+use cgmath::{Matrix4, Point3, Vector3, InnerSpace, MetricSpace};
+
+use renderer::{math::{BoundingBox, ModelMatrix, ray::Ray, camera::{Camera, Projection}}, scene::{Scene, InstanceHandle}, mesh::{MeshHandle, DEFAULT_MESH}};
+
+const EXTENT : Extent2D = Extent2D{width:800,height:600};
+const QUAD : [[f32;3]; 6] = [
+    [0.0,0.0,0.0], [1.0,0.0,0.0], [1.0,1.0,0.0],
+    [0.0,0.0,0.0], [1.0,1.0,0.0], [0.0,1.0,0.0],
+];
+
+#[test]
+fn ray_triangle(){
+    let ray = Ray::new(Point3::new(0.25, 0.25, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    let a = Point3::new(0.0, 0.0, 0.0);
+    let b = Point3::new(1.0, 0.0, 0.0);
+    let c = Point3::new(0.0, 1.0, 0.0);
+    assert_eq!(ray.intersect_triangle(a, b, c), Some(5.0));
+    assert_eq!(ray.intersect_triangle(a, c, b), Some(5.0));
+    let outside = Ray::new(Point3::new(0.75, 0.75, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    assert_eq!(outside.intersect_triangle(a, b, c), None);
+    let behind = Ray::new(Point3::new(0.25, 0.25, 5.0), Vector3::new(0.0, 0.0, 1.0));
+    assert_eq!(behind.intersect_triangle(a, b, c), None);
+    let parallel = Ray::new(Point3::new(-1.0, 0.25, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(parallel.intersect_triangle(a, b, c), None);
+}
+#[test]
+fn ray_plane_and_line(){
+    let ray = Ray::new(Point3::new(1.0, 2.0, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    assert_eq!(ray.intersect_plane(Point3::new(0.0, 0.0, 1.0), Vector3::new(0.0, 0.0, 1.0)), Some(4.0));
+    assert_eq!(ray.intersect_plane(Point3::new(0.0, 0.0, 6.0), Vector3::new(0.0, 0.0, 1.0)), None);
+    assert_eq!(ray.intersect_plane(Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0)), None);
+    let (distance, position) = ray.closest_to_line(Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0)).expect("Lines are not parallel");
+    assert!((distance - 5.0).abs() < 1e-5);
+    assert!((position - 1.0).abs() < 1e-5);
+    assert_eq!(ray.closest_to_line(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)), None);
+}
+#[test]
+fn ray_bounds(){
+    let bounds = BoundingBox{min:Point3::new(-1.0, -1.0, -1.0), max:Point3::new(1.0, 1.0, 1.0)};
+    let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(ray.intersect_bounds(&bounds), Some(4.0));
+    let inside = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+    assert_eq!(inside.intersect_bounds(&bounds), Some(0.0));
+    let miss = Ray::new(Point3::new(-5.0, 2.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(miss.intersect_bounds(&bounds), None);
+    let away = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+    assert_eq!(away.intersect_bounds(&bounds), None);
+    let diagonal = Ray::new(Point3::new(-5.0, -5.0, 0.0), Vector3::new(1.0, 1.0, 0.0));
+    assert!((diagonal.intersect_bounds(&bounds).unwrap() - 4.0 * std::f32::consts::SQRT_2).abs() < 1e-5);
+}
+#[test]
+fn scene_pick_closest_instance(){
+    let mut scene = Scene::new(vec!(ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(0.0, 0.0, -2.0))}));
+    scene.add_instance(InstanceHandle(7), ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(0.0, 0.0, 1.0))});
+    scene.add_instance(InstanceHandle(8), ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(5.0, 0.0, 3.0))});
+    let ray = Ray::new(Point3::new(0.5, 0.5, 10.0), Vector3::new(0.0, 0.0, -1.0));
+    let picked = scene.pick(&ray, &QUAD).expect("Ray should hit an instance");
+    assert_eq!(picked.handle, InstanceHandle(7));
+    assert!(picked.point.distance(Point3::new(0.5, 0.5, 1.0)) < 1e-5);
+    scene.remove_instance(InstanceHandle(7));
+    assert_eq!(scene.pick(&ray, &QUAD).map(|picked|picked.handle), Some(InstanceHandle(0)));
+    let miss = Ray::new(Point3::new(3.0, 3.0, 10.0), Vector3::new(0.0, 0.0, -1.0));
+    assert_eq!(scene.pick(&miss, &QUAD), None);
+}
+#[test]
+fn screen_ray_through_center(){
+    for projection in [Projection::Perspective, Projection::Orthographic]{
+        let mut camera = Camera::new(EXTENT);
+        camera.set_projection_mode(projection);
+        camera.finish_transition();
+        let ray = camera.screen_ray((EXTENT.width as f32 / 2.0, EXTENT.height as f32 / 2.0));
+        let expected = (camera.get_center() - camera.get_eye()).normalize();
+        assert!(ray.direction.dot(expected) > 0.9999, "{:?}", projection);
+        let distance = (camera.get_center() - ray.origin).dot(ray.direction);
+        assert!(ray.at(distance).distance(camera.get_center()) < 1e-3, "{:?}", projection);
+    }
+}
+#[test]
+fn screen_ray_picks_instance_under_cursor(){
+    for reverse_z in [false, true]{
+        let mut camera = Camera::new(EXTENT);
+        camera.set_reverse_z(reverse_z);
+        camera.look_at(Point3::new(0.5, -0.5, 4.0), Point3::new(0.5, 0.5, 0.0));
+        let scene = Scene::new(ModelMatrix::get_default());
+        let center = camera.screen_ray((400.0, 300.0));
+        let picked = scene.pick(&center, &QUAD).expect("Cursor is over the quad");
+        assert_eq!(picked.handle, InstanceHandle(0));
+        assert!(picked.point.distance(Point3::new(0.5, 0.5, 0.0)) < 1e-3);
+        assert_eq!(scene.pick(&camera.screen_ray((0.0, 0.0)), &QUAD), None);
+    }
+}
+#[test]
+fn pick_known_instance(){
+    let mut scene = Scene::new(ModelMatrix::get_default());
+    scene.add_instance(InstanceHandle(3), ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(0.0, 0.0, 2.0))});
+    let ray = Ray::new(Point3::new(0.5, 0.5, 10.0), Vector3::new(0.0, 0.0, -1.0));
+    let picked = scene.pick_instance(&ray, &QUAD, InstanceHandle(0));
+    assert_eq!(picked.handle, InstanceHandle(0));
+    assert!(picked.point.distance(Point3::new(0.5, 0.5, 0.0)) < 1e-5);
+    let edge = Ray::new(Point3::new(1.0001, 0.5, 10.0), Vector3::new(0.0, 0.0, -1.0));
+    assert_eq!(scene.pick_instance(&edge, &QUAD, InstanceHandle(3)).handle, InstanceHandle(3));
+}
+#[test]
+fn instances_are_batched_by_mesh(){
+    let mut scene = Scene::new(ModelMatrix::get_default());
+    let far = Matrix4::from_translation(Vector3::new(10.0, 0.0, 0.0));
+    scene.add_instance(InstanceHandle(1), ModelMatrix{matrix:far});
+    scene.add_instance(InstanceHandle(2), ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(20.0, 0.0, 0.0))});
+    scene.add_mesh(MeshHandle(1), vec!([0.0,0.0,0.0], [4.0,0.0,0.0], [0.0,4.0,0.0]));
+    assert!(!scene.set_instance_mesh(InstanceHandle(0), MeshHandle(7)));
+    assert!(scene.set_instance_mesh(InstanceHandle(0), MeshHandle(1)));
+    scene.add_instance(InstanceHandle(3), ModelMatrix{matrix:far});
+    assert_eq!(scene.get_mesh_batches(), vec!((DEFAULT_MESH, 0, 3), (MeshHandle(1), 3, 1)));
+    assert_eq!(scene.get_handle(3), Some(InstanceHandle(0)));
+    assert_eq!(scene.get_instance_mesh(InstanceHandle(0)), Some(MeshHandle(1)));
+    scene.set_selection(vec!(InstanceHandle(0), InstanceHandle(3)));
+    assert_eq!(scene.get_selection_batches(), vec!((MeshHandle(1), 0, 1), (DEFAULT_MESH, 1, 1)));
+    let bounds = scene.get_bounds(&BoundingBox{min:Point3::new(0.0, 0.0, 0.0), max:Point3::new(1.0, 1.0, 0.0)}).expect("Scene has instances");
+    assert_eq!(bounds.max, Point3::new(21.0, 4.0, 0.0));
+    let ray = Ray::new(Point3::new(3.0, 0.5, 5.0), Vector3::new(0.0, 0.0, -1.0));
+    assert_eq!(scene.pick(&ray, &QUAD).map(|picked|picked.handle), Some(InstanceHandle(0)));
+}
@@ -0,0 +1,56 @@
+use cgmath::{Matrix4, Point3, Vector3, Transform, MetricSpace};
+
+use renderer::{math::{BoundingBox, ModelMatrix}, scene::{Scene, InstanceHandle}};
+
+fn scene() -> Scene{
+    let mut scene = Scene::new(ModelMatrix::get_default());
+    scene.add_instance(InstanceHandle(1), ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(2.0, 0.0, 0.0))});
+    scene.add_instance(InstanceHandle(2), ModelMatrix{matrix:Matrix4::from_translation(Vector3::new(4.0, 0.0, 0.0))});
+    scene.dirty = false;
+    return scene;
+}
+
+#[test]
+fn select_replaces_and_extends(){
+    let mut scene = scene();
+    scene.select(Some(InstanceHandle(1)), false);
+    assert_eq!(scene.get_selection(), &[InstanceHandle(1)]);
+    assert!(scene.dirty);
+    scene.select(Some(InstanceHandle(2)), true);
+    assert_eq!(scene.get_selection(), &[InstanceHandle(1), InstanceHandle(2)]);
+    scene.select(Some(InstanceHandle(1)), true);
+    assert_eq!(scene.get_selection(), &[InstanceHandle(2)]);
+    scene.select(Some(InstanceHandle(0)), false);
+    assert_eq!(scene.get_selection(), &[InstanceHandle(0)]);
+    scene.select(None, true);
+    assert_eq!(scene.get_selection(), &[InstanceHandle(0)]);
+    scene.select(None, false);
+    assert!(scene.get_selection().is_empty());
+}
+#[test]
+fn selection_ignores_unknown_and_removed_instances(){
+    let mut scene = scene();
+    scene.set_selection(vec!(InstanceHandle(2), InstanceHandle(7), InstanceHandle(2), InstanceHandle(1)));
+    assert_eq!(scene.get_selection(), &[InstanceHandle(2), InstanceHandle(1)]);
+    scene.dirty = false;
+    scene.set_selection(vec!(InstanceHandle(2), InstanceHandle(1)));
+    assert!(!scene.dirty);
+    scene.remove_instance(InstanceHandle(2));
+    assert!(!scene.is_selected(InstanceHandle(2)));
+    assert!(scene.is_selected(InstanceHandle(1)));
+}
+#[test]
+fn selection_matrices_scale_about_mesh_center(){
+    let mut scene = scene();
+    scene.set_selection(vec!(InstanceHandle(1)));
+    let bounds = BoundingBox{min:Point3::new(0.0, 0.0, 0.0), max:Point3::new(1.0, 1.0, 0.0)};
+    let matrices = scene.get_selection_matrices(&bounds, 2.0);
+    assert_eq!(matrices.len(), 2);
+    assert_eq!(matrices[0].matrix, Matrix4::from_translation(Vector3::new(2.0, 0.0, 0.0)));
+    let center = matrices[1].matrix.transform_point(Point3::new(0.5, 0.5, 0.0));
+    assert!(center.distance(Point3::new(2.5, 0.5, 0.0)) < 1e-5);
+    let corner = matrices[1].matrix.transform_point(Point3::new(1.0, 1.0, 0.0));
+    assert!(corner.distance(Point3::new(3.5, 1.5, 0.0)) < 1e-5);
+    scene.set_selection(vec!());
+    assert!(scene.get_selection_matrices(&bounds, 2.0).is_empty());
+}
@@ -0,0 +1,16 @@
+use renderer::functions::vertex::get_changed_ranges;
+
+#[test]
+fn unchanged_data_has_no_ranges(){
+    assert!(get_changed_ranges(&[1, 2, 3], &[1, 2, 3]).is_empty());
+    assert!(get_changed_ranges::<u32>(&[1, 2, 3], &[]).is_empty());
+}
+#[test]
+fn changed_elements_are_coalesced(){
+    assert_eq!(get_changed_ranges(&[1, 2, 3, 4, 5], &[1, 9, 9, 4, 9]), vec!(1..3, 4..5));
+}
+#[test]
+fn appended_elements_are_uploaded(){
+    assert_eq!(get_changed_ranges(&[1, 2], &[1, 3, 4, 5]), vec!(1..4));
+    assert_eq!(get_changed_ranges(&[], &[7, 8]), vec!(0..2));
+}